@@ -96,6 +96,10 @@ impl Application {
         self.set_accels_for_action("win.open-circuit", &["<Control>o"]);
         self.set_accels_for_action("win.save-circuit", &["<Control>s"]);
         self.set_accels_for_action("win.save-circuit-as", &["<Control><Shift>s"]);
+
+        self.set_accels_for_action("win.halt-simulation", &["<Control>h"]);
+        self.set_accels_for_action("win.resume-simulation", &["<Control>r"]);
+        self.set_accels_for_action("win.cancel-simulation", &["<Control><Shift>c"]);
     }
 
     fn show_about_dialog(&self) {
@@ -0,0 +1,229 @@
+#[cfg(test)]
+pub mod mock;
+
+use std::fmt::Write;
+
+use anyhow::Result;
+
+use crate::vector::{self, Vector, VectorData};
+
+/// A simulator that circuits can be loaded into and commands run on.
+// The futures are only ever awaited on the main thread, so they don't need
+// to be `Send`.
+#[allow(async_fn_in_trait)]
+pub trait Backend {
+    /// Loads a circuit, given as its netlist lines.
+    async fn circuit(&self, circuit: Vec<String>) -> Result<()>;
+
+    async fn command(&self, command: &str) -> Result<()>;
+
+    async fn current_plot_name(&self) -> Result<String>;
+
+    async fn all_plot_names(&self) -> Result<Vec<String>>;
+
+    async fn all_vector_names(&self, plot_name: &str) -> Result<Vec<String>>;
+
+    /// Returns the name of the scale vector of the plot named `plot_name`,
+    /// or `None` if it has none or the simulator doesn't report it.
+    async fn scale_name(&self, plot_name: &str) -> Result<Option<String>>;
+
+    /// Returns the name of the analysis that produced the plot named
+    /// `plot_name` (e.g., `Transient Analysis`), or `None` if the simulator
+    /// doesn't report it.
+    async fn analysis_name(&self, plot_name: &str) -> Result<Option<String>>;
+
+    /// Returns the vector named `vector_name`, which may be qualified with
+    /// the plot name (e.g., `tran1.time`).
+    async fn vector_info(&self, vector_name: &str) -> Result<Vector>;
+}
+
+/// What a plot holds, in a form that can be shown to the user.
+#[derive(Debug)]
+pub enum PlotContent {
+    /// Vectors that are functions of a common scale vector.
+    Traces { scale: Vector, vectors: Vec<Vector> },
+    /// Vectors with a single value each (e.g., from an operating point).
+    Scalars(Vec<Vector>),
+}
+
+impl PlotContent {
+    /// Fetches every vector of the plot named `plot_name`.
+    pub async fn fetch(backend: &impl Backend, plot_name: &str) -> Result<Self> {
+        let vector_names = backend.all_vector_names(plot_name).await?;
+
+        let mut vectors = Vec::new();
+        for vector_name in vector_names {
+            let vector = backend
+                .vector_info(&format!("{}.{}", plot_name, vector_name))
+                .await?;
+            vectors.push(vector);
+        }
+
+        let scale_index = match backend.scale_name(plot_name).await? {
+            // Names are case-insensitive in ngspice.
+            Some(scale_name) => vectors
+                .iter()
+                .position(|vector| vector.name.eq_ignore_ascii_case(&scale_name)),
+            None => vector::find_scale(&vectors),
+        };
+
+        Ok(Self::from_vectors(vectors, scale_index))
+    }
+
+    /// Sorts out the vector at `scale_index` as the scale, if there is one.
+    pub fn from_vectors(mut vectors: Vec<Vector>, scale_index: Option<usize>) -> Self {
+        if let Some(scale_index) = scale_index.filter(|index| *index < vectors.len()) {
+            let scale = vectors.remove(scale_index);
+            Self::Traces { scale, vectors }
+        } else {
+            Self::Scalars(vectors)
+        }
+    }
+}
+
+/// Formats single-valued vectors, one `name: value` per line.
+pub fn format_scalars(vectors: &[Vector]) -> String {
+    let mut text = String::new();
+
+    for vector in vectors {
+        match &vector.data {
+            VectorData::Real(real) => {
+                debug_assert_eq!(real.len(), 1);
+
+                writeln!(text, "{}: {}", vector.name, real[0]).unwrap();
+            }
+            VectorData::Complex(complex) => {
+                debug_assert_eq!(complex.len(), 1);
+
+                writeln!(
+                    text,
+                    "{}: {} + {}j",
+                    vector.name, complex[0].re, complex[0].im
+                )
+                .unwrap();
+            }
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use gtk::glib;
+
+    use super::{mock::MockBackend, *};
+    use crate::vector::{Complex, VectorType};
+
+    fn real(name: &str, vtype: VectorType, data: &[f64]) -> Vector {
+        Vector {
+            name: name.to_string(),
+            vtype,
+            data: VectorData::Real(data.to_vec()),
+        }
+    }
+
+    #[test]
+    fn fetch_traces() {
+        let backend = MockBackend::new();
+        backend.add_plot(
+            "tran1",
+            vec![
+                real("v(out)", VectorType::Voltage, &[0.0, 0.5, 1.0]),
+                real("time", VectorType::Time, &[0.0, 1e-3, 2e-3]),
+            ],
+        );
+
+        let content = glib::MainContext::new()
+            .block_on(PlotContent::fetch(&backend, "tran1"))
+            .unwrap();
+
+        let PlotContent::Traces { scale, vectors } = content else {
+            panic!("expected traces, got {:?}", content);
+        };
+        assert_eq!(scale.name, "time");
+        assert_eq!(vectors.len(), 1);
+        assert_eq!(vectors[0].name, "v(out)");
+    }
+
+    #[test]
+    fn fetch_reported_scale() {
+        // A DC sweep of `v1`, whose scale is named and typed like any other
+        // voltage.
+        let backend = MockBackend::new();
+        backend.add_plot_with_scale(
+            "dc1",
+            Some("V1"),
+            vec![
+                real("v(out)", VectorType::Voltage, &[0.0, 0.5, 1.0]),
+                real("v1", VectorType::Voltage, &[0.0, 1.0, 2.0]),
+            ],
+        );
+
+        let content = glib::MainContext::new()
+            .block_on(PlotContent::fetch(&backend, "dc1"))
+            .unwrap();
+
+        let PlotContent::Traces { scale, vectors } = content else {
+            panic!("expected traces, got {:?}", content);
+        };
+        assert_eq!(scale.name, "v1");
+        assert_eq!(vectors.len(), 1);
+    }
+
+    #[test]
+    fn fetch_scalars() {
+        let backend = MockBackend::new();
+        backend.add_plot(
+            "op1",
+            vec![
+                real("v(out)", VectorType::Voltage, &[2.5]),
+                real("i(v1)", VectorType::Current, &[-1e-3]),
+            ],
+        );
+
+        let content = glib::MainContext::new()
+            .block_on(PlotContent::fetch(&backend, "op1"))
+            .unwrap();
+
+        let PlotContent::Scalars(vectors) = content else {
+            panic!("expected scalars, got {:?}", content);
+        };
+        assert_eq!(format_scalars(&vectors), "v(out): 2.5\ni(v1): -0.001\n");
+    }
+
+    #[test]
+    fn hierarchical_vector_names() {
+        let backend = MockBackend::new();
+        backend.add_plot(
+            "tran1",
+            vec![real("x1.out", VectorType::Voltage, &[0.0, 1.0])],
+        );
+
+        let main_context = glib::MainContext::new();
+        for vector_name in ["x1.out", "tran1.x1.out"] {
+            let vector = main_context
+                .block_on(backend.vector_info(vector_name))
+                .unwrap();
+            assert_eq!(vector.name, "x1.out");
+        }
+    }
+
+    #[test]
+    fn fetch_unknown_plot() {
+        let backend = MockBackend::new();
+
+        let ret = glib::MainContext::new().block_on(PlotContent::fetch(&backend, "tran1"));
+        assert!(ret.is_err());
+    }
+
+    #[test]
+    fn format_complex_scalars() {
+        let vectors = [Vector {
+            name: "v(out)".to_string(),
+            vtype: VectorType::Voltage,
+            data: VectorData::Complex(vec![Complex::new(1.0, -2.0)]),
+        }];
+        assert_eq!(format_scalars(&vectors), "v(out): 1 + -2j\n");
+    }
+}
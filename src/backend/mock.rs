@@ -0,0 +1,170 @@
+//! A scripted in-process backend, for testing code that talks to a simulator
+//! without the real ngspice library.
+
+use std::{cell::RefCell, collections::VecDeque};
+
+use anyhow::{bail, ensure, Context, Result};
+
+use super::Backend;
+use crate::vector::Vector;
+
+type CommandEffect = Box<dyn FnOnce(&MockBackend) -> Result<()>>;
+
+struct Plot {
+    name: String,
+    scale_name: Option<String>,
+    vectors: Vec<Vector>,
+}
+
+/// Backend that holds plots in memory and only accepts the commands it was
+/// scripted to expect, in order.
+///
+/// Like ngspice, it starts with an empty `const` plot as the current one.
+pub struct MockBackend {
+    plots: RefCell<Vec<Plot>>,
+    current_plot_name: RefCell<String>,
+    script: RefCell<VecDeque<(String, CommandEffect)>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self {
+            plots: RefCell::new(vec![Plot {
+                name: "const".to_string(),
+                scale_name: None,
+                vectors: Vec::new(),
+            }]),
+            current_plot_name: RefCell::new("const".to_string()),
+            script: RefCell::new(VecDeque::new()),
+        }
+    }
+
+    /// Adds a plot and makes it the current one, as running an analysis does.
+    /// Its scale is left for the caller to guess.
+    pub fn add_plot(&self, name: &str, vectors: Vec<Vector>) {
+        self.add_plot_with_scale(name, None, vectors);
+    }
+
+    /// Like [`Self::add_plot`], but reports the vector named `scale_name` as
+    /// the scale of the plot.
+    pub fn add_plot_with_scale(&self, name: &str, scale_name: Option<&str>, vectors: Vec<Vector>) {
+        self.plots.borrow_mut().push(Plot {
+            name: name.to_string(),
+            scale_name: scale_name.map(String::from),
+            vectors,
+        });
+        self.current_plot_name.replace(name.to_string());
+    }
+
+    /// Expects `command` to be the next command run, and runs `effect` when
+    /// it is.
+    pub fn expect_command(
+        &self,
+        command: &str,
+        effect: impl FnOnce(&Self) -> Result<()> + 'static,
+    ) {
+        self.script
+            .borrow_mut()
+            .push_back((command.to_string(), Box::new(effect)));
+    }
+
+    /// Returns whether every expected command has been run.
+    pub fn is_script_done(&self) -> bool {
+        self.script.borrow().is_empty()
+    }
+}
+
+impl Default for MockBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Backend for MockBackend {
+    async fn circuit(&self, _circuit: Vec<String>) -> Result<()> {
+        Ok(())
+    }
+
+    async fn command(&self, command: &str) -> Result<()> {
+        let (expected, effect) = self
+            .script
+            .borrow_mut()
+            .pop_front()
+            .with_context(|| format!("Unexpected command `{}`", command))?;
+        ensure!(
+            expected == command,
+            "Expected command `{}`, got `{}`",
+            expected,
+            command
+        );
+        effect(self)
+    }
+
+    async fn current_plot_name(&self) -> Result<String> {
+        Ok(self.current_plot_name.borrow().clone())
+    }
+
+    async fn all_plot_names(&self) -> Result<Vec<String>> {
+        Ok(self
+            .plots
+            .borrow()
+            .iter()
+            .rev()
+            .map(|plot| plot.name.clone())
+            .collect())
+    }
+
+    async fn all_vector_names(&self, plot_name: &str) -> Result<Vec<String>> {
+        let plots = self.plots.borrow();
+        let Some(plot) = plots.iter().find(|plot| plot.name == plot_name) else {
+            bail!("Plot `{}` not found", plot_name);
+        };
+        Ok(plot
+            .vectors
+            .iter()
+            .map(|vector| vector.name.clone())
+            .collect())
+    }
+
+    async fn scale_name(&self, plot_name: &str) -> Result<Option<String>> {
+        let plots = self.plots.borrow();
+        let Some(plot) = plots.iter().find(|plot| plot.name == plot_name) else {
+            bail!("Plot `{}` not found", plot_name);
+        };
+        Ok(plot.scale_name.clone())
+    }
+
+    async fn analysis_name(&self, plot_name: &str) -> Result<Option<String>> {
+        ensure!(
+            self.plots
+                .borrow()
+                .iter()
+                .any(|plot| plot.name == plot_name),
+            "Plot `{}` not found",
+            plot_name
+        );
+        Ok(None)
+    }
+
+    async fn vector_info(&self, vector_name: &str) -> Result<Vector> {
+        let plots = self.plots.borrow();
+        // Vector names may contain dots too (e.g., `x1.out`), so only a
+        // known plot name qualifies them.
+        let current_plot_name = self.current_plot_name.borrow().clone();
+        let (plot_name, vector_name) = vector_name
+            .split_once('.')
+            .filter(|(plot_name, _)| plots.iter().any(|plot| plot.name == *plot_name))
+            .unwrap_or((&current_plot_name, vector_name));
+
+        plots
+            .iter()
+            .find(|plot| plot.name == plot_name)
+            .and_then(|plot| {
+                plot.vectors
+                    .iter()
+                    .find(|vector| vector.name == vector_name)
+            })
+            .cloned()
+            .with_context(|| format!("Vector `{}.{}` not found", plot_name, vector_name))
+    }
+}
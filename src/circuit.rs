@@ -10,6 +10,13 @@ use gtk::{
 };
 use gtk_source::{prelude::*, subclass::prelude::*};
 
+/// The future of a load or save, along with the stream of its progress in
+/// bytes.
+type FileIo = (
+    Pin<Box<dyn Future<Output = Result<(), glib::Error>>>>,
+    Pin<Box<dyn Stream<Item = (i64, i64)>>>,
+);
+
 mod imp {
     use std::{cell::Cell, marker::PhantomData};
 
@@ -245,17 +252,10 @@ impl Circuit {
             }
         };
 
-        ret.trim().to_lowercase().to_string()
+        ret.trim().to_lowercase()
     }
 
-    #[allow(clippy::type_complexity)]
-    async fn handle_file_io(
-        &self,
-        (io_fut, mut progress_stream): (
-            Pin<Box<dyn Future<Output = Result<(), glib::Error>>>>,
-            Pin<Box<dyn Stream<Item = (i64, i64)>>>,
-        ),
-    ) -> Result<()> {
+    async fn handle_file_io(&self, (io_fut, mut progress_stream): FileIo) -> Result<()> {
         let progress_fut = async {
             while let Some((current_n_bytes, total_n_bytes)) = progress_stream.next().await {
                 let progress = if total_n_bytes == 0 || current_n_bytes > total_n_bytes {
@@ -0,0 +1,299 @@
+//! Formats the shown plot can be exported to, and serialization of vectors
+//! into formats that spreadsheets and scripts understand.
+
+use std::fmt::Write;
+
+use crate::vector::{Complex, Vector, VectorData};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Csv,
+    Tsv,
+    Json,
+}
+
+impl DataFormat {
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Json => "json",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Svg,
+    Pdf,
+}
+
+impl ImageFormat {
+    pub fn from_nick(nick: &str) -> Option<Self> {
+        match nick {
+            "png" => Some(Self::Png),
+            "svg" => Some(Self::Svg),
+            "pdf" => Some(Self::Pdf),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Svg => "svg",
+            Self::Pdf => "pdf",
+        }
+    }
+}
+
+/// Serializes `vectors` alongside the `scale` they are functions of.
+pub fn export(format: DataFormat, scale: &Vector, vectors: &[&Vector]) -> String {
+    match format {
+        DataFormat::Csv => to_delimited(',', scale, vectors),
+        DataFormat::Tsv => to_delimited('\t', scale, vectors),
+        DataFormat::Json => to_json(scale, vectors),
+    }
+}
+
+/// Writes a header row with the names and units, followed by a row per
+/// point. Complex vectors are split into a real and an imaginary column.
+fn to_delimited(separator: char, scale: &Vector, vectors: &[&Vector]) -> String {
+    let columns = [scale]
+        .into_iter()
+        .chain(vectors.iter().copied())
+        .collect::<Vec<_>>();
+
+    let mut text = String::new();
+
+    let header = columns
+        .iter()
+        .flat_map(|vector| column_names(vector))
+        .map(|name| quote(&name, separator))
+        .collect::<Vec<_>>();
+    writeln!(text, "{}", header.join(&separator.to_string())).unwrap();
+
+    let n_rows = columns
+        .iter()
+        .map(|vector| data_len(&vector.data))
+        .max()
+        .unwrap_or(0);
+    for row in 0..n_rows {
+        let cells = columns
+            .iter()
+            .flat_map(|vector| match &vector.data {
+                VectorData::Real(real) => {
+                    vec![real.get(row).map(ToString::to_string).unwrap_or_default()]
+                }
+                VectorData::Complex(complex) => match complex.get(row) {
+                    Some(value) => vec![value.re.to_string(), value.im.to_string()],
+                    None => vec![String::new(), String::new()],
+                },
+            })
+            .collect::<Vec<_>>();
+        writeln!(text, "{}", cells.join(&separator.to_string())).unwrap();
+    }
+
+    text
+}
+
+fn column_names(vector: &Vector) -> Vec<String> {
+    let unit = vector.vtype.unit();
+    let with_unit = |name: String| {
+        if unit.is_empty() {
+            name
+        } else {
+            format!("{} ({})", name, unit)
+        }
+    };
+
+    if vector.data.is_complex() {
+        vec![
+            with_unit(format!("real({})", vector.name)),
+            with_unit(format!("imag({})", vector.name)),
+        ]
+    } else {
+        vec![with_unit(vector.name.clone())]
+    }
+}
+
+/// Quotes a field if it contains characters with a special meaning.
+fn quote(field: &str, separator: char) -> String {
+    if field.contains([separator, '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Writes an object with the scale and the other vectors, each with their
+/// name, unit and values. Complex values are split into `real` and `imag`
+/// arrays.
+fn to_json(scale: &Vector, vectors: &[&Vector]) -> String {
+    let mut text = String::new();
+
+    text.push_str("{\n  \"scale\": ");
+    write_json_vector(&mut text, scale);
+    text.push_str(",\n  \"vectors\": [");
+    for (index, vector) in vectors.iter().enumerate() {
+        if index > 0 {
+            text.push(',');
+        }
+        text.push_str("\n    ");
+        write_json_vector(&mut text, vector);
+    }
+    if !vectors.is_empty() {
+        text.push_str("\n  ");
+    }
+    text.push_str("]\n}\n");
+
+    text
+}
+
+fn write_json_vector(text: &mut String, vector: &Vector) {
+    write!(
+        text,
+        "{{\"name\": {}, \"unit\": {}, ",
+        json_string(&vector.name),
+        json_string(vector.vtype.unit())
+    )
+    .unwrap();
+
+    match &vector.data {
+        VectorData::Real(real) => {
+            write!(text, "\"values\": {}", json_numbers(real.iter().copied())).unwrap();
+        }
+        VectorData::Complex(complex) => {
+            write!(
+                text,
+                "\"real\": {}, \"imag\": {}",
+                json_numbers(complex.iter().map(|c: &Complex| c.re)),
+                json_numbers(complex.iter().map(|c: &Complex| c.im))
+            )
+            .unwrap();
+        }
+    }
+
+    text.push('}');
+}
+
+fn json_string(string: &str) -> String {
+    let mut ret = String::from('"');
+    for c in string.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            '\r' => ret.push_str("\\r"),
+            '\t' => ret.push_str("\\t"),
+            c if c.is_control() => write!(ret, "\\u{:04x}", c as u32).unwrap(),
+            c => ret.push(c),
+        }
+    }
+    ret.push('"');
+    ret
+}
+
+/// Formats numbers as a JSON array, with non-finite values as `null`, as
+/// JSON has no representation for them.
+fn json_numbers(values: impl Iterator<Item = f64>) -> String {
+    let items = values
+        .map(|value| {
+            if value.is_finite() {
+                value.to_string()
+            } else {
+                "null".to_string()
+            }
+        })
+        .collect::<Vec<_>>();
+    format!("[{}]", items.join(", "))
+}
+
+fn data_len(data: &VectorData) -> usize {
+    match data {
+        VectorData::Real(real) => real.len(),
+        VectorData::Complex(complex) => complex.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::VectorType;
+
+    fn time() -> Vector {
+        Vector {
+            name: "time".to_string(),
+            vtype: VectorType::Time,
+            data: VectorData::Real(vec![0.0, 0.5]),
+        }
+    }
+
+    #[test]
+    fn csv() {
+        let vector = Vector {
+            name: "v(a,b)".to_string(),
+            vtype: VectorType::Voltage,
+            data: VectorData::Real(vec![1.0, f64::NAN]),
+        };
+        assert_eq!(
+            export(DataFormat::Csv, &time(), &[&vector]),
+            "time (s),\"v(a,b) (V)\"\n0,1\n0.5,NaN\n"
+        );
+    }
+
+    #[test]
+    fn tsv_complex_uneven() {
+        let vector = Vector {
+            name: "v(out)".to_string(),
+            vtype: VectorType::Voltage,
+            data: VectorData::Complex(vec![Complex::new(1.0, -2.0)]),
+        };
+        assert_eq!(
+            export(DataFormat::Tsv, &time(), &[&vector]),
+            "time (s)\treal(v(out)) (V)\timag(v(out)) (V)\n0\t1\t-2\n0.5\t\t\n"
+        );
+    }
+
+    #[test]
+    fn json() {
+        let vectors = [
+            Vector {
+                name: "v(\"out\")".to_string(),
+                vtype: VectorType::NoType,
+                data: VectorData::Real(vec![1.0, f64::INFINITY]),
+            },
+            Vector {
+                name: "i(v1)".to_string(),
+                vtype: VectorType::Current,
+                data: VectorData::Complex(vec![Complex::new(1.0, 2.0)]),
+            },
+        ];
+        assert_eq!(
+            export(DataFormat::Json, &time(), &[&vectors[0], &vectors[1]]),
+            "{\n  \"scale\": {\"name\": \"time\", \"unit\": \"s\", \"values\": [0, 0.5]},\n  \
+             \"vectors\": [\n    \
+             {\"name\": \"v(\\\"out\\\")\", \"unit\": \"\", \"values\": [1, null]},\n    \
+             {\"name\": \"i(v1)\", \"unit\": \"A\", \"real\": [1], \"imag\": [2]}\n  ]\n}\n"
+        );
+    }
+
+    #[test]
+    fn json_no_vectors() {
+        assert_eq!(
+            export(DataFormat::Json, &time(), &[]),
+            "{\n  \"scale\": {\"name\": \"time\", \"unit\": \"s\", \"values\": [0, 0.5]},\n  \
+             \"vectors\": []\n}\n"
+        );
+    }
+}
@@ -18,24 +18,29 @@
     clippy::unused_self,
     clippy::dbg_macro,
     clippy::todo,
-    clippy::map_unwrap_or,
     clippy::or_fun_call,
     clippy::print_stdout
 )]
 
 mod application;
+mod backend;
 mod circuit;
 mod color_widget;
 mod colors;
 mod config;
+mod export;
 mod i18n;
 mod ngspice;
 mod output_view;
 mod plot;
 mod plot_view;
 mod plot_view_filter_row;
+mod plot_view_group_row;
 mod plots;
 mod plots_dropdown;
+mod rawfile;
+mod si;
+mod vector;
 mod window;
 
 use gettextrs::{gettext, LocaleCategory};
@@ -1,20 +1,71 @@
-use std::{fmt, sync::Arc};
+mod process;
+mod sys;
 
-use anyhow::Result;
-use elektron_ngspice::VectorInfo;
-use futures_channel::mpsc;
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    ffi::{CStr, CString},
+    fmt,
+    os::raw::{c_char, c_int, c_void},
+    rc::Rc,
+    slice,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+use anyhow::{bail, ensure, Context, Result};
+use futures_channel::{mpsc, oneshot};
 use futures_util::StreamExt;
 use gtk::{gio, glib};
 
+use self::process::Process;
+use crate::{
+    backend::Backend,
+    vector::{Complex, Vector, VectorData, VectorType},
+};
+
+/// Vectors of the plot that a simulation is about to fill in.
+#[derive(Debug)]
+pub struct InitData {
+    pub plot_name: String,
+    /// The analysis that produced the plot, e.g., `Transient Analysis`.
+    pub analysis_name: String,
+    /// Vectors in the same order as the values sent on each data point.
+    pub vectors: Vec<InitVector>,
+    /// Position of the scale vector in `vectors`, if the plot has one.
+    pub scale_index: Option<usize>,
+}
+
+#[derive(Debug)]
+pub struct InitVector {
+    pub name: String,
+    pub is_real: bool,
+}
+
+#[derive(Clone)]
 pub struct Callbacks {
     send_char_tx: mpsc::UnboundedSender<String>,
+    send_progress_tx: mpsc::UnboundedSender<f64>,
     controlled_exit_tx: mpsc::UnboundedSender<(i32, bool, bool)>,
+    send_init_data_tx: mpsc::UnboundedSender<InitData>,
+    send_data_tx: mpsc::UnboundedSender<Vec<Complex>>,
+    background_thread_running_tx: mpsc::UnboundedSender<bool>,
+    /// Names of the scales of the plots simulations were run for, by plot
+    /// name, as ngspice reports them only when a simulation starts.
+    scale_names: Arc<Mutex<HashMap<String, String>>>,
+    /// Names of the analyses of the same plots, by plot name.
+    analysis_names: Arc<Mutex<HashMap<String, String>>>,
+    /// Notified the next time the background thread stops.
+    background_thread_stopped_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
 }
 
 impl Callbacks {
     pub fn new(
         send_char: impl Fn(String) + 'static,
+        send_progress: impl Fn(f64) + 'static,
         controlled_exit: impl Fn(i32, bool, bool) + 'static,
+        send_init_data: impl Fn(InitData) + 'static,
+        send_data: impl Fn(Vec<Complex>) + 'static,
+        background_thread_running: impl Fn(bool) + 'static,
     ) -> Self {
         let (send_char_tx, mut send_char_rx) = mpsc::unbounded();
         glib::spawn_future_local(async move {
@@ -23,6 +74,13 @@ impl Callbacks {
             }
         });
 
+        let (send_progress_tx, mut send_progress_rx) = mpsc::unbounded();
+        glib::spawn_future_local(async move {
+            while let Some(progress) = send_progress_rx.next().await {
+                send_progress(progress);
+            }
+        });
+
         let (controlled_exit_tx, mut controlled_exit_rx) = mpsc::unbounded();
         glib::spawn_future_local(async move {
             while let Some((status, unload, quit)) = controlled_exit_rx.next().await {
@@ -30,109 +88,575 @@ impl Callbacks {
             }
         });
 
+        let (send_init_data_tx, mut send_init_data_rx) = mpsc::unbounded();
+        glib::spawn_future_local(async move {
+            while let Some(init_data) = send_init_data_rx.next().await {
+                send_init_data(init_data);
+            }
+        });
+
+        let (send_data_tx, mut send_data_rx) = mpsc::unbounded();
+        glib::spawn_future_local(async move {
+            while let Some(values) = send_data_rx.next().await {
+                send_data(values);
+            }
+        });
+
+        let (background_thread_running_tx, mut background_thread_running_rx) = mpsc::unbounded();
+        glib::spawn_future_local(async move {
+            while let Some(is_running) = background_thread_running_rx.next().await {
+                background_thread_running(is_running);
+            }
+        });
+
         Self {
             send_char_tx,
+            send_progress_tx,
             controlled_exit_tx,
+            send_init_data_tx,
+            send_data_tx,
+            background_thread_running_tx,
+            scale_names: Arc::default(),
+            analysis_names: Arc::default(),
+            background_thread_stopped_tx: Arc::default(),
         }
     }
 }
 
-impl elektron_ngspice::Callbacks for Callbacks {
-    fn send_char(&mut self, string: &str) {
-        self.send_char_tx
-            .unbounded_send(string.to_string())
-            .unwrap();
+unsafe extern "C" fn send_char(string: *mut c_char, _id: c_int, user_data: *mut c_void) -> c_int {
+    let callbacks = &*(user_data as *const Callbacks);
+    if let Ok(string) = CStr::from_ptr(string).to_str() {
+        let _ = callbacks.send_char_tx.unbounded_send(string.to_string());
+    }
+    0
+}
+
+unsafe extern "C" fn send_stat(string: *mut c_char, _id: c_int, user_data: *mut c_void) -> c_int {
+    let callbacks = &*(user_data as *const Callbacks);
+    if let Some(progress) = CStr::from_ptr(string)
+        .to_str()
+        .ok()
+        .and_then(parse_progress)
+    {
+        let _ = callbacks.send_progress_tx.unbounded_send(progress);
+    }
+    0
+}
+
+/// Parses the progress out of ngspice's status messages, which look like
+/// `tran: 42.5%` while an analysis runs and `--ready--` once it is done.
+fn parse_progress(stat: &str) -> Option<f64> {
+    if stat.trim() == "--ready--" {
+        return Some(1.0);
+    }
+
+    let (_, percent) = stat.rsplit_once(':')?;
+    let percent = percent.trim().strip_suffix('%')?.parse::<f64>().ok()?;
+    Some((percent / 100.0).clamp(0.0, 1.0))
+}
+
+unsafe extern "C" fn controlled_exit(
+    status: c_int,
+    unload: bool,
+    quit: bool,
+    _id: c_int,
+    user_data: *mut c_void,
+) -> c_int {
+    let callbacks = &*(user_data as *const Callbacks);
+    let _ = callbacks
+        .controlled_exit_tx
+        .unbounded_send((status, unload, quit));
+    0
+}
+
+unsafe extern "C" fn send_init_data(
+    vec_info_all: *mut sys::VecInfoAll,
+    _id: c_int,
+    user_data: *mut c_void,
+) -> c_int {
+    let callbacks = &*(user_data as *const Callbacks);
+    let vec_info_all = &*vec_info_all;
+
+    let vec_infos = slice::from_raw_parts(vec_info_all.vecs, vec_info_all.veccount as usize)
+        .iter()
+        .map(|vec_info| &**vec_info)
+        .collect::<Vec<_>>();
+
+    // Every vector points to its scale, including the scale itself.
+    let scale_index = vec_infos
+        .first()
+        .map(|first| first.pdvecscale)
+        .filter(|scale| !scale.is_null())
+        .and_then(|scale| {
+            vec_infos
+                .iter()
+                .position(|vec_info| vec_info.pdvec == scale)
+        });
+
+    let init_data = InitData {
+        plot_name: CStr::from_ptr(vec_info_all.name)
+            .to_string_lossy()
+            .to_string(),
+        analysis_name: CStr::from_ptr(vec_info_all.type_)
+            .to_string_lossy()
+            .to_string(),
+        vectors: vec_infos
+            .iter()
+            .map(|vec_info| InitVector {
+                name: CStr::from_ptr(vec_info.vecname)
+                    .to_string_lossy()
+                    .to_string(),
+                is_real: vec_info.is_real,
+            })
+            .collect(),
+        scale_index,
+    };
+    if let Some(scale_index) = scale_index {
+        lock(&callbacks.scale_names).insert(
+            init_data.plot_name.clone(),
+            init_data.vectors[scale_index].name.clone(),
+        );
     }
+    lock(&callbacks.analysis_names)
+        .insert(init_data.plot_name.clone(), init_data.analysis_name.clone());
+    let _ = callbacks.send_init_data_tx.unbounded_send(init_data);
+    0
+}
+
+unsafe extern "C" fn send_data(
+    vec_values_all: *mut sys::VecValuesAll,
+    _n_vectors: c_int,
+    _id: c_int,
+    user_data: *mut c_void,
+) -> c_int {
+    let callbacks = &*(user_data as *const Callbacks);
+    let vec_values_all = &*vec_values_all;
 
-    fn controlled_exit(&mut self, status: i32, unload: bool, quit: bool) {
-        self.controlled_exit_tx
-            .unbounded_send((status, unload, quit))
-            .unwrap();
+    let values = slice::from_raw_parts(vec_values_all.vecsa, vec_values_all.veccount as usize)
+        .iter()
+        .map(|vec_values| Complex::new((**vec_values).creal, (**vec_values).cimag))
+        .collect();
+    let _ = callbacks.send_data_tx.unbounded_send(values);
+    0
+}
+
+/// Reports the background thread starting, with `noruns` false, and stopping.
+unsafe extern "C" fn background_thread_running(
+    noruns: bool,
+    _id: c_int,
+    user_data: *mut c_void,
+) -> c_int {
+    let callbacks = &*(user_data as *const Callbacks);
+    if noruns {
+        if let Some(stopped_tx) = lock(&callbacks.background_thread_stopped_tx).take() {
+            let _ = stopped_tx.send(());
+        }
     }
+    let _ = callbacks
+        .background_thread_running_tx
+        .unbounded_send(!noruns);
+    0
+}
+
+/// Locks `mutex` even if a panic poisoned it, as the callbacks above must not
+/// unwind into ngspice. The maps it guards stay consistent either way.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+struct Inner {
+    library: sys::Library,
+    // Passed to ngspice as user data, so it must outlive the library.
+    _callbacks: Box<Callbacks>,
+    // Dropped last, once the library is unloaded, which notifies `unload`.
+    released_tx: Mutex<Option<oneshot::Sender<()>>>,
+}
+
+impl Inner {
+    /// Loads the library and initializes ngspice with the given callbacks.
+    fn init(callbacks: Callbacks) -> Result<Self> {
+        let library = sys::Library::load()?;
+        let callbacks = Box::new(callbacks);
+
+        let user_data = &*callbacks as *const Callbacks;
+        let ret = unsafe {
+            (library.ngSpice_Init)(
+                Some(send_char),
+                Some(send_stat),
+                Some(controlled_exit),
+                Some(send_data),
+                Some(send_init_data),
+                Some(background_thread_running),
+                user_data as *mut c_void,
+            )
+        };
+        ensure!(ret == 0, "Failed to initialize with code {}", ret);
+
+        Ok(Self {
+            library,
+            _callbacks: callbacks,
+            released_tx: Mutex::default(),
+        })
+    }
+
+    fn command(&self, command: &str) -> Result<()> {
+        let command = CString::new(command)?;
+        let ret = unsafe { (self.library.ngSpice_Command)(command.as_ptr() as *mut c_char) };
+        ensure!(ret == 0, "Command failed with code {}", ret);
+        Ok(())
+    }
+
+    fn circuit(&self, circuit: Vec<String>) -> Result<()> {
+        let lines = circuit
+            .into_iter()
+            .map(CString::new)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // ngspice expects the lines to be terminated by a null pointer.
+        let mut ptrs = lines
+            .iter()
+            .map(|line| line.as_ptr() as *mut c_char)
+            .chain([std::ptr::null_mut()])
+            .collect::<Vec<_>>();
+
+        let ret = unsafe { (self.library.ngSpice_Circ)(ptrs.as_mut_ptr()) };
+        ensure!(ret == 0, "Failed to load circuit with code {}", ret);
+        Ok(())
+    }
+
+    fn current_plot(&self) -> Result<String> {
+        let current_plot = unsafe { CStr::from_ptr((self.library.ngSpice_CurPlot)()) };
+        Ok(current_plot.to_str()?.to_string())
+    }
+
+    fn all_plots(&self) -> Result<Vec<String>> {
+        unsafe { strings_from_ptr((self.library.ngSpice_AllPlots)()) }
+    }
+
+    fn all_vecs(&self, plot_name: &str) -> Result<Vec<String>> {
+        let plot_name = CString::new(plot_name)?;
+        unsafe {
+            strings_from_ptr((self.library.ngSpice_AllVecs)(
+                plot_name.as_ptr() as *mut c_char
+            ))
+        }
+    }
+
+    fn vector_info(&self, vector_name: &str) -> Result<Vector> {
+        let c_vector_name = CString::new(vector_name)?;
+        let vector_info =
+            unsafe { (self.library.ngGet_Vec_Info)(c_vector_name.as_ptr() as *mut c_char) };
+
+        if vector_info.is_null() {
+            bail!("Vector `{}` not found", vector_name);
+        }
+
+        unsafe {
+            let vector_info = &*vector_info;
+            let len = vector_info.v_length as usize;
+            let data = if !vector_info.v_realdata.is_null() {
+                VectorData::Real(slice::from_raw_parts(vector_info.v_realdata, len).to_vec())
+            } else if !vector_info.v_compdata.is_null() {
+                VectorData::Complex(
+                    slice::from_raw_parts(vector_info.v_compdata, len)
+                        .iter()
+                        .map(|c| Complex::new(c.cx_real, c.cx_imag))
+                        .collect(),
+                )
+            } else {
+                bail!("Vector `{}` has no data", vector_name);
+            };
+
+            Ok(Vector {
+                name: CStr::from_ptr(vector_info.v_name).to_str()?.to_string(),
+                vtype: VectorType::from_raw(vector_info.v_type),
+                data,
+            })
+        }
+    }
+}
+
+/// Collects a null-terminated array of C strings.
+unsafe fn strings_from_ptr(ptrs: *mut *mut c_char) -> Result<Vec<String>> {
+    let mut strings = Vec::new();
+
+    if ptrs.is_null() {
+        return Ok(strings);
+    }
+
+    let mut i = 0;
+    while !(*ptrs.add(i)).is_null() {
+        strings.push(CStr::from_ptr(*ptrs.add(i)).to_str()?.to_string());
+        i += 1;
+    }
+
+    Ok(strings)
+}
+
+/// How ngspice is run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Loaded into our process as a shared library. This is faster and
+    /// allows streaming data while a simulation runs.
+    #[default]
+    SharedLibrary,
+    /// Run as a separate process, which survives simulator crashes.
+    Subprocess,
+}
+
+#[derive(Clone)]
+enum Instance {
+    SharedLibrary(Arc<Inner>),
+    Subprocess(Rc<Process>),
 }
 
 pub struct NgSpice {
-    inner: Arc<elektron_ngspice::NgSpice<'static, Callbacks>>,
+    callbacks: Callbacks,
+    mode: Cell<Mode>,
+    instance: RefCell<Option<Instance>>,
 }
 
 impl fmt::Debug for NgSpice {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        f.debug_struct("NgSpice").finish()
+        f.debug_struct("NgSpice")
+            .field("mode", &self.mode.get())
+            .finish()
     }
 }
 
 impl NgSpice {
-    pub fn new(callbacks: Callbacks) -> Result<Self> {
-        static mut CALLBACKS_INSTANCE: Option<Callbacks> = None;
+    /// Creates an uninitialized instance. [`Self::init`] must be called
+    /// before it can be used.
+    pub fn new(callbacks: Callbacks) -> Self {
+        Self {
+            callbacks,
+            mode: Cell::new(Mode::default()),
+            instance: RefCell::new(None),
+        }
+    }
 
-        let inner = unsafe {
-            assert!(
-                CALLBACKS_INSTANCE.is_none(),
-                "Multiple Ngspice instance is not supported"
-            );
+    pub fn mode(&self) -> Mode {
+        self.mode.get()
+    }
+
+    /// Tears down the current instance, if any, and initializes a fresh one
+    /// running in `mode`.
+    ///
+    /// This must be called again after ngspice reports a controlled exit, as
+    /// it can't be used anymore after that.
+    pub async fn init(&self, mode: Mode) -> Result<()> {
+        self.mode.set(mode);
 
-            CALLBACKS_INSTANCE.replace(callbacks);
+        // The old instance is torn down first, so that the library is
+        // unloaded and reloaded with a clean state.
+        if let Some(Instance::SharedLibrary(inner)) = self.instance.take() {
+            self.unload(inner).await;
+        }
+        // Plot names are reused by the new instance.
+        lock(&self.callbacks.scale_names).clear();
+        lock(&self.callbacks.analysis_names).clear();
 
-            elektron_ngspice::NgSpice::new(CALLBACKS_INSTANCE.as_mut().unwrap())?
+        let instance = match mode {
+            Mode::SharedLibrary => {
+                Instance::SharedLibrary(Arc::new(Inner::init(self.callbacks.clone())?))
+            }
+            Mode::Subprocess => Instance::Subprocess(Rc::new(Process::spawn(&self.callbacks)?)),
         };
+        self.instance.replace(Some(instance));
 
-        Ok(Self { inner })
+        Ok(())
     }
 
-    pub async fn circuit(
-        &self,
-        circuit: impl IntoIterator<Item = impl Into<String>>,
-    ) -> Result<()> {
-        let circuit = circuit.into_iter().map(|s| s.into()).collect::<Vec<_>>();
-        self.unblock(|inner| inner.circuit(circuit)).await?;
-        Ok(())
+    /// Starts `command` in the background and returns without waiting for
+    /// it to finish, which is reported to the `background_thread_running`
+    /// callback.
+    pub async fn background_command(&self, command: &str) -> Result<()> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => {
+                let command = format!("bg_{}", command.trim());
+                unblock(inner, move |inner| inner.command(&command)).await
+            }
+            Instance::Subprocess(process) => process.background_command(command),
+        }
     }
 
-    pub async fn current_plot_name(&self) -> Result<String> {
-        let current_plot_name = self.unblock(|inner| inner.current_plot()).await?;
-        Ok(current_plot_name)
+    /// Halts the command running in the background, which can later be
+    /// continued by running `resume` in the background.
+    pub async fn halt(&self) -> Result<()> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => {
+                unblock(inner, |inner| inner.command("bg_halt")).await
+            }
+            Instance::Subprocess(process) => process.halt(),
+        }
     }
 
-    pub async fn all_plot_names(&self) -> Result<Vec<String>> {
-        let all_plot_names = self.unblock(|inner| inner.all_plots()).await?;
-        Ok(all_plot_names)
+    /// Stops the background thread of `inner`, and waits for the tasks still
+    /// using it to finish (e.g., the one running the `quit` that made ngspice
+    /// exit), so that the library is unloaded once this returns.
+    async fn unload(&self, inner: Arc<Inner>) {
+        // Registered before checking, so that the thread can't stop unnoticed
+        // in between.
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        lock(&self.callbacks.background_thread_stopped_tx).replace(stopped_tx);
+        if unsafe { (inner.library.ngSpice_running)() } {
+            match unblock(inner.clone(), |inner| inner.command("bg_halt")).await {
+                Ok(()) => {
+                    let _ = stopped_rx.await;
+                }
+                Err(err) => tracing::warn!("Failed to halt the background thread: {:?}", err),
+            }
+        }
+        lock(&self.callbacks.background_thread_stopped_tx).take();
+
+        // Whichever task drops the last reference also drops the sender.
+        let (released_tx, released_rx) = oneshot::channel();
+        lock(&inner.released_tx).replace(released_tx);
+        drop(inner);
+        let _ = released_rx.await;
     }
 
-    pub async fn all_vector_names(&self, plot_name: impl Into<String>) -> Result<Vec<String>> {
-        let plot_name = plot_name.into();
-        let all_vector_names = self
-            .unblock(move |inner| inner.all_vecs(&plot_name))
-            .await?;
-        Ok(all_vector_names)
+    fn instance(&self) -> Result<Instance> {
+        self.instance
+            .borrow()
+            .clone()
+            .context("Ngspice is not initialized")
     }
+}
 
-    pub async fn vector_info(&self, vector_name: impl Into<String>) -> Result<VectorInfo<'_>> {
-        let vec_name = vector_name.into();
-        let vector_info = self
-            .unblock(move |inner| inner.vector_info(&vec_name))
-            .await?;
-        Ok(vector_info)
+impl Backend for NgSpice {
+    async fn circuit(&self, circuit: Vec<String>) -> Result<()> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => unblock(inner, |inner| inner.circuit(circuit)).await,
+            Instance::Subprocess(process) => process.circuit(circuit).await,
+        }
     }
 
-    pub async fn command(&self, command: impl Into<String>) -> Result<()> {
-        let command = command.into();
-        self.unblock(move |inner| inner.command(&command)).await?;
-        Ok(())
+    async fn command(&self, command: &str) -> Result<()> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => {
+                let command = command.to_string();
+                unblock(inner, move |inner| inner.command(&command)).await
+            }
+            Instance::Subprocess(process) => process.command(command).await,
+        }
     }
 
-    /// Spawns a task on the thread pool and returns a future that resolves to
-    /// the return value of the task.
-    #[must_use]
-    async fn unblock<R>(
-        &self,
-        func: impl FnOnce(&elektron_ngspice::NgSpice<'static, Callbacks>) -> R + Send + 'static,
-    ) -> R
-    where
-        R: Send + 'static,
-    {
-        let inner = self.inner.clone();
-        gio::spawn_blocking(move || func(&inner))
-            .await
-            .expect("Failed to spawn blocking task")
+    async fn current_plot_name(&self) -> Result<String> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => unblock(inner, |inner| inner.current_plot()).await,
+            Instance::Subprocess(process) => process.current_plot_name().await,
+        }
+    }
+
+    async fn all_plot_names(&self) -> Result<Vec<String>> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => unblock(inner, |inner| inner.all_plots()).await,
+            Instance::Subprocess(process) => process.all_plot_names().await,
+        }
+    }
+
+    async fn all_vector_names(&self, plot_name: &str) -> Result<Vec<String>> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => {
+                let plot_name = plot_name.to_string();
+                unblock(inner, move |inner| inner.all_vecs(&plot_name)).await
+            }
+            Instance::Subprocess(process) => process.all_vector_names(plot_name).await,
+        }
+    }
+
+    async fn scale_name(&self, plot_name: &str) -> Result<Option<String>> {
+        match self.instance()? {
+            Instance::SharedLibrary(_) => {
+                Ok(lock(&self.callbacks.scale_names).get(plot_name).cloned())
+            }
+            Instance::Subprocess(process) => process.scale_name(plot_name).await,
+        }
+    }
+
+    async fn analysis_name(&self, plot_name: &str) -> Result<Option<String>> {
+        match self.instance()? {
+            Instance::SharedLibrary(_) => {
+                Ok(lock(&self.callbacks.analysis_names).get(plot_name).cloned())
+            }
+            Instance::Subprocess(process) => process.analysis_name(plot_name).await,
+        }
+    }
+
+    async fn vector_info(&self, vector_name: &str) -> Result<Vector> {
+        match self.instance()? {
+            Instance::SharedLibrary(inner) => {
+                let vector_name = vector_name.to_string();
+                unblock(inner, move |inner| inner.vector_info(&vector_name)).await
+            }
+            Instance::Subprocess(process) => process.vector_info(vector_name).await,
+        }
+    }
+}
+
+/// Spawns a task on the thread pool and returns a future that resolves to
+/// the return value of the task.
+async fn unblock<R>(inner: Arc<Inner>, func: impl FnOnce(&Inner) -> R + Send + 'static) -> R
+where
+    R: Send + 'static,
+{
+    gio::spawn_blocking(move || func(&inner))
+        .await
+        .expect("Failed to spawn blocking task")
+}
+
+impl VectorType {
+    /// Converts ngspice's `enum simulation_types`.
+    fn from_raw(v_type: c_int) -> Self {
+        match v_type {
+            1 => Self::Time,
+            2 => Self::Frequency,
+            3 => Self::Voltage,
+            4 => Self::Current,
+            5 => Self::VoltageDensity,
+            6 => Self::CurrentDensity,
+            7 => Self::SqrVoltageDensity,
+            8 => Self::SqrCurrentDensity,
+            9 => Self::SqrVoltage,
+            10 => Self::SqrCurrent,
+            11 => Self::Pole,
+            12 => Self::Zero,
+            13 => Self::SParam,
+            14 => Self::Temperature,
+            15 => Self::Resistance,
+            16 => Self::Impedance,
+            17 => Self::Admittance,
+            18 => Self::Power,
+            19 => Self::Phase,
+            20 => Self::Decibel,
+            21 => Self::Capacitance,
+            22 => Self::Charge,
+            _ => Self::NoType,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_progress_running() {
+        assert_eq!(parse_progress("tran: 42.5%"), Some(0.425));
+        assert_eq!(parse_progress("Source Stepping: 10%"), Some(0.1));
+        assert_eq!(parse_progress("dc:  0.0%"), Some(0.0));
+    }
+
+    #[test]
+    fn parse_progress_ready() {
+        assert_eq!(parse_progress("--ready--"), Some(1.0));
+    }
+
+    #[test]
+    fn parse_progress_invalid() {
+        assert_eq!(parse_progress(""), None);
+        assert_eq!(parse_progress("tran"), None);
+        assert_eq!(parse_progress("tran: done"), None);
     }
 }
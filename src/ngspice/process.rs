@@ -0,0 +1,387 @@
+//! Runs the ngspice executable as a subprocess, so that crashes inside the
+//! simulator can't take the app down with them.
+//!
+//! ngspice is started in pipe mode, where it reads commands from stdin and
+//! writes its output to stdout and stderr without prompts. Since nothing
+//! marks the end of a command's output, every command is followed by an
+//! `echo` of a unique marker, and the command is considered done once the
+//! marker is read back. Vectors are read back by writing the plot they
+//! belong to to a rawfile.
+
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, VecDeque},
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use anyhow::{ensure, Context, Result};
+use futures_channel::{mpsc, oneshot};
+use gtk::{gio, glib, prelude::*};
+
+use super::Callbacks;
+use crate::{rawfile, vector::Vector};
+
+const EXECUTABLE: &str = "ngspice";
+
+const MARKER_PREFIX: &str = "@@spicy-done-";
+
+#[cfg(unix)]
+const SIGINT: i32 = 2;
+
+/// A command whose output is still being read.
+struct Pending {
+    marker: String,
+    /// Whether the output is shown to the user rather than returned.
+    forward_output: bool,
+    output: String,
+    done_tx: oneshot::Sender<String>,
+}
+
+pub struct Process {
+    subprocess: gio::Subprocess,
+    stdin: gio::OutputStream,
+    pending: Rc<RefCell<VecDeque<Pending>>>,
+    next_id: Cell<u64>,
+    background_thread_running_tx: mpsc::UnboundedSender<bool>,
+    plot_cache: RefCell<HashMap<String, Rc<rawfile::RawPlot>>>,
+    /// Where circuits and rawfiles are exchanged with the subprocess.
+    dir: PathBuf,
+    is_disposed: Rc<Cell<bool>>,
+}
+
+impl Process {
+    pub fn spawn(callbacks: &Callbacks) -> Result<Self> {
+        let dir = glib::mkdtemp(glib::tmp_dir().join("spicy-XXXXXX"))
+            .context("Failed to create temporary directory")?;
+
+        let subprocess = gio::Subprocess::newv(
+            &[OsStr::new(EXECUTABLE), OsStr::new("--pipe")],
+            gio::SubprocessFlags::STDIN_PIPE
+                | gio::SubprocessFlags::STDOUT_PIPE
+                | gio::SubprocessFlags::STDERR_PIPE,
+        )
+        .with_context(|| format!("Failed to spawn `{}`", EXECUTABLE))?;
+
+        let pending = Rc::new(RefCell::new(VecDeque::new()));
+        let is_disposed = Rc::new(Cell::new(false));
+
+        let stdout = gio::DataInputStream::new(&subprocess.stdout_pipe().unwrap());
+        glib::spawn_future_local(read_stdout(
+            stdout,
+            pending.clone(),
+            callbacks.send_char_tx.clone(),
+        ));
+
+        let stderr = gio::DataInputStream::new(&subprocess.stderr_pipe().unwrap());
+        glib::spawn_future_local(read_stderr(stderr, callbacks.send_char_tx.clone()));
+
+        glib::spawn_future_local(watch_exit(
+            subprocess.clone(),
+            pending.clone(),
+            is_disposed.clone(),
+            callbacks.controlled_exit_tx.clone(),
+        ));
+
+        let this = Self {
+            stdin: subprocess.stdin_pipe().unwrap(),
+            subprocess,
+            pending,
+            next_id: Cell::new(0),
+            background_thread_running_tx: callbacks.background_thread_running_tx.clone(),
+            plot_cache: RefCell::default(),
+            dir,
+            is_disposed,
+        };
+
+        // Don't ask for confirmation on `quit`, don't page long outputs, and
+        // write rawfiles in the format the reader expects.
+        for command in ["set noaskquit", "set nomoremode", "set filetype=binary"] {
+            drop(this.send(command, false)?);
+        }
+
+        Ok(this)
+    }
+
+    pub async fn circuit(&self, circuit: Vec<String>) -> Result<()> {
+        let path = self.dir.join(format!("circuit-{}.cir", self.next_id()));
+        fs::write(&path, circuit.join("\n")).context("Failed to write circuit")?;
+
+        self.command(&format!("source {}", quote(&path))).await?;
+
+        let _ = fs::remove_file(&path);
+
+        Ok(())
+    }
+
+    /// Runs a command, showing its output to the user.
+    pub async fn command(&self, command: &str) -> Result<()> {
+        self.plot_cache.borrow_mut().clear();
+        self.send(command, true)?
+            .await
+            .context("Ngspice exited while running the command")?;
+        Ok(())
+    }
+
+    /// Runs a command without waiting for it to finish, reporting it as
+    /// running until then the same way the shared library reports its
+    /// background thread.
+    pub fn background_command(&self, command: &str) -> Result<()> {
+        self.plot_cache.borrow_mut().clear();
+        let done_rx = self.send(command, true)?;
+
+        let running_tx = self.background_thread_running_tx.clone();
+        running_tx.unbounded_send(true).unwrap();
+        glib::spawn_future_local(async move {
+            let _ = done_rx.await;
+            running_tx.unbounded_send(false).unwrap();
+        });
+
+        Ok(())
+    }
+
+    /// Interrupts the running analysis, the same way Ctrl+C does in a
+    /// terminal, so that it can be continued with `resume`.
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn halt(&self) -> Result<()> {
+        #[cfg(unix)]
+        {
+            self.subprocess.send_signal(SIGINT);
+            Ok(())
+        }
+
+        #[cfg(not(unix))]
+        anyhow::bail!("Halting a subprocess is not supported on this platform")
+    }
+
+    pub async fn current_plot_name(&self) -> Result<String> {
+        let output = self.query("echo $curplot").await?;
+        Ok(output.trim().to_string())
+    }
+
+    pub async fn all_plot_names(&self) -> Result<Vec<String>> {
+        let output = self.query("echo $plots").await?;
+        Ok(output.split_whitespace().map(String::from).collect())
+    }
+
+    pub async fn all_vector_names(&self, plot_name: &str) -> Result<Vec<String>> {
+        let plot = self.raw_plot(plot_name).await?;
+        Ok(plot
+            .vectors
+            .iter()
+            .map(|vector| vector.name.clone())
+            .collect())
+    }
+
+    /// Returns the name of the scale of a plot, which is the first vector of
+    /// the rawfile it is written to.
+    pub async fn scale_name(&self, plot_name: &str) -> Result<Option<String>> {
+        let plot = self.raw_plot(plot_name).await?;
+        Ok(rawfile::scale_index(&plot.vectors).map(|index| plot.vectors[index].name.clone()))
+    }
+
+    /// Returns the name of the analysis of a plot, which is the plot name
+    /// of the rawfile it is written to.
+    pub async fn analysis_name(&self, plot_name: &str) -> Result<Option<String>> {
+        let plot = self.raw_plot(plot_name).await?;
+        Ok(Some(plot.name.clone()))
+    }
+
+    pub async fn vector_info(&self, vector_name: &str) -> Result<Vector> {
+        // Vector names may contain dots too (e.g., `v(x1.out)`), but never
+        // in the plot name part.
+        let (plot_name, vector_name) = match vector_name.split_once('.') {
+            Some((plot_name, name)) if !plot_name.contains('(') => (plot_name.to_string(), name),
+            _ => (self.current_plot_name().await?, vector_name),
+        };
+
+        self.raw_plot(&plot_name)
+            .await?
+            .vectors
+            .iter()
+            .find(|vector| vector.name == vector_name)
+            .cloned()
+            .with_context(|| format!("Vector `{}.{}` not found", plot_name, vector_name))
+    }
+
+    /// Returns a plot with every vector of it, read from a rawfile ngspice
+    /// writes it to.
+    async fn raw_plot(&self, plot_name: &str) -> Result<Rc<rawfile::RawPlot>> {
+        if let Some(plot) = self.plot_cache.borrow().get(plot_name) {
+            return Ok(plot.clone());
+        }
+
+        // `setplot` would ask which plot to use if it doesn't exist.
+        ensure!(
+            self.all_plot_names()
+                .await?
+                .iter()
+                .any(|name| name == plot_name),
+            "Plot `{}` not found",
+            plot_name
+        );
+
+        let path = self.dir.join(format!("plot-{}.raw", self.next_id()));
+
+        // `write` only writes the vectors of the current plot.
+        let current_plot_name = self.current_plot_name().await?;
+        self.query(&format!("setplot {}", plot_name)).await?;
+        self.query(&format!("write {}", quote(&path))).await?;
+        self.query(&format!("setplot {}", current_plot_name))
+            .await?;
+
+        let (bytes, _) = gio::File::for_path(&path)
+            .load_contents_future()
+            .await
+            .context("Failed to read rawfile")?;
+        let _ = fs::remove_file(&path);
+
+        let plot = rawfile::parse(&bytes)?
+            .into_iter()
+            .next()
+            .context("Rawfile has no plots")?;
+
+        let plot = Rc::new(plot);
+        self.plot_cache
+            .borrow_mut()
+            .insert(plot_name.to_string(), plot.clone());
+
+        Ok(plot)
+    }
+
+    /// Runs a command and returns its output instead of showing it.
+    async fn query(&self, command: &str) -> Result<String> {
+        self.send(command, false)?
+            .await
+            .context("Ngspice exited while running the command")
+    }
+
+    /// Writes a command to stdin, and returns a receiver for its output that
+    /// resolves once the command is done.
+    fn send(&self, command: &str, forward_output: bool) -> Result<oneshot::Receiver<String>> {
+        ensure!(
+            !command.contains('\n'),
+            "Command must not span multiple lines"
+        );
+
+        let marker = format!("{}{}", MARKER_PREFIX, self.next_id());
+        let input = format!("{}\necho {}\n", command, marker);
+
+        let (done_tx, done_rx) = oneshot::channel();
+        self.pending.borrow_mut().push_back(Pending {
+            marker,
+            forward_output,
+            output: String::new(),
+            done_tx,
+        });
+
+        // The writes are done synchronously so that concurrent commands can't
+        // interleave. Commands are short enough to fit in the pipe's buffer,
+        // so this doesn't block even while ngspice is busy.
+        self.stdin
+            .write_all(input.as_bytes(), gio::Cancellable::NONE)
+            .context("Failed to write to ngspice")?;
+        self.stdin.flush(gio::Cancellable::NONE)?;
+
+        Ok(done_rx)
+    }
+
+    fn next_id(&self) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        id
+    }
+}
+
+impl Drop for Process {
+    fn drop(&mut self) {
+        self.is_disposed.set(true);
+        self.subprocess.force_exit();
+
+        if let Err(err) = fs::remove_dir_all(&self.dir) {
+            tracing::warn!("Failed to remove `{}`: {:?}", self.dir.display(), err);
+        }
+    }
+}
+
+/// Quotes `path` as a command argument, as the temporary directory may
+/// contain spaces (e.g., in the user name).
+fn quote(path: &Path) -> String {
+    format!("\"{}\"", path.display())
+}
+
+async fn read_stdout(
+    stream: gio::DataInputStream,
+    pending: Rc<RefCell<VecDeque<Pending>>>,
+    send_char_tx: mpsc::UnboundedSender<String>,
+) {
+    while let Some(line) = read_line(&stream).await {
+        let mut pending = pending.borrow_mut();
+
+        if pending.front().is_some_and(|front| line == front.marker) {
+            let front = pending.pop_front().unwrap();
+            let _ = front.done_tx.send(front.output);
+            continue;
+        }
+
+        match pending.front_mut() {
+            Some(front) if !front.forward_output => {
+                front.output.push_str(&line);
+                front.output.push('\n');
+            }
+            _ => send_char_tx
+                .unbounded_send(format!("stdout {}", line))
+                .unwrap(),
+        }
+    }
+}
+
+async fn read_stderr(stream: gio::DataInputStream, send_char_tx: mpsc::UnboundedSender<String>) {
+    while let Some(line) = read_line(&stream).await {
+        send_char_tx
+            .unbounded_send(format!("stderr {}", line))
+            .unwrap();
+    }
+}
+
+/// Reads a line, returning `None` once the stream is closed.
+async fn read_line(stream: &gio::DataInputStream) -> Option<String> {
+    match stream.read_line_utf8_future(glib::Priority::DEFAULT).await {
+        Ok(line) => line.map(|line| line.to_string()),
+        Err(err) => {
+            tracing::warn!("Failed to read ngspice output: {:?}", err);
+            None
+        }
+    }
+}
+
+/// Reports the subprocess exiting on its own (e.g., on `quit` or a crash) the
+/// same way the shared library reports a controlled exit.
+async fn watch_exit(
+    subprocess: gio::Subprocess,
+    pending: Rc<RefCell<VecDeque<Pending>>>,
+    is_disposed: Rc<Cell<bool>>,
+    controlled_exit_tx: mpsc::UnboundedSender<(i32, bool, bool)>,
+) {
+    if let Err(err) = subprocess.wait_future().await {
+        tracing::warn!("Failed to wait for ngspice: {:?}", err);
+    }
+
+    if is_disposed.get() {
+        return;
+    }
+
+    // Fail the commands that will never finish.
+    pending.borrow_mut().clear();
+
+    let status = if subprocess.has_exited() {
+        subprocess.exit_status()
+    } else {
+        -1
+    };
+    controlled_exit_tx
+        .unbounded_send((status, false, true))
+        .unwrap();
+}
@@ -0,0 +1,130 @@
+//! Raw bindings to ngspice's shared library interface (`sharedspice.h`).
+//!
+//! The library is loaded at runtime so that Spicy can still start and report
+//! an error when ngspice is not installed.
+
+use std::os::raw::{c_char, c_int, c_short, c_void};
+
+use anyhow::Result;
+use libloading::library_filename;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct NgComplex {
+    pub cx_real: f64,
+    pub cx_imag: f64,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct VectorInfo {
+    pub v_name: *mut c_char,
+    pub v_type: c_int,
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub v_flags: c_short,
+    pub v_realdata: *mut f64,
+    pub v_compdata: *mut NgComplex,
+    pub v_length: c_int,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct VecValues {
+    pub name: *mut c_char,
+    pub creal: f64,
+    pub cimag: f64,
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub is_scale: bool,
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub is_complex: bool,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct VecValuesAll {
+    pub veccount: c_int,
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub vecindex: c_int,
+    pub vecsa: *mut *mut VecValues,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct VecInfo {
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub number: c_int,
+    pub vecname: *mut c_char,
+    pub is_real: bool,
+    pub pdvec: *mut c_void,
+    pub pdvecscale: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct VecInfoAll {
+    pub name: *mut c_char,
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub title: *mut c_char,
+    // Unread, but part of the layout.
+    #[allow(dead_code)]
+    pub date: *mut c_char,
+    pub type_: *mut c_char,
+    pub veccount: c_int,
+    pub vecs: *mut *mut VecInfo,
+}
+
+pub type SendChar = unsafe extern "C" fn(*mut c_char, c_int, *mut c_void) -> c_int;
+pub type SendStat = unsafe extern "C" fn(*mut c_char, c_int, *mut c_void) -> c_int;
+pub type ControlledExit = unsafe extern "C" fn(c_int, bool, bool, c_int, *mut c_void) -> c_int;
+pub type SendData = unsafe extern "C" fn(*mut VecValuesAll, c_int, c_int, *mut c_void) -> c_int;
+pub type SendInitData = unsafe extern "C" fn(*mut VecInfoAll, c_int, *mut c_void) -> c_int;
+pub type BGThreadRunning = unsafe extern "C" fn(bool, c_int, *mut c_void) -> c_int;
+
+#[allow(non_snake_case)]
+pub struct Library {
+    pub ngSpice_Init: unsafe extern "C" fn(
+        Option<SendChar>,
+        Option<SendStat>,
+        Option<ControlledExit>,
+        Option<SendData>,
+        Option<SendInitData>,
+        Option<BGThreadRunning>,
+        *mut c_void,
+    ) -> c_int,
+    pub ngSpice_Command: unsafe extern "C" fn(*mut c_char) -> c_int,
+    pub ngGet_Vec_Info: unsafe extern "C" fn(*mut c_char) -> *mut VectorInfo,
+    pub ngSpice_Circ: unsafe extern "C" fn(*mut *mut c_char) -> c_int,
+    pub ngSpice_CurPlot: unsafe extern "C" fn() -> *mut c_char,
+    pub ngSpice_AllPlots: unsafe extern "C" fn() -> *mut *mut c_char,
+    pub ngSpice_AllVecs: unsafe extern "C" fn(*mut c_char) -> *mut *mut c_char,
+    pub ngSpice_running: unsafe extern "C" fn() -> bool,
+
+    // Must be dropped last, as the function pointers above point into it.
+    _library: libloading::Library,
+}
+
+impl Library {
+    /// Loads the ngspice shared library from the default search path.
+    pub fn load() -> Result<Self> {
+        unsafe {
+            let library = libloading::Library::new(library_filename("ngspice"))?;
+            Ok(Self {
+                ngSpice_Init: *library.get(b"ngSpice_Init\0")?,
+                ngSpice_Command: *library.get(b"ngSpice_Command\0")?,
+                ngGet_Vec_Info: *library.get(b"ngGet_Vec_Info\0")?,
+                ngSpice_Circ: *library.get(b"ngSpice_Circ\0")?,
+                ngSpice_CurPlot: *library.get(b"ngSpice_CurPlot\0")?,
+                ngSpice_AllPlots: *library.get(b"ngSpice_AllPlots\0")?,
+                ngSpice_AllVecs: *library.get(b"ngSpice_AllVecs\0")?,
+                ngSpice_running: *library.get(b"ngSpice_running\0")?,
+                _library: library,
+            })
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use gtk::{glib, prelude::*, subclass::prelude::*};
 
+use crate::vector::Vector;
+
 mod imp {
     use std::cell::{Cell, OnceCell};
 
@@ -12,6 +14,9 @@ mod imp {
         pub(super) name: OnceCell<String>,
         #[property(get, set, construct_only)]
         pub(super) is_current: Cell<bool>,
+
+        pub(super) imported_vectors: OnceCell<Vec<Vector>>,
+        pub(super) analysis_name: OnceCell<String>,
     }
 
     #[glib::object_subclass]
@@ -35,4 +40,28 @@ impl Plot {
             .property("is-current", is_current)
             .build()
     }
+
+    /// Creates a plot that holds its own vectors, e.g., read from a rawfile,
+    /// instead of living in the simulator.
+    pub fn imported(name: &str, analysis_name: &str, vectors: Vec<Vector>) -> Self {
+        let this = Self::new(name, false);
+        this.imp().imported_vectors.set(vectors).unwrap();
+        this.imp()
+            .analysis_name
+            .set(analysis_name.to_string())
+            .unwrap();
+        this
+    }
+
+    /// Returns the name of the analysis that produced the plot, if known.
+    pub fn analysis_name(&self) -> Option<&str> {
+        self.imp().analysis_name.get().map(|name| name.as_str())
+    }
+
+    pub fn imported_vectors(&self) -> Option<&[Vector]> {
+        self.imp()
+            .imported_vectors
+            .get()
+            .map(|vectors| vectors.as_slice())
+    }
 }
@@ -1,53 +1,582 @@
-use anyhow::Result;
+mod cairo_backend;
+mod chart;
+mod chart_paintable;
+mod cursors;
+mod dash;
+mod expression;
+mod lod;
+mod measure;
+mod overlay;
+mod spectrum;
+mod theme;
+mod ticks;
+mod vector_tree;
+mod viewport;
+
+use std::{
+    any::Any,
+    cell::Ref,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{anyhow, ensure, Context, Result};
+use gettextrs::gettext;
 use gtk::{
-    gdk,
+    gdk, gio,
     glib::{self, clone},
+    graphene, gsk,
     prelude::*,
     subclass::prelude::*,
 };
-use plotters::style::RGBAColor;
-use plotters_gtk4::{Paintable, PaintableBackend};
-
-use crate::{colors, plot_view_filter_row::PlotViewFilterRow};
-
-const COLORS: [gdk::RGBA; 7] = [
-    colors::BLUE_3,
-    colors::GREEN_3,
-    colors::YELLOW_3,
-    colors::ORANGE_3,
-    colors::RED_3,
-    colors::PURPLE_3,
-    colors::BROWN_3,
-];
-
-struct Vector {
-    name: String,
-    data: Vec<f64>,
-    color: gdk::RGBA,
+
+use self::{
+    chart::{Rendered, Scene, YAxis, BASE_DPI},
+    chart_paintable::ChartPaintable,
+    expression::Expression,
+    theme::Theme,
+    vector_tree::Kind,
+    viewport::{Pane, Viewport},
+};
+use crate::{
+    export::{self, DataFormat, ImageFormat},
+    plot_view_filter_row::PlotViewFilterRow,
+    plot_view_group_row::PlotViewGroupRow,
+    si,
+    vector::{Complex, Vector, VectorData, VectorType},
+};
+
+/// Minimum time between redraws while vectors are being appended to.
+const LIVE_UPDATE_INTERVAL: Duration = Duration::from_millis(150);
+
+/// How much a scroll step zooms out, or in when scrolling the other way.
+const SCROLL_ZOOM_FACTOR: f64 = 1.2;
+
+/// How much the zoom keyboard shortcuts zoom in or out.
+const KEY_ZOOM_FACTOR: f64 = 1.5;
+
+/// Fraction of the shown range the arrow keys pan by.
+const KEY_PAN_FRACTION: f64 = 0.1;
+
+/// Size in pixels below which a rubber band is taken as an accidental drag.
+const MIN_RUBBER_BAND_SIZE: f64 = 4.0;
+
+/// How far in pixels from a cursor it can be grabbed.
+const CURSOR_GRAB_DISTANCE: f64 = 5.0;
+
+/// How far in pixels each level of the vector tree is indented.
+const TREE_INDENT: i32 = 18;
+
+/// How complex vectors are split into two quantities, each on its own Y axis.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, glib::Enum)]
+#[enum_type(name = "SpicyComplexMode")]
+pub enum ComplexMode {
+    #[default]
+    #[enum_value(nick = "db-phase")]
+    DbPhase,
+    #[enum_value(nick = "magnitude-phase")]
+    MagnitudePhase,
+    #[enum_value(nick = "real-imaginary")]
+    RealImaginary,
+}
+
+impl ComplexMode {
+    /// Returns the upper and lower pane values of `c`.
+    fn split(self, c: Complex) -> (f64, f64) {
+        match self {
+            Self::DbPhase => (c.db(), c.arg_deg()),
+            Self::MagnitudePhase => (c.norm(), c.arg_deg()),
+            Self::RealImaginary => (c.re, c.im),
+        }
+    }
+
+    /// Returns what the upper and lower pane values of a vector with `unit`
+    /// are called, and their units.
+    fn quantities(self, unit: &'static str) -> [(&'static str, &'static str); 2] {
+        match self {
+            Self::DbPhase => [("dB", "dB"), ("phase", "°")],
+            Self::MagnitudePhase => [("magnitude", unit), ("phase", "°")],
+            Self::RealImaginary => [("real", unit), ("imaginary", unit)],
+        }
+    }
+
+    /// Returns the names and units of the upper and lower pane Y axes, for
+    /// vectors with `unit`.
+    fn y_axes(self, unit: &'static str) -> [(&'static str, &'static str); 2] {
+        match self {
+            Self::DbPhase => [("Magnitude", "dB"), ("Phase", "°")],
+            Self::MagnitudePhase => [("Magnitude", unit), ("Phase", "°")],
+            Self::RealImaginary => [("Real", unit), ("Imaginary", unit)],
+        }
+    }
+}
+
+/// The quantity of the data of a vector a trace draws.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DataPart {
+    /// The real part, which is all of it for real vectors.
+    Real,
+    /// The upper pane value of complex vectors.
+    Upper(ComplexMode),
+    /// The lower pane value of complex vectors.
+    Lower(ComplexMode),
+}
+
+impl DataPart {
+    fn of(self, data: &VectorData) -> Vec<f64> {
+        match (self, data) {
+            (Self::Upper(complex_mode), VectorData::Complex(complex)) => {
+                complex.iter().map(|&c| complex_mode.split(c).0).collect()
+            }
+            (Self::Lower(complex_mode), VectorData::Complex(complex)) => {
+                complex.iter().map(|&c| complex_mode.split(c).1).collect()
+            }
+            _ => data.to_real(),
+        }
+    }
+}
+
+/// How the line of a trace is drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "SpicyLineStyle")]
+pub enum LineStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+}
+
+impl LineStyle {
+    /// Returns the lengths of the dashes and gaps in pixels, which is empty
+    /// for solid lines.
+    fn dash_pattern(self) -> &'static [f64] {
+        match self {
+            Self::Solid => &[],
+            Self::Dashed => &[6.0, 4.0],
+            Self::Dotted => &[2.0, 3.0],
+        }
+    }
+}
+
+/// A vector of another plot, kept to be shown along with the shown plot.
+struct PinnedVector {
+    plot_name: String,
+    /// The scale of the plot the vector is a function of.
+    scale: Rc<[f64]>,
+    vector: Vector,
+}
+
+/// Where the data of a shown vector comes from.
+enum Origin {
+    /// The vector is of the shown plot.
+    Plot,
+    /// The vector is pinned from another plot, and resampled onto the scale
+    /// of the shown one.
+    Pinned(Rc<PinnedVector>),
+    /// The vector is the difference between the vectors named `minuend` and
+    /// `subtrahend`.
+    Difference { minuend: String, subtrahend: String },
+    /// The vector is computed by `expression` from the other vectors, and
+    /// is in `unit`, which may be one no vector type has.
+    Expression {
+        expression: Rc<Expression>,
+        unit: &'static str,
+    },
+}
+
+struct StyledVector {
+    vector: Vector,
+    origin: Origin,
+    /// Index of the color in the palette of the [`Theme`].
+    palette_index: usize,
+    /// Color chosen by the user over the one of the palette.
+    custom_color: Option<gdk::RGBA>,
+    line_style: LineStyle,
     is_visible: bool,
+    /// Key of the Y axis the user moved the vector to, over the one of its
+    /// unit. Only real vectors can be moved.
+    axis_key: Option<String>,
+    /// Magnitude spectrum of the vector in dB, shown in its place while the
+    /// spectrum is.
+    spectrum: Option<Rc<Vector>>,
+}
+
+impl StyledVector {
+    fn new(vector: Vector, palette_index: usize, origin: Origin) -> Self {
+        Self {
+            vector,
+            origin,
+            palette_index,
+            custom_color: None,
+            line_style: LineStyle::default(),
+            is_visible: true,
+            axis_key: None,
+            spectrum: None,
+        }
+    }
+
+    /// Returns the vector as it is shown, which is its spectrum while the
+    /// spectrum is shown.
+    fn shown(&self) -> &Vector {
+        self.spectrum.as_deref().unwrap_or(&self.vector)
+    }
+
+    /// Returns the name of the vector within its plot, or of the vector a
+    /// difference is taken from.
+    fn base_name(&self) -> &str {
+        match &self.origin {
+            Origin::Plot => &self.vector.name,
+            Origin::Pinned(pinned) => &pinned.vector.name,
+            Origin::Difference { minuend, .. } => minuend,
+            Origin::Expression { expression, .. } => expression.source(),
+        }
+    }
+
+    fn unit(&self) -> &'static str {
+        match &self.origin {
+            Origin::Expression { unit, .. } if self.spectrum.is_none() => unit,
+            _ => self.shown().vtype.unit(),
+        }
+    }
+
+    fn color(&self, theme: &Theme) -> gdk::RGBA {
+        self.custom_color
+            .unwrap_or_else(|| theme.trace_color(self.palette_index))
+    }
+}
+
+/// Which samples of a trace are measured.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "SpicyMeasurementRange")]
+pub enum MeasurementRange {
+    #[default]
+    Whole,
+    /// The zoomed X range.
+    Shown,
+    /// Between the vertical cursors, or the whole trace while they are
+    /// hidden.
+    Cursors,
+}
+
+/// The window samples are weighted by before their spectrum is taken.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, glib::Enum)]
+#[enum_type(name = "SpicySpectrumWindow")]
+pub enum SpectrumWindow {
+    /// No weighting, which resolves tones best but leaks the most between
+    /// them.
+    Rectangular,
+    #[default]
+    Hann,
+    #[enum_value(nick = "blackman-harris")]
+    BlackmanHarris,
+    /// Leaks over many bins, but shows the amplitude of tones truest.
+    #[enum_value(nick = "flat-top")]
+    FlatTop,
+}
+
+impl SpectrumWindow {
+    fn coefficients(self) -> &'static [f64] {
+        match self {
+            Self::Rectangular => spectrum::RECTANGULAR,
+            Self::Hann => spectrum::HANN,
+            Self::BlackmanHarris => spectrum::BLACKMAN_HARRIS,
+            Self::FlatTop => spectrum::FLAT_TOP,
+        }
+    }
+}
+
+/// How vectors of different units share the chart.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, glib::Enum)]
+#[enum_type(name = "SpicyUnitLayout")]
+pub enum UnitLayout {
+    /// Each unit gets a pane of its own.
+    #[default]
+    Stacked,
+    /// Units are paired on the left and right Y axes of each pane.
+    #[enum_value(nick = "secondary-axis")]
+    SecondaryAxis,
+}
+
+/// What a drag on the chart does.
+enum Drag {
+    /// Moves the shown ranges along with the pointer.
+    Pan {
+        pane: Pane,
+        x_start: (f64, f64),
+        /// The Y axes of the pane.
+        axes: Vec<(usize, Pane)>,
+        /// The ranges of `axes`, which are `None` if the axis fits its range
+        /// to the data, which it keeps doing while panning.
+        y_starts: Vec<Option<(f64, f64)>>,
+    },
+    /// Zooms into the dragged rectangle, given in picture coordinates.
+    RubberBand {
+        pane_index: usize,
+        start: (f64, f64),
+        end: (f64, f64),
+    },
+    /// Moves a measurement cursor along with the pointer.
+    Cursor {
+        handle: CursorHandle,
+        pane: Pane,
+        /// Where the drag started, in picture coordinates.
+        start: (f64, f64),
+    },
+}
+
+/// One of the measurement cursors. Horizontal cursors are on the top pane.
+#[derive(Debug, Clone, Copy)]
+enum CursorHandle {
+    Vertical(usize),
+    Horizontal(usize),
+}
+
+/// A scene, and what the view state becomes once it is shown.
+struct SceneUpdate {
+    scene: Scene,
+    /// Keys of the Y axes of the scene, in order.
+    axis_keys: Vec<String>,
+}
+
+/// Identifies the chart coordinates of a part of the data of a vector.
+#[derive(PartialEq, Eq, Hash)]
+struct ChartDataKey {
+    name: String,
+    /// Number of samples, which tells a vector apart from its spectrum
+    /// until that is taken.
+    len: usize,
+    part: DataPart,
+    is_log: bool,
+}
+
+/// What the readouts were last filled from, so that renders which change
+/// none of it don't fill them again. Settings that the readouts don't share
+/// with the chart fill them when they change instead.
+#[derive(PartialEq)]
+struct ReadoutInputs {
+    /// Increased whenever the data of the vectors changes.
+    vectors_generation: u64,
+    visible_names: Vec<String>,
+    complex_mode: ComplexMode,
+    /// The shown cursors, and whether the horizontal ones are on a log axis.
+    vertical_cursors: Option<[f64; 2]>,
+    horizontal_cursors: Option<([f64; 2], bool)>,
+    measurement_x_range: Option<(f64, f64)>,
+}
+
+impl ReadoutInputs {
+    fn vectors_eq(&self, other: &Self) -> bool {
+        self.vectors_generation == other.vectors_generation
+            && self.visible_names == other.visible_names
+            && self.complex_mode == other.complex_mode
+    }
+
+    fn cursors_eq(&self, other: &Self) -> bool {
+        self.vertical_cursors == other.vertical_cursors
+            && self.horizontal_cursors == other.horizontal_cursors
+    }
+}
+
+/// Identifies the spectrum of a vector. Samples are only ever appended to
+/// the vectors of a plot, so the number of samples of the scale tells their
+/// data apart.
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct SpectrumKey {
+    name: String,
+    len: usize,
+    window: SpectrumWindow,
+}
+
+/// Samples of vectors whose spectra are yet to be taken.
+struct SpectrumJob {
+    xs: Vec<f64>,
+    grid: spectrum::Grid,
+    window: SpectrumWindow,
+    ys: Vec<(SpectrumKey, Vec<f64>)>,
+}
+
+impl SpectrumJob {
+    /// Returns the frequencies the spectra are functions of.
+    fn scale_vector(&self) -> Vector {
+        Vector {
+            name: "frequency".to_string(),
+            vtype: VectorType::Frequency,
+            data: VectorData::Real(self.grid.frequencies()),
+        }
+    }
+
+    /// Takes the magnitude spectra in dB.
+    fn run(&self) -> Vec<(SpectrumKey, Vec<f64>)> {
+        let coefficients = self.window.coefficients();
+        self.ys
+            .iter()
+            .map(|(key, ys)| {
+                let decibels = spectrum::magnitudes(&self.xs, ys, &self.grid, coefficients)
+                    .into_iter()
+                    .map(|magnitude| 20.0 * magnitude.log10())
+                    .collect();
+                (key.clone(), decibels)
+            })
+            .collect()
+    }
 }
 
 mod imp {
-    use std::cell::RefCell;
+    use std::cell::{Cell, RefCell};
 
     use super::*;
 
-    #[derive(Default, gtk::CompositeTemplate)]
+    #[derive(Default, glib::Properties, gtk::CompositeTemplate)]
+    #[properties(wrapper_type = super::PlotView)]
     #[template(resource = "/io/github/seadve/Spicy/ui/plot_view.ui")]
     pub struct PlotView {
+        #[property(get, set = Self::set_complex_mode, explicit_notify, builder(ComplexMode::default()))]
+        pub(super) complex_mode: Cell<ComplexMode>,
+        /// Whether exported data includes the vectors that are hidden.
+        #[property(get, set)]
+        pub(super) export_hidden: Cell<bool>,
+        #[property(get, set = Self::set_log_x, explicit_notify)]
+        pub(super) log_x: Cell<bool>,
+        #[property(get, set = Self::set_log_y, explicit_notify)]
+        pub(super) log_y: Cell<bool>,
+        #[property(get, set = Self::set_show_cursors, explicit_notify)]
+        pub(super) show_cursors: Cell<bool>,
+        #[property(get, set = Self::set_show_horizontal_cursors, explicit_notify)]
+        pub(super) show_horizontal_cursors: Cell<bool>,
+        #[property(get, set = Self::set_unit_layout, explicit_notify, builder(UnitLayout::default()))]
+        pub(super) unit_layout: Cell<UnitLayout>,
+        /// Whether vectors pinned from other plots are also shown as their
+        /// difference from the vector of the same name in the shown plot.
+        #[property(get, set = Self::set_show_differences, explicit_notify)]
+        pub(super) show_differences: Cell<bool>,
+        #[property(get, set = Self::set_show_measurements, explicit_notify)]
+        pub(super) show_measurements: Cell<bool>,
+        #[property(get, set = Self::set_measurement_range, explicit_notify, builder(MeasurementRange::default()))]
+        pub(super) measurement_range: Cell<MeasurementRange>,
+        /// Whether the spectra of the vectors are shown in their place, which
+        /// they only are for transient plots.
+        #[property(get, set = Self::set_show_spectrum, explicit_notify)]
+        pub(super) show_spectrum: Cell<bool>,
+        #[property(get, set = Self::set_spectrum_window, explicit_notify, builder(SpectrumWindow::default()))]
+        pub(super) spectrum_window: Cell<SpectrumWindow>,
+        /// Whether the vector list is grouped into voltages, currents, and
+        /// device quantities.
+        #[property(get, set = Self::set_group_by_kind, explicit_notify)]
+        pub(super) group_by_kind: Cell<bool>,
+        /// Whether the vector list is grouped by the subcircuit instances
+        /// the vectors are in.
+        #[property(get, set = Self::set_group_by_instance, explicit_notify)]
+        pub(super) group_by_instance: Cell<bool>,
+
         #[template_child]
-        pub(super) picture: TemplateChild<gtk::Picture>, // Unused
+        pub(super) picture: TemplateChild<gtk::Picture>,
         #[template_child]
-        pub(super) paintable: TemplateChild<Paintable>,
+        pub(super) paintable: TemplateChild<ChartPaintable>,
+        #[template_child]
+        pub(super) cursor_readout_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) cursor_readout_grid: TemplateChild<gtk::Grid>,
+        #[template_child]
+        pub(super) measurements_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) measured_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub(super) measured_names: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub(super) delay_reference_dropdown: TemplateChild<gtk::DropDown>,
+        #[template_child]
+        pub(super) delay_reference_names: TemplateChild<gtk::StringList>,
+        #[template_child]
+        pub(super) measurements_grid: TemplateChild<gtk::Grid>,
+        #[template_child]
+        pub(super) spectrum_revealer: TemplateChild<gtk::Revealer>,
+        #[template_child]
+        pub(super) fundamental_entry: TemplateChild<gtk::Entry>,
+        #[template_child]
+        pub(super) spectrum_grid: TemplateChild<gtk::Grid>,
         #[template_child]
         pub(super) separator: TemplateChild<gtk::Separator>, // Unused
         #[template_child]
+        pub(super) search_entry: TemplateChild<gtk::SearchEntry>,
+        #[template_child]
         pub(super) scrolled_window: TemplateChild<gtk::ScrolledWindow>, // Unused
         #[template_child]
         pub(super) filter_list_box: TemplateChild<gtk::ListBox>,
+        #[template_child]
+        pub(super) expression_entry: TemplateChild<gtk::Entry>,
+
+        /// Name of the plot the scale vector is of.
+        pub(super) plot_name: RefCell<String>,
+        pub(super) scale_vector: RefCell<Option<Vector>>,
+        /// Frequencies of the spectra, shown in place of the scale vector
+        /// while they are.
+        pub(super) spectrum_scale: RefCell<Option<Vector>>,
+        /// Spectra taken of the vectors, kept until the vectors change.
+        pub(super) spectra: RefCell<HashMap<SpectrumKey, Rc<Vector>>>,
+        /// Whether spectra are being taken in the background.
+        pub(super) is_taking_spectra: Cell<bool>,
+        /// Increased when spectra are dropped, so that those being taken of
+        /// the same samples are dropped too.
+        pub(super) spectra_generation: Cell<u64>,
+        /// Frequency of the fundamental the distortion of the spectra is
+        /// measured for, or `None` for the strongest tone.
+        pub(super) fundamental: Cell<Option<f64>>,
+        /// The vectors of the shown plot, followed by the pinned vectors of
+        /// other plots, their differences, and the vectors of expressions.
+        pub(super) other_vectors: RefCell<Vec<StyledVector>>,
+        /// Vectors pinned from plots, kept as other plots are shown.
+        pub(super) pinned_vectors: RefCell<Vec<Rc<PinnedVector>>>,
+        /// Expressions added by the user, evaluated for each shown plot.
+        pub(super) expressions: RefCell<Vec<Rc<Expression>>>,
+        /// Whether the scale vector never decreases, so that traces can be
+        /// decimated and samples can be searched for.
+        pub(super) is_scale_increasing: Cell<bool>,
+        /// Chart coordinates of the shown vectors, computed once for each
+        /// and shared with renders until the vectors change.
+        pub(super) chart_data: RefCell<HashMap<ChartDataKey, Arc<[f64]>>>,
+        /// Increased whenever `chart_data` is cleared.
+        pub(super) vectors_generation: Cell<u64>,
+        pub(super) readout_inputs: RefCell<Option<ReadoutInputs>>,
+        /// The traces of the last render, decimated to the width they were
+        /// drawn at. It is moved to each render while it runs.
+        pub(super) lod_cache: RefCell<chart::LodCache>,
+        pub(super) update_paintable_source_id: RefCell<Option<glib::SourceId>>,
+        /// Size and scale factor the chart was last rendered at.
+        pub(super) render_size: Cell<((u32, u32), i32)>,
+        pub(super) is_rendering: Cell<bool>,
+        /// Whether the chart is to be rendered again once the current render
+        /// is done.
+        pub(super) needs_render: Cell<bool>,
+        /// Increased when the shown vectors are replaced or cleared, so that
+        /// renders of the previous ones are dropped.
+        pub(super) render_generation: Cell<u64>,
+        /// Entries of the vector list, one for each of its rows.
+        pub(super) vector_tree: RefCell<Vec<vector_tree::Entry>>,
+        /// Whether each entry of the vector list is shown.
+        pub(super) shown_entries: RefCell<Vec<bool>>,
+        /// Keys of the groups collapsed by the user.
+        pub(super) collapsed_groups: RefCell<HashSet<String>>,
 
-        pub(super) time_vector: RefCell<Vec<f64>>,
-        pub(super) other_vectors: RefCell<Vec<Vector>>,
+        pub(super) viewport: RefCell<Viewport>,
+        /// Where each Y axis was last drawn on the paintable, from the top
+        /// pane to the bottom one. Secondary axes follow the primary axis of
+        /// their pane, and share its area.
+        pub(super) panes: RefCell<Vec<Pane>>,
+        /// Keys of the Y axes as last drawn, in the order of `panes`.
+        pub(super) axis_keys: RefCell<Vec<String>>,
+        /// Last position of the pointer on the picture.
+        pub(super) pointer_position: Cell<Option<(f64, f64)>>,
+        /// Index of the sample nearest to the pointer, which the crosshair
+        /// and tooltip snap to.
+        pub(super) hovered_sample: Cell<Option<usize>>,
+        pub(super) drag: RefCell<Option<Drag>>,
+        /// Scale values of the vertical cursors, or `None` if they are yet to
+        /// be placed.
+        pub(super) vertical_cursors: Cell<Option<[f64; 2]>>,
+        /// Chart Y coordinates of the horizontal cursors on the top pane.
+        pub(super) horizontal_cursors: Cell<Option<[f64; 2]>>,
     }
 
     #[glib::object_subclass]
@@ -57,7 +586,128 @@ mod imp {
         type ParentType = gtk::Widget;
 
         fn class_init(klass: &mut Self::Class) {
+            ChartPaintable::ensure_type();
+
             klass.bind_template();
+
+            klass.install_property_action("plot-view.complex-mode", "complex-mode");
+            klass.install_property_action("plot-view.export-hidden", "export-hidden");
+            klass.install_property_action("plot-view.log-x", "log-x");
+            klass.install_property_action("plot-view.log-y", "log-y");
+            klass.install_property_action("plot-view.show-cursors", "show-cursors");
+            klass.install_property_action("plot-view.show-measurements", "show-measurements");
+            klass.install_property_action("plot-view.measurement-range", "measurement-range");
+            klass.install_property_action("plot-view.show-spectrum", "show-spectrum");
+            klass.install_property_action("plot-view.spectrum-window", "spectrum-window");
+            klass.install_property_action(
+                "plot-view.show-horizontal-cursors",
+                "show-horizontal-cursors",
+            );
+            klass.install_property_action("plot-view.unit-layout", "unit-layout");
+            klass.install_property_action("plot-view.show-differences", "show-differences");
+            klass.install_property_action("plot-view.group-by-kind", "group-by-kind");
+            klass.install_property_action("plot-view.group-by-instance", "group-by-instance");
+
+            klass.install_action("plot-view.reset-view", None, |obj, _, _| {
+                obj.reset_view();
+            });
+
+            klass.install_action("plot-view.pin-visible", None, |obj, _, _| {
+                obj.pin_visible_vectors();
+            });
+            klass.install_action("plot-view.unpin-all", None, |obj, _, _| {
+                obj.unpin_all_vectors();
+            });
+
+            klass.install_action("plot-view.show-all-vectors", None, |obj, _, _| {
+                obj.update_vector_visibility(|_| true);
+            });
+            klass.install_action("plot-view.hide-all-vectors", None, |obj, _, _| {
+                obj.update_vector_visibility(|_| false);
+            });
+            klass.install_action("plot-view.invert-vector-visibility", None, |obj, _, _| {
+                obj.update_vector_visibility(|is_visible| !is_visible);
+            });
+
+            klass.add_binding_action(
+                gdk::Key::_0,
+                gdk::ModifierType::empty(),
+                "plot-view.reset-view",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::Home,
+                gdk::ModifierType::empty(),
+                "plot-view.reset-view",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::c,
+                gdk::ModifierType::empty(),
+                "plot-view.show-cursors",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::m,
+                gdk::ModifierType::empty(),
+                "plot-view.show-measurements",
+                None,
+            );
+            klass.add_binding_action(
+                gdk::Key::f,
+                gdk::ModifierType::empty(),
+                "plot-view.show-spectrum",
+                None,
+            );
+            klass.add_binding(
+                gdk::Key::f,
+                gdk::ModifierType::CONTROL_MASK,
+                |obj, _| {
+                    obj.imp().search_entry.grab_focus();
+                    true
+                },
+                None,
+            );
+
+            for key in [gdk::Key::plus, gdk::Key::equal, gdk::Key::KP_Add] {
+                klass.add_binding(
+                    key,
+                    gdk::ModifierType::empty(),
+                    |obj, _| {
+                        obj.zoom_x_by(1.0 / KEY_ZOOM_FACTOR);
+                        true
+                    },
+                    None,
+                );
+            }
+            for key in [gdk::Key::minus, gdk::Key::KP_Subtract] {
+                klass.add_binding(
+                    key,
+                    gdk::ModifierType::empty(),
+                    |obj, _| {
+                        obj.zoom_x_by(KEY_ZOOM_FACTOR);
+                        true
+                    },
+                    None,
+                );
+            }
+
+            for (key, x_fraction, y_fraction) in [
+                (gdk::Key::Left, -KEY_PAN_FRACTION, 0.0),
+                (gdk::Key::Right, KEY_PAN_FRACTION, 0.0),
+                (gdk::Key::Up, 0.0, KEY_PAN_FRACTION),
+                (gdk::Key::Down, 0.0, -KEY_PAN_FRACTION),
+            ] {
+                klass.add_binding(
+                    key,
+                    gdk::ModifierType::empty(),
+                    move |obj, _| {
+                        obj.pan_by(x_fraction, y_fraction);
+                        true
+                    },
+                    None,
+                );
+            }
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -65,16 +715,100 @@ mod imp {
         }
     }
 
+    #[glib::derived_properties]
     impl ObjectImpl for PlotView {
         fn constructed(&self) {
             self.parent_constructed();
 
             let obj = self.obj();
 
+            self.setup_gestures();
+            obj.update_pin_actions();
+            obj.update_spectrum_action();
+
+            adw::StyleManager::default().connect_dark_notify(clone!(@weak obj => move |_| {
+                obj.update_theme();
+            }));
+
+            self.picture
+                .connect_scale_factor_notify(clone!(@weak obj => move |_| {
+                    obj.update_paintable();
+                }));
+
             self.filter_list_box
                 .connect_row_activated(clone!(@weak obj => move |_, row| {
-                    let filter_row = row.downcast_ref::<PlotViewFilterRow>().unwrap();
-                    filter_row.handle_activation();
+                    if let Some(filter_row) = row.downcast_ref::<PlotViewFilterRow>() {
+                        filter_row.handle_activation();
+                    } else if let Some(group_row) = row.downcast_ref::<PlotViewGroupRow>() {
+                        group_row.handle_activation();
+                    }
+                }));
+            self.filter_list_box.set_filter_func(
+                clone!(@weak obj => @default-return true, move |row| {
+                    let is_shown = obj
+                        .imp()
+                        .shown_entries
+                        .borrow()
+                        .get(row.index() as usize)
+                        .copied()
+                        .unwrap_or(true);
+                    is_shown
+                }),
+            );
+
+            self.search_entry
+                .connect_search_changed(clone!(@weak obj => move |_| {
+                    obj.update_shown_entries();
+                }));
+            self.search_entry
+                .connect_stop_search(clone!(@weak obj => move |entry| {
+                    entry.set_text("");
+                    obj.imp().picture.grab_focus();
+                }));
+
+            self.measured_dropdown
+                .connect_selected_notify(clone!(@weak obj => move |_| {
+                    obj.update_measurements();
+                }));
+            self.delay_reference_dropdown
+                .connect_selected_notify(clone!(@weak obj => move |_| {
+                    obj.update_measurements();
+                }));
+
+            self.expression_entry
+                .connect_activate(clone!(@weak obj => move |entry| {
+                    if entry.text().trim().is_empty() {
+                        return;
+                    }
+                    match obj.add_expression(&entry.text()) {
+                        Ok(()) => entry.set_text(""),
+                        Err(err) => {
+                            entry.add_css_class("error");
+                            entry.set_tooltip_text(Some(&err.to_string()));
+                        }
+                    }
+                }));
+            self.expression_entry.connect_changed(|entry| {
+                entry.remove_css_class("error");
+                entry.set_tooltip_text(None);
+            });
+
+            self.fundamental_entry
+                .connect_changed(clone!(@weak obj => move |entry| {
+                    let text = entry.text();
+                    let fundamental = if text.trim().is_empty() {
+                        Ok(None)
+                    } else {
+                        expression::parse_number(&text).map(Some)
+                    };
+                    match fundamental {
+                        Ok(fundamental) => {
+                            entry.remove_css_class("error");
+                            obj.imp().fundamental.set(fundamental);
+                            obj.update_spectrum_readout();
+                        }
+                        Err(_) => entry.add_css_class("error"),
+                    }
                 }));
         }
 
@@ -83,151 +817,2546 @@ mod imp {
         }
     }
 
-    impl WidgetImpl for PlotView {}
-}
+    impl WidgetImpl for PlotView {
+        fn size_allocate(&self, width: i32, height: i32, baseline: i32) {
+            self.parent_size_allocate(width, height, baseline);
 
-glib::wrapper! {
-    pub struct PlotView(ObjectSubclass<imp::PlotView>)
-        @extends gtk::Widget;
-}
+            let obj = self.obj();
+            if obj.picture_render_size() != self.render_size.get() {
+                obj.update_paintable();
+            }
+        }
 
-impl PlotView {
-    pub fn clear(&self) {
-        let imp = self.imp();
-        imp.time_vector.borrow_mut().clear();
-        imp.other_vectors.borrow_mut().clear();
-        imp.paintable.clear();
-        self.update_filter_list_box();
+        fn snapshot(&self, snapshot: &gtk::Snapshot) {
+            self.parent_snapshot(snapshot);
+
+            let obj = self.obj();
+            let Some(bounds) = self.picture.compute_bounds(&*obj) else {
+                return;
+            };
+
+            let drag = self.drag.borrow();
+            let Some(Drag::RubberBand { start, end, .. }) = drag.as_ref() else {
+                if drag.is_none() {
+                    self.snapshot_crosshair(snapshot, &bounds);
+                }
+                return;
+            };
+            let rect = graphene::Rect::new(
+                bounds.x() + start.0.min(end.0) as f32,
+                bounds.y() + start.1.min(end.1) as f32,
+                (end.0 - start.0).abs() as f32,
+                (end.1 - start.1).abs() as f32,
+            );
+
+            let accent = Theme::current().accent;
+            let mut fill = accent;
+            fill.set_alpha(0.2);
+            snapshot.append_color(&fill, &rect);
+            snapshot.append_border(
+                &gsk::RoundedRect::from_rect(rect, 0.0),
+                &[1.0; 4],
+                &[accent; 4],
+            );
+        }
     }
 
-    pub fn set_vectors(
-        &self,
-        time_vector: Vec<f64>,
-        other_vectors: Vec<(String, Vec<f64>)>,
-    ) -> Result<()> {
-        let imp = self.imp();
+    impl PlotView {
+        /// Draws a vertical line through the hovered sample across all panes,
+        /// and a horizontal one through the pointer on its pane.
+        fn snapshot_crosshair(&self, snapshot: &gtk::Snapshot, bounds: &graphene::Rect) {
+            let obj = self.obj();
 
-        imp.time_vector.replace(time_vector);
+            let (Some(position), Some(sample_index)) =
+                (self.pointer_position.get(), self.hovered_sample.get())
+            else {
+                return;
+            };
+            let Some(x) = obj
+                .shown_scale_vector()
+                .as_ref()
+                .and_then(|scale_vector| scale_vector.data.real_at(sample_index))
+            else {
+                return;
+            };
+            let hovered_pane_index = obj.pane_at(position).map(|(index, _)| index);
+            let color = Theme::current().crosshair;
 
-        imp.other_vectors.replace(
-            other_vectors
-                .into_iter()
-                .zip(COLORS)
-                .map(|((name, data), color)| Vector {
-                    name,
-                    data,
-                    color,
-                    is_visible: true,
-                })
-                .collect(),
-        );
+            let panes = self.panes.borrow();
+            for (pane_index, pane) in panes.iter().enumerate() {
+                // Secondary axes are drawn over the area of the primary one.
+                if pane_index > 0 && panes[pane_index - 1].shares_area(pane) {
+                    continue;
+                }
 
-        self.update_filter_list_box();
-        self.update_paintable()?;
+                let (x_pixel, _) = pane.chart_to_pixel((pane.x_from_data(x), pane.y_range.0));
+                let (left, top) = obj.paintable_to_picture((x_pixel, pane.y_pixels.0 as f64));
+                let (_, bottom) = obj.paintable_to_picture((x_pixel, pane.y_pixels.1 as f64));
+                snapshot.append_color(
+                    &color,
+                    &graphene::Rect::new(
+                        bounds.x() + left as f32,
+                        bounds.y() + top as f32,
+                        1.0,
+                        (bottom - top) as f32,
+                    ),
+                );
 
-        Ok(())
-    }
+                if hovered_pane_index == Some(pane_index) {
+                    let (left, _) = obj.paintable_to_picture((pane.x_pixels.0 as f64, 0.0));
+                    let (right, _) = obj.paintable_to_picture((pane.x_pixels.1 as f64, 0.0));
+                    snapshot.append_color(
+                        &color,
+                        &graphene::Rect::new(
+                            bounds.x() + left as f32,
+                            bounds.y() + position.1 as f32,
+                            (right - left) as f32,
+                            1.0,
+                        ),
+                    );
+                }
+            }
+        }
 
-    fn update_paintable(&self) -> Result<()> {
-        use plotters::prelude::*;
+        fn setup_gestures(&self) {
+            let obj = self.obj();
 
-        let imp = self.imp();
+            let motion = gtk::EventControllerMotion::new();
+            motion.connect_motion(clone!(@weak obj => move |_, x, y| {
+                obj.imp().pointer_position.set(Some((x, y)));
+                obj.update_hovered_sample();
+            }));
+            motion.connect_leave(clone!(@weak obj => move |_| {
+                obj.imp().pointer_position.set(None);
+                obj.update_hovered_sample();
+            }));
+            self.picture.add_controller(motion);
 
-        // TODO Support Adwaita dark theme and colors
-        let root_area = PaintableBackend::new(&imp.paintable).into_drawing_area();
-        root_area.fill(&WHITE)?;
+            self.picture.set_has_tooltip(true);
+            self.picture.connect_query_tooltip(
+                clone!(@weak obj => @default-return false, move |_, _, _, _, tooltip| {
+                    let Some(text) = obj.hovered_sample_text() else {
+                        return false;
+                    };
+                    tooltip.set_text(Some(&text));
+                    true
+                }),
+            );
 
-        let time_vector = imp.time_vector.borrow();
-        let other_vectors = imp.other_vectors.borrow();
-        let other_vectors_iter = other_vectors.iter().filter(|v| v.is_visible);
+            // Scrolling zooms the X axis, Shift the Y axis, and Control both.
+            let scroll = gtk::EventControllerScroll::new(gtk::EventControllerScrollFlags::VERTICAL);
+            scroll.connect_scroll(clone!(@weak obj => @default-return glib::Propagation::Proceed, move |controller, _, dy| {
+                let imp = obj.imp();
 
-        let x_min = time_vector
-            .iter()
-            .min_by(|a, b| a.total_cmp(b))
-            .copied()
-            .unwrap_or(0.0);
-        let x_max = time_vector
-            .iter()
-            .max_by(|a, b| a.total_cmp(b))
-            .copied()
-            .unwrap_or(0.0);
+                let Some(position) = imp.pointer_position.get() else {
+                    return glib::Propagation::Proceed;
+                };
+                let Some((_, pane)) = obj.pane_at(position) else {
+                    return glib::Propagation::Proceed;
+                };
 
-        let y_min = other_vectors_iter
-            .clone()
-            .flat_map(|vector| vector.data.iter())
-            .min_by(|a, b| a.total_cmp(b))
-            .copied()
-            .unwrap_or(0.0);
-        let y_max = other_vectors_iter
-            .clone()
-            .flat_map(|vector| vector.data.iter())
-            .max_by(|a, b| a.total_cmp(b))
-            .copied()
-            .unwrap_or(0.0);
-
-        let mut cc = ChartBuilder::on(&root_area)
-            .margin_left(10)
-            .margin_right(20)
-            .margin_top(20)
-            .margin_bottom(10)
-            .x_label_area_size(40)
-            .y_label_area_size(40)
-            .build_cartesian_2d(x_min..x_max, y_min..y_max)?;
-
-        cc.configure_mesh()
-            .x_desc("Time (ms)")
-            .x_label_formatter(&|v| format!("{:.0}", v * 1e3))
-            .y_label_formatter(&|v| format!("{:.1}", v))
-            .draw()?;
-
-        for vector in other_vectors_iter.clone() {
-            let style = ShapeStyle {
-                color: to_plotters_color(vector.color),
-                filled: true,
-                stroke_width: 1,
-            };
-            cc.draw_series(LineSeries::new(
-                time_vector.iter().copied().zip(vector.data.iter().copied()),
-                style,
-            ))?
-            .label(&vector.name)
-            .legend(move |(x, y)| PathElement::new([(x, y), (x + 20, y)], style));
-        }
+                let state = controller.current_event_state();
+                let (zoom_x, zoom_y) = if state.contains(gdk::ModifierType::CONTROL_MASK) {
+                    (true, true)
+                } else if state.contains(gdk::ModifierType::SHIFT_MASK) {
+                    (false, true)
+                } else {
+                    (true, false)
+                };
 
-        root_area.present()?;
+                let factor = SCROLL_ZOOM_FACTOR.powf(dy);
+                let (center_x, _) = pane.pixel_to_chart(obj.picture_to_paintable(position));
+                let mut viewport = imp.viewport.borrow_mut();
+                if zoom_x {
+                    let x_range = viewport.x().unwrap_or(pane.x_range);
+                    viewport.set_x(Some(viewport::zoom(x_range, center_x, factor)));
+                }
+                if zoom_y {
+                    for (axis_index, axis_pane) in obj.axes_sharing(&pane) {
+                        let (_, center_y) = axis_pane.pixel_to_chart(obj.picture_to_paintable(position));
+                        let y_range = viewport.y(axis_index).unwrap_or(axis_pane.y_range);
+                        viewport.set_y(axis_index, Some(viewport::zoom(y_range, center_y, factor)));
+                    }
+                }
+                drop(viewport);
 
-        Ok(())
-    }
+                obj.update_paintable();
 
-    fn update_filter_list_box(&self) {
-        let imp = self.imp();
+                glib::Propagation::Stop
+            }));
+            self.picture.add_controller(scroll);
 
-        imp.filter_list_box.remove_all();
+            // Dragging pans, and dragging with the secondary button or with
+            // Shift zooms into the dragged rectangle.
+            let drag = gtk::GestureDrag::builder().button(0).build();
+            drag.connect_drag_begin(clone!(@weak obj => move |gesture, x, y| {
+                let imp = obj.imp();
 
-        for vector in imp.other_vectors.borrow().iter() {
-            let row = PlotViewFilterRow::new(&vector.name, vector.color);
-            row.connect_is_active_notify(clone!(@weak self as obj => move |row| {
-                obj.imp()
-                    .other_vectors
-                    .borrow_mut()
-                    .iter_mut()
-                    .find(|v| v.name == row.name())
-                    .expect("vector must exist")
-                    .is_visible = row.is_active();
-                if let Err(err) = obj.update_paintable() {
-                    tracing::error!("Failed to update paintable: {:?}", err);
+                imp.picture.grab_focus();
+
+                if let Some((handle, pane)) = obj.cursor_at((x, y)) {
+                    imp.drag.replace(Some(Drag::Cursor {
+                        handle,
+                        pane,
+                        start: (x, y),
+                    }));
+                    return;
                 }
+
+                let Some((pane_index, pane)) = obj.pane_at((x, y)) else {
+                    gesture.set_state(gtk::EventSequenceState::Denied);
+                    return;
+                };
+
+                let is_rubber_band = gesture.current_button() == gdk::BUTTON_SECONDARY
+                    || gesture
+                        .current_event_state()
+                        .contains(gdk::ModifierType::SHIFT_MASK);
+                let drag = if is_rubber_band {
+                    Drag::RubberBand {
+                        pane_index,
+                        start: (x, y),
+                        end: (x, y),
+                    }
+                } else {
+                    let viewport = imp.viewport.borrow();
+                    let axes = obj.axes_sharing(&pane);
+                    Drag::Pan {
+                        pane,
+                        x_start: viewport.x().unwrap_or(pane.x_range),
+                        y_starts: axes
+                            .iter()
+                            .map(|(axis_index, _)| viewport.y(*axis_index))
+                            .collect(),
+                        axes,
+                    }
+                };
+                imp.drag.replace(Some(drag));
             }));
-            imp.filter_list_box.append(&row);
+            drag.connect_drag_update(clone!(@weak obj => move |gesture, offset_x, offset_y| {
+                let imp = obj.imp();
+
+                // Claimed only once moving, so that double clicks still reach
+                // the click gesture.
+                gesture.set_state(gtk::EventSequenceState::Claimed);
+
+                match imp.drag.borrow_mut().as_mut() {
+                    Some(Drag::Pan {
+                        pane,
+                        x_start,
+                        axes,
+                        y_starts,
+                    }) => {
+                        let scale = obj.paintable_scale();
+                        let mut viewport = imp.viewport.borrow_mut();
+                        viewport.set_x(Some(viewport::pan(
+                            *x_start,
+                            -offset_x / scale * pane.x_per_pixel(),
+                        )));
+                        for ((axis_index, axis_pane), y_start) in axes.iter().zip(y_starts.iter()) {
+                            if let Some(y_start) = y_start {
+                                viewport.set_y(
+                                    *axis_index,
+                                    Some(viewport::pan(
+                                        *y_start,
+                                        offset_y / scale * axis_pane.y_per_pixel(),
+                                    )),
+                                );
+                            }
+                        }
+                    }
+                    Some(Drag::RubberBand { start, end, .. }) => {
+                        *end = (start.0 + offset_x, start.1 + offset_y);
+                        obj.queue_draw();
+                        return;
+                    }
+                    Some(Drag::Cursor { handle, pane, start }) => {
+                        let (x, y) = pane.pixel_to_chart(
+                            obj.picture_to_paintable((start.0 + offset_x, start.1 + offset_y)),
+                        );
+                        match *handle {
+                            CursorHandle::Vertical(index) => {
+                                let mut cursors = imp.vertical_cursors.get().unwrap();
+                                cursors[index] = pane.x_to_data(x);
+                                imp.vertical_cursors.set(Some(cursors));
+                            }
+                            CursorHandle::Horizontal(index) => {
+                                let mut cursors = imp.horizontal_cursors.get().unwrap();
+                                cursors[index] = y;
+                                imp.horizontal_cursors.set(Some(cursors));
+                            }
+                        }
+                    }
+                    None => return,
+                }
+
+                obj.update_paintable();
+            }));
+            drag.connect_drag_end(clone!(@weak obj => move |_, _, _| {
+                let imp = obj.imp();
+
+                let drag = imp.drag.take();
+                obj.update_hovered_sample();
+
+                let Some(Drag::RubberBand {
+                    pane_index,
+                    start,
+                    end,
+                }) = drag
+                else {
+                    return;
+                };
+                obj.queue_draw();
+
+                if (end.0 - start.0).abs() < MIN_RUBBER_BAND_SIZE
+                    || (end.1 - start.1).abs() < MIN_RUBBER_BAND_SIZE
+                {
+                    return;
+                }
+
+                let Some(pane) = imp.panes.borrow().get(pane_index).copied() else {
+                    return;
+                };
+                let start = obj.picture_to_paintable(start);
+                let end = obj.picture_to_paintable(end);
+
+                let mut viewport = imp.viewport.borrow_mut();
+                viewport.set_x(Some((pane.pixel_to_chart(start).0, pane.pixel_to_chart(end).0)));
+                for (axis_index, axis_pane) in obj.axes_sharing(&pane) {
+                    viewport.set_y(
+                        axis_index,
+                        Some((axis_pane.pixel_to_chart(start).1, axis_pane.pixel_to_chart(end).1)),
+                    );
+                }
+                drop(viewport);
+
+                obj.update_paintable();
+            }));
+            drag.connect_cancel(clone!(@weak obj => move |_, _| {
+                if obj.imp().drag.take().is_some() {
+                    obj.queue_draw();
+                }
+            }));
+            self.picture.add_controller(drag);
+
+            // Dropping a row of the vector list moves its vector to the Y
+            // axis under the pointer.
+            let drop_target = gtk::DropTarget::new(String::static_type(), gdk::DragAction::MOVE);
+            drop_target.connect_drop(
+                clone!(@weak obj => @default-return false, move |_, value, x, y| {
+                    let Ok(name) = value.get::<String>() else {
+                        return false;
+                    };
+                    obj.move_vector_to_axis_at(&name, (x, y))
+                }),
+            );
+            self.picture.add_controller(drop_target);
+
+            let click = gtk::GestureClick::new();
+            click.connect_pressed(clone!(@weak obj => move |_, n_press, _, _| {
+                if n_press == 2 {
+                    obj.reset_view();
+                }
+            }));
+            self.picture.add_controller(click);
         }
-    }
+
+        fn set_complex_mode(&self, complex_mode: ComplexMode) {
+            if self.complex_mode.get() == complex_mode {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.complex_mode.set(complex_mode);
+            // The Y axes now show different quantities.
+            self.viewport.borrow_mut().reset_y();
+            self.horizontal_cursors.set(None);
+            obj.update_paintable();
+            obj.notify_complex_mode();
+        }
+
+        fn set_log_x(&self, log_x: bool) {
+            if self.log_x.get() == log_x {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.log_x.set(log_x);
+            // Ranges are in chart coordinates, which are now different.
+            self.viewport.borrow_mut().set_x(None);
+            obj.update_paintable();
+            obj.notify_log_x();
+        }
+
+        fn set_log_y(&self, log_y: bool) {
+            if self.log_y.get() == log_y {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.log_y.set(log_y);
+            self.viewport.borrow_mut().reset_y();
+            self.horizontal_cursors.set(None);
+            obj.update_paintable();
+            obj.notify_log_y();
+        }
+
+        fn set_show_cursors(&self, show_cursors: bool) {
+            if self.show_cursors.get() == show_cursors {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.show_cursors.set(show_cursors);
+            obj.update_paintable();
+            obj.notify_show_cursors();
+        }
+
+        fn set_show_horizontal_cursors(&self, show_horizontal_cursors: bool) {
+            if self.show_horizontal_cursors.get() == show_horizontal_cursors {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.show_horizontal_cursors.set(show_horizontal_cursors);
+            obj.update_paintable();
+            obj.notify_show_horizontal_cursors();
+        }
+
+        fn set_unit_layout(&self, unit_layout: UnitLayout) {
+            if self.unit_layout.get() == unit_layout {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.unit_layout.set(unit_layout);
+            obj.update_paintable();
+            obj.notify_unit_layout();
+        }
+
+        fn set_show_differences(&self, show_differences: bool) {
+            if self.show_differences.get() == show_differences {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.show_differences.set(show_differences);
+            obj.update_overlay_vectors();
+            obj.update_filter_list_box();
+            obj.update_paintable();
+            obj.notify_show_differences();
+        }
+
+        fn set_show_measurements(&self, show_measurements: bool) {
+            if self.show_measurements.get() == show_measurements {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.show_measurements.set(show_measurements);
+            obj.update_measurements();
+            obj.notify_show_measurements();
+        }
+
+        fn set_measurement_range(&self, measurement_range: MeasurementRange) {
+            if self.measurement_range.get() == measurement_range {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.measurement_range.set(measurement_range);
+            obj.update_measurements();
+            obj.notify_measurement_range();
+        }
+
+        fn set_show_spectrum(&self, show_spectrum: bool) {
+            if self.show_spectrum.get() == show_spectrum {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.show_spectrum.set(show_spectrum);
+            // The X axis is now of another quantity.
+            self.viewport.borrow_mut().reset();
+            self.vertical_cursors.set(None);
+            self.horizontal_cursors.set(None);
+            obj.update_spectra();
+            obj.update_measured_names();
+            obj.update_paintable();
+            obj.notify_show_spectrum();
+        }
+
+        fn set_spectrum_window(&self, spectrum_window: SpectrumWindow) {
+            if self.spectrum_window.get() == spectrum_window {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.spectrum_window.set(spectrum_window);
+            obj.update_spectra();
+            obj.update_paintable();
+            obj.notify_spectrum_window();
+        }
+
+        fn set_group_by_kind(&self, group_by_kind: bool) {
+            if self.group_by_kind.get() == group_by_kind {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.group_by_kind.set(group_by_kind);
+            obj.update_filter_list_box();
+            obj.notify_group_by_kind();
+        }
+
+        fn set_group_by_instance(&self, group_by_instance: bool) {
+            if self.group_by_instance.get() == group_by_instance {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.group_by_instance.set(group_by_instance);
+            obj.update_filter_list_box();
+            obj.notify_group_by_instance();
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PlotView(ObjectSubclass<imp::PlotView>)
+        @extends gtk::Widget;
+}
+
+impl PlotView {
+    pub fn clear(&self) {
+        let imp = self.imp();
+        imp.scale_vector.replace(None);
+        imp.spectrum_scale.replace(None);
+        imp.spectra.borrow_mut().clear();
+        imp.spectra_generation.set(imp.spectra_generation.get() + 1);
+        imp.other_vectors.borrow_mut().clear();
+        self.invalidate_chart_data();
+        imp.viewport.borrow_mut().reset();
+        imp.panes.borrow_mut().clear();
+        imp.vertical_cursors.set(None);
+        imp.horizontal_cursors.set(None);
+        self.cancel_queued_update_paintable();
+        imp.render_generation.set(imp.render_generation.get() + 1);
+        imp.paintable.clear();
+        self.update_filter_list_box();
+        self.update_cursor_readout();
+        self.update_measurements();
+        self.update_spectrum_readout();
+        self.update_pin_actions();
+        self.update_spectrum_action();
+    }
+
+    /// Shows `other_vectors` of the plot named `plot_name` as functions of
+    /// `scale_vector`, along with the vectors pinned from other plots.
+    pub fn set_vectors(&self, plot_name: &str, scale_vector: Vector, other_vectors: Vec<Vector>) {
+        let imp = self.imp();
+
+        // Frequency sweeps span decades. This is only a default, so the
+        // user's choice is kept across plots of the same kind of sweep (e.g.,
+        // while a simulation runs or is rerun).
+        let prev_scale_type = imp
+            .scale_vector
+            .borrow()
+            .as_ref()
+            .map(|scale_vector| scale_vector.vtype);
+        let log_x_changed = prev_scale_type != Some(scale_vector.vtype) && {
+            let log_x = scale_vector.vtype == VectorType::Frequency;
+            imp.log_x.replace(log_x) != log_x
+        };
+
+        imp.plot_name.replace(plot_name.to_string());
+        imp.is_scale_increasing.set(
+            scale_vector
+                .data
+                .to_real()
+                .windows(2)
+                .all(|pair| pair[0] <= pair[1]),
+        );
+        imp.scale_vector.replace(Some(scale_vector));
+        imp.spectrum_scale.replace(None);
+        imp.spectra.borrow_mut().clear();
+        // Plots of another run have the same vectors and sample counts, but
+        // not the same data.
+        self.invalidate_chart_data();
+        imp.viewport.borrow_mut().reset();
+        imp.vertical_cursors.set(None);
+        imp.horizontal_cursors.set(None);
+
+        imp.other_vectors.replace(
+            other_vectors
+                .into_iter()
+                .enumerate()
+                .map(|(palette_index, vector)| {
+                    StyledVector::new(vector, palette_index, Origin::Plot)
+                })
+                .collect(),
+        );
+        self.update_overlay_vectors();
+
+        self.cancel_queued_update_paintable();
+        self.update_filter_list_box();
+        imp.render_generation.set(imp.render_generation.get() + 1);
+        self.update_paintable();
+        self.update_pin_actions();
+        self.update_spectrum_action();
+
+        if log_x_changed {
+            self.notify_log_x();
+        }
+    }
+
+    /// Returns whether vectors are shown, as opposed to being cleared.
+    pub fn has_vectors(&self) -> bool {
+        self.imp().scale_vector.borrow().is_some()
+    }
+
+    /// Returns the size the chart is drawn at on screen.
+    pub fn chart_size(&self) -> (u32, u32) {
+        self.picture_render_size().0
+    }
+
+    /// Returns the vectors of the shown plot, starting with the scale
+    /// vector, without the pinned and computed ones.
+    pub fn plot_vectors(&self) -> Vec<Vector> {
+        let imp = self.imp();
+
+        imp.scale_vector
+            .borrow()
+            .iter()
+            .cloned()
+            .chain(
+                imp.other_vectors
+                    .borrow()
+                    .iter()
+                    .filter(|styled| matches!(styled.origin, Origin::Plot))
+                    .map(|styled| styled.vector.clone()),
+            )
+            .collect()
+    }
+
+    /// Serializes the scale vector and the visible vectors, or all of them
+    /// if `export-hidden` is set. Returns `None` if nothing is shown.
+    ///
+    /// Spectra that are yet to be taken, like those of hidden vectors, are
+    /// taken on the thread pool for the export only.
+    pub async fn export_data(&self, format: DataFormat) -> Option<String> {
+        let imp = self.imp();
+
+        let export_hidden = imp.export_hidden.get();
+        let job = self.spectrum_job(export_hidden);
+        let (scale_vector, vectors) = {
+            let other_vectors = imp.other_vectors.borrow();
+            let exported = other_vectors
+                .iter()
+                .filter(|styled| export_hidden || styled.is_visible);
+            match &job {
+                Some(job) => {
+                    let spectra = imp.spectra.borrow();
+                    let vectors = exported
+                        .map(|styled| {
+                            let key = SpectrumKey {
+                                name: styled.vector.name.clone(),
+                                len: job.xs.len(),
+                                window: job.window,
+                            };
+                            // Those missing are either taken below or are of
+                            // complex vectors.
+                            spectra
+                                .get(&key)
+                                .cloned()
+                                .unwrap_or_else(|| empty_spectrum(&key.name))
+                                .as_ref()
+                                .clone()
+                        })
+                        .collect::<Vec<_>>();
+                    (job.scale_vector(), vectors)
+                }
+                None => (
+                    self.shown_scale_vector().clone()?,
+                    exported
+                        .map(|styled| styled.shown().clone())
+                        .collect::<Vec<_>>(),
+                ),
+            }
+        };
+
+        let exported = gio::spawn_blocking(move || {
+            let mut vectors = vectors;
+            if let Some(job) = job {
+                for (key, decibels) in job.run() {
+                    if let Some(vector) = vectors.iter_mut().find(|v| v.name == key.name) {
+                        vector.data = VectorData::Real(decibels);
+                    }
+                }
+            }
+            export::export(format, &scale_vector, &vectors.iter().collect::<Vec<_>>())
+        })
+        .await;
+
+        match exported {
+            Ok(text) => Some(text),
+            Err(panic) => {
+                tracing::error!("Exporting data panicked: {:?}", panic_message(&panic));
+                None
+            }
+        }
+    }
+
+    /// Appends a sample to the scale vector and to each of the other vectors,
+    /// in the order they were given in [`Self::set_vectors`].
+    ///
+    /// This is meant for data arriving while a simulation is running, so
+    /// redraws are throttled.
+    pub fn push_samples(&self, scale_sample: f64, other_samples: &[Complex]) {
+        let imp = self.imp();
+
+        let mut scale_vector = imp.scale_vector.borrow_mut();
+        let Some(scale_vector) = scale_vector.as_mut() else {
+            return;
+        };
+        let is_increasing = (scale_vector.data.len().checked_sub(1))
+            .and_then(|last| scale_vector.data.real_at(last))
+            .is_none_or(|last| last <= scale_sample);
+        imp.is_scale_increasing
+            .set(imp.is_scale_increasing.get() && is_increasing);
+        scale_vector.data.push(Complex::new(scale_sample, 0.0));
+
+        for (styled, &sample) in imp.other_vectors.borrow_mut().iter_mut().zip(other_samples) {
+            styled.vector.data.push(sample);
+        }
+        self.invalidate_chart_data();
+
+        self.queue_update_paintable();
+    }
+
+    fn queue_update_paintable(&self) {
+        let imp = self.imp();
+
+        if imp.update_paintable_source_id.borrow().is_some() {
+            return;
+        }
+
+        let source_id = glib::timeout_add_local_once(
+            LIVE_UPDATE_INTERVAL,
+            clone!(@weak self as obj => move || {
+                obj.imp().update_paintable_source_id.replace(None);
+
+                obj.update_derived_vectors();
+                obj.update_spectra();
+                obj.update_paintable();
+            }),
+        );
+        imp.update_paintable_source_id.replace(Some(source_id));
+    }
+
+    /// Pins the visible vectors of the shown plot, to keep showing them
+    /// along with the vectors of the plots shown next.
+    pub fn pin_visible_vectors(&self) {
+        let imp = self.imp();
+
+        let Some(scale) = imp
+            .scale_vector
+            .borrow()
+            .as_ref()
+            .map(|scale_vector| Rc::<[f64]>::from(scale_vector.data.to_real()))
+        else {
+            return;
+        };
+        let plot_name = imp.plot_name.borrow().clone();
+        let new_pins = imp
+            .other_vectors
+            .borrow()
+            .iter()
+            .filter(|styled| styled.is_visible && matches!(styled.origin, Origin::Plot))
+            .map(|styled| {
+                Rc::new(PinnedVector {
+                    plot_name: plot_name.clone(),
+                    scale: scale.clone(),
+                    vector: styled.vector.clone(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut pinned_vectors = imp.pinned_vectors.borrow_mut();
+        pinned_vectors.retain(|pinned| {
+            !new_pins.iter().any(|new_pin| {
+                new_pin.plot_name == pinned.plot_name && new_pin.vector.name == pinned.vector.name
+            })
+        });
+        pinned_vectors.extend(new_pins);
+        drop(pinned_vectors);
+
+        // Vectors pinned from the shown plot are only shown along with other
+        // plots.
+        self.update_pin_actions();
+    }
+
+    pub fn unpin_all_vectors(&self) {
+        let imp = self.imp();
+
+        imp.pinned_vectors.borrow_mut().clear();
+        self.update_overlay_vectors();
+        self.update_filter_list_box();
+        self.update_paintable();
+        self.update_pin_actions();
+    }
+
+    fn update_pin_actions(&self) {
+        let imp = self.imp();
+
+        self.action_set_enabled("plot-view.pin-visible", self.has_vectors());
+        self.action_set_enabled(
+            "plot-view.unpin-all",
+            !imp.pinned_vectors.borrow().is_empty(),
+        );
+    }
+
+    /// Adds a trace of the math expression `source`, e.g., `v(out)-v(in)`,
+    /// which is evaluated again for each plot shown next.
+    pub fn add_expression(&self, source: &str) -> Result<()> {
+        let imp = self.imp();
+
+        let expression = Expression::parse(source)?;
+        let is_taken = imp
+            .expressions
+            .borrow()
+            .iter()
+            .any(|other| other.source() == expression.source())
+            || imp
+                .other_vectors
+                .borrow()
+                .iter()
+                .any(|styled| styled.vector.name == expression.source());
+        ensure!(
+            !is_taken,
+            "There is already a trace named “{}”",
+            expression.source()
+        );
+
+        // Mistakes are told right away, rather than by an empty trace.
+        if let Some(scale_vector) = imp.scale_vector.borrow().as_ref() {
+            let other_vectors = imp.other_vectors.borrow();
+            let vectors = other_vectors
+                .iter()
+                .map(|styled| &styled.vector)
+                .collect::<Vec<_>>();
+            expression.evaluate(scale_vector, &vectors)?;
+        }
+
+        imp.expressions.borrow_mut().push(Rc::new(expression));
+        self.update_overlay_vectors();
+        self.update_filter_list_box();
+        self.update_paintable();
+        Ok(())
+    }
+
+    /// Removes the trace of the expression named `name`.
+    fn remove_expression(&self, name: &str) {
+        self.imp()
+            .expressions
+            .borrow_mut()
+            .retain(|expression| expression.source() != name);
+        self.update_overlay_vectors();
+        self.update_filter_list_box();
+        self.update_paintable();
+    }
+
+    /// Replaces the vectors that are not of the shown plot with those pinned
+    /// from other plots, their differences if shown, and those of the
+    /// expressions, keeping the styles of those that remain.
+    fn update_overlay_vectors(&self) {
+        let imp = self.imp();
+
+        let plot_name = imp.plot_name.borrow().clone();
+        let show_differences = imp.show_differences.get();
+
+        let mut other_vectors = imp.other_vectors.borrow_mut();
+        let (plot_vectors, old_overlays): (Vec<_>, Vec<_>) = std::mem::take(&mut *other_vectors)
+            .into_iter()
+            .partition(|styled| matches!(styled.origin, Origin::Plot));
+        *other_vectors = plot_vectors;
+
+        // The overlays may come out with other data under the same names.
+        imp.spectra.borrow_mut().retain(|key, _| {
+            other_vectors
+                .iter()
+                .any(|styled| styled.vector.name == key.name)
+        });
+        imp.spectra_generation.set(imp.spectra_generation.get() + 1);
+
+        let mut overlays = Vec::new();
+        for pinned in imp
+            .pinned_vectors
+            .borrow()
+            .iter()
+            .filter(|pinned| pinned.plot_name != plot_name)
+        {
+            let name = format!("{}.{}", pinned.plot_name, pinned.vector.name);
+            let counterpart_index = other_vectors
+                .iter()
+                .position(|styled| styled.vector.name == pinned.vector.name);
+
+            // Pinned vectors share the color of their counterpart in the shown
+            // plot, and are told apart by their dashes.
+            let mut styled = StyledVector::new(
+                Vector {
+                    name: name.clone(),
+                    vtype: pinned.vector.vtype,
+                    data: VectorData::empty(!pinned.vector.data.is_complex()),
+                },
+                counterpart_index.map_or(other_vectors.len() + overlays.len(), |index| {
+                    other_vectors[index].palette_index
+                }),
+                Origin::Pinned(pinned.clone()),
+            );
+            styled.line_style = LineStyle::Dashed;
+            overlays.push(styled);
+
+            if let Some(index) = counterpart_index.filter(|_| show_differences) {
+                let counterpart = &other_vectors[index].vector;
+                overlays.push(StyledVector::new(
+                    Vector {
+                        name: format!("{} − {}", counterpart.name, name),
+                        vtype: counterpart.vtype,
+                        data: VectorData::empty(true),
+                    },
+                    other_vectors.len() + overlays.len(),
+                    Origin::Difference {
+                        minuend: counterpart.name.clone(),
+                        subtrahend: name,
+                    },
+                ));
+            }
+        }
+
+        for expression in imp.expressions.borrow().iter() {
+            overlays.push(StyledVector::new(
+                Vector {
+                    name: expression.source().to_string(),
+                    vtype: VectorType::NoType,
+                    data: VectorData::empty(true),
+                },
+                other_vectors.len() + overlays.len(),
+                Origin::Expression {
+                    expression: expression.clone(),
+                    unit: "",
+                },
+            ));
+        }
+
+        for mut styled in overlays {
+            if let Some(old) = old_overlays
+                .iter()
+                .find(|old| old.vector.name == styled.vector.name)
+            {
+                styled.custom_color = old.custom_color;
+                styled.line_style = old.line_style;
+                styled.is_visible = old.is_visible;
+                styled.axis_key = old.axis_key.clone();
+            }
+            other_vectors.push(styled);
+        }
+        drop(other_vectors);
+
+        self.update_derived_vectors();
+        self.update_spectra();
+    }
+
+    /// Recomputes the data of the vectors that are not of the shown plot
+    /// from the data they come from.
+    fn update_derived_vectors(&self) {
+        let imp = self.imp();
+
+        let scale_vector = imp.scale_vector.borrow();
+        let Some(scale_vector) = scale_vector.as_ref() else {
+            return;
+        };
+        let xs = scale_vector.data.to_real();
+
+        self.invalidate_chart_data();
+
+        let mut other_vectors = imp.other_vectors.borrow_mut();
+        for styled in other_vectors.iter_mut() {
+            if let Origin::Pinned(pinned) = &styled.origin {
+                styled.vector.data = overlay::resample(&pinned.scale, &pinned.vector.data, &xs);
+            }
+        }
+
+        // Differences are taken between vectors that are up to date by now.
+        let differences = other_vectors
+            .iter()
+            .enumerate()
+            .filter_map(|(index, styled)| {
+                let Origin::Difference {
+                    minuend,
+                    subtrahend,
+                } = &styled.origin
+                else {
+                    return None;
+                };
+                let data_of = |name: &str| {
+                    other_vectors
+                        .iter()
+                        .find(|other| other.vector.name == name)
+                        .map(|other| &other.vector.data)
+                };
+                Some((
+                    index,
+                    overlay::difference(data_of(minuend)?, data_of(subtrahend)?),
+                ))
+            })
+            .collect::<Vec<_>>();
+        for (index, data) in differences {
+            other_vectors[index].vector.data = data;
+        }
+
+        // Expressions are evaluated last, in order, from all of the above.
+        for index in 0..other_vectors.len() {
+            let Origin::Expression { expression, .. } = &other_vectors[index].origin else {
+                continue;
+            };
+            let expression = expression.clone();
+            let vectors = other_vectors
+                .iter()
+                .map(|styled| &styled.vector)
+                .collect::<Vec<_>>();
+            let evaluated = expression.evaluate(scale_vector, &vectors);
+
+            let styled = &mut other_vectors[index];
+            match evaluated {
+                Ok((data, new_unit)) => {
+                    styled.vector.data = data;
+                    styled.vector.vtype = new_unit.vector_type();
+                    if let Origin::Expression { unit, .. } = &mut styled.origin {
+                        *unit = new_unit.symbol();
+                    }
+                }
+                // The shown plot may lack the vectors it uses.
+                Err(_) => styled.vector.data = VectorData::empty(true),
+            }
+        }
+    }
+
+    /// Shows the spectra of the vectors while the spectrum is shown, or
+    /// drops them otherwise.
+    ///
+    /// Only the spectra of the visible vectors are taken, in the background.
+    /// Until they all are, the previous spectra stay shown, and vectors
+    /// without one show an empty spectrum.
+    fn update_spectra(&self) {
+        let imp = self.imp();
+
+        let Some(job) = self.spectrum_job(false) else {
+            if imp.spectrum_scale.replace(None).is_some() {
+                self.invalidate_chart_data();
+            }
+            for styled in imp.other_vectors.borrow_mut().iter_mut() {
+                styled.spectrum = None;
+            }
+            return;
+        };
+
+        if job.ys.is_empty() || imp.spectrum_scale.borrow().is_none() {
+            self.show_spectra(&job);
+        } else {
+            for styled in imp.other_vectors.borrow_mut().iter_mut() {
+                styled
+                    .spectrum
+                    .get_or_insert_with(|| empty_spectrum(&styled.vector.name));
+            }
+        }
+
+        if !job.ys.is_empty() {
+            self.take_spectra(job);
+        }
+    }
+
+    /// Returns the samples of the vectors whose spectra are yet to be taken,
+    /// of the visible ones only unless `include_hidden` is set, or `None` if
+    /// no spectrum is shown.
+    fn spectrum_job(&self, include_hidden: bool) -> Option<SpectrumJob> {
+        let imp = self.imp();
+
+        if !imp.show_spectrum.get() {
+            return None;
+        }
+        let xs = imp
+            .scale_vector
+            .borrow()
+            .as_ref()
+            .filter(|scale_vector| scale_vector.vtype == VectorType::Time)?
+            .data
+            .to_real();
+        let grid = spectrum::Grid::new(&xs)?;
+        let window = imp.spectrum_window.get();
+
+        let spectra = imp.spectra.borrow();
+        let ys = imp
+            .other_vectors
+            .borrow()
+            .iter()
+            .filter(|styled| include_hidden || styled.is_visible)
+            .filter_map(|styled| {
+                // Transient vectors are real, so those that aren't are left
+                // out.
+                let VectorData::Real(ys) = &styled.vector.data else {
+                    return None;
+                };
+                let key = SpectrumKey {
+                    name: styled.vector.name.clone(),
+                    len: xs.len(),
+                    window,
+                };
+                (!spectra.contains_key(&key)).then(|| (key, ys.clone()))
+            })
+            .collect();
+
+        Some(SpectrumJob {
+            xs,
+            grid,
+            window,
+            ys,
+        })
+    }
+
+    /// Takes the spectra of `job` in the background, and shows them once
+    /// done. Requests made in the meantime are merged into one, made once
+    /// the current one is done.
+    fn take_spectra(&self, job: SpectrumJob) {
+        let imp = self.imp();
+
+        if imp.is_taking_spectra.replace(true) {
+            return;
+        }
+
+        let generation = imp.spectra_generation.get();
+        glib::spawn_future_local(clone!(@weak self as obj => async move {
+            let taken = gio::spawn_blocking(move || {
+                let taken = job.run();
+                (job, taken)
+            })
+            .await;
+
+            let imp = obj.imp();
+            imp.is_taking_spectra.set(false);
+            match taken {
+                // The vectors may have been replaced since.
+                Ok((job, taken)) if generation == imp.spectra_generation.get() => {
+                    obj.add_spectra(taken);
+                    // Samples of live plots may have been added since, but
+                    // these are shown until the spectra of those are taken.
+                    if imp.show_spectrum.get() && imp.spectrum_window.get() == job.window {
+                        obj.show_spectra(&job);
+                    }
+                }
+                Ok(_) => {}
+                Err(panic) => {
+                    tracing::error!("Taking spectra panicked: {:?}", panic_message(&panic));
+                    return;
+                }
+            }
+            obj.update_spectra();
+            obj.update_paintable();
+        }));
+    }
+
+    fn add_spectra(&self, taken: Vec<(SpectrumKey, Vec<f64>)>) {
+        let mut spectra = self.imp().spectra.borrow_mut();
+        for (key, decibels) in taken {
+            let vector = Vector {
+                name: key.name.clone(),
+                vtype: VectorType::Decibel,
+                data: VectorData::Real(decibels),
+            };
+            spectra.insert(key, Rc::new(vector));
+        }
+    }
+
+    /// Shows the spectra of the samples of `job`, with those yet to be taken
+    /// empty.
+    fn show_spectra(&self, job: &SpectrumJob) {
+        let imp = self.imp();
+
+        let len = job.xs.len();
+        let mut spectra = imp.spectra.borrow_mut();
+        // Spectra of fewer samples aren't shown again.
+        spectra.retain(|key, _| key.len >= len);
+        for styled in imp.other_vectors.borrow_mut().iter_mut() {
+            let key = SpectrumKey {
+                name: styled.vector.name.clone(),
+                len,
+                window: job.window,
+            };
+            // Complex vectors have no spectra taken.
+            let spectrum = spectra
+                .get(&key)
+                .cloned()
+                .unwrap_or_else(|| empty_spectrum(&styled.vector.name));
+            styled.spectrum = Some(spectrum);
+        }
+
+        imp.spectrum_scale.replace(Some(job.scale_vector()));
+        self.invalidate_chart_data();
+    }
+
+    /// Drops the chart coordinates of the vectors, whose data changed.
+    fn invalidate_chart_data(&self) {
+        let imp = self.imp();
+
+        imp.chart_data.borrow_mut().clear();
+        imp.vectors_generation.set(imp.vectors_generation.get() + 1);
+    }
+
+    /// Returns the chart coordinates of `part` of the data of `vector`, on a
+    /// log axis or not. They are computed once for each vector, until the
+    /// vectors change.
+    fn chart_data(&self, vector: &Vector, part: DataPart, is_log: bool) -> Arc<[f64]> {
+        let key = ChartDataKey {
+            name: vector.name.clone(),
+            len: vector.data.len(),
+            part,
+            is_log,
+        };
+        self.imp()
+            .chart_data
+            .borrow_mut()
+            .entry(key)
+            .or_insert_with(|| {
+                let data = part.of(&vector.data);
+                if is_log {
+                    data.iter().map(|v| v.log10()).collect()
+                } else {
+                    data.into()
+                }
+            })
+            .clone()
+    }
+
+    /// Returns whether the shown scale never decreases, as the frequencies of
+    /// spectra don't.
+    fn is_scale_increasing(&self) -> bool {
+        let imp = self.imp();
+        imp.spectrum_scale.borrow().is_some() || imp.is_scale_increasing.get()
+    }
+
+    /// Returns the scale the shown vectors are functions of, which is the
+    /// frequency while the spectra are shown.
+    fn shown_scale_vector(&self) -> Ref<'_, Option<Vector>> {
+        let imp = self.imp();
+
+        let spectrum_scale = imp.spectrum_scale.borrow();
+        if spectrum_scale.is_some() {
+            spectrum_scale
+        } else {
+            imp.scale_vector.borrow()
+        }
+    }
+
+    /// Only transient plots have spectra.
+    fn update_spectrum_action(&self) {
+        let is_transient = self
+            .imp()
+            .scale_vector
+            .borrow()
+            .as_ref()
+            .is_some_and(|scale_vector| scale_vector.vtype == VectorType::Time);
+        self.action_set_enabled("plot-view.show-spectrum", is_transient);
+    }
+
+    fn cancel_queued_update_paintable(&self) {
+        if let Some(source_id) = self.imp().update_paintable_source_id.take() {
+            source_id.remove();
+        }
+    }
+
+    /// Renders the chart as an image of `width` by `height` chart pixels.
+    /// PNG images have `dpi` pixels per inch, while vector formats are sized
+    /// in points, so that the chart has the same physical size as on a screen
+    /// at the base DPI.
+    ///
+    /// The chart is drawn the same way it is on screen, directly on a cairo
+    /// surface of the requested format, on the thread pool.
+    pub async fn export_image(
+        &self,
+        format: ImageFormat,
+        width: u32,
+        height: u32,
+        dpi: f64,
+    ) -> Result<Vec<u8>> {
+        let pixels_per_chart_pixel = match format {
+            ImageFormat::Png => f64::max(dpi / BASE_DPI, 1.0),
+            ImageFormat::Svg | ImageFormat::Pdf => 1.0,
+        };
+        let columns = (width as f64 * pixels_per_chart_pixel).ceil() as usize;
+        let mut scene = self.scene().context("No plot is shown")?.scene;
+
+        gio::spawn_blocking(move || {
+            chart::decimate(&mut scene, columns, &mut chart::LodCache::default());
+            chart::render_image(&scene, format, (width, height), dpi)
+        })
+        .await
+        .map_err(|panic| anyhow!("Rendering image panicked: {:?}", panic_message(&panic)))?
+    }
+
+    /// Shows the full range of the data again.
+    pub fn reset_view(&self) {
+        self.imp().viewport.borrow_mut().reset();
+        self.update_paintable();
+    }
+
+    /// Zooms the X axis around the middle of the shown range.
+    fn zoom_x_by(&self, factor: f64) {
+        let imp = self.imp();
+
+        let Some(pane) = imp.panes.borrow().first().copied() else {
+            return;
+        };
+
+        let mut viewport = imp.viewport.borrow_mut();
+        let x_range = viewport.x().unwrap_or(pane.x_range);
+        let center = (x_range.0 + x_range.1) / 2.0;
+        viewport.set_x(Some(viewport::zoom(x_range, center, factor)));
+        drop(viewport);
+
+        self.update_paintable();
+    }
+
+    /// Pans by fractions of the shown ranges. Panning vertically applies to
+    /// every pane.
+    fn pan_by(&self, x_fraction: f64, y_fraction: f64) {
+        let imp = self.imp();
+
+        let panes = imp.panes.borrow().clone();
+        let Some(first_pane) = panes.first() else {
+            return;
+        };
+
+        let mut viewport = imp.viewport.borrow_mut();
+        if x_fraction != 0.0 {
+            let (min, max) = viewport.x().unwrap_or(first_pane.x_range);
+            viewport.set_x(Some(viewport::pan((min, max), (max - min) * x_fraction)));
+        }
+        if y_fraction != 0.0 {
+            for (index, pane) in panes.iter().enumerate() {
+                let (min, max) = viewport.y(index).unwrap_or(pane.y_range);
+                viewport.set_y(
+                    index,
+                    Some(viewport::pan((min, max), (max - min) * y_fraction)),
+                );
+            }
+        }
+        drop(viewport);
+
+        self.update_paintable();
+    }
+
+    /// Returns the cursor close to a position on the picture, and the pane it
+    /// was found on.
+    fn cursor_at(&self, position: (f64, f64)) -> Option<(CursorHandle, Pane)> {
+        let imp = self.imp();
+
+        let (pane_index, pane) = self.pane_at(position)?;
+        let (x, y) = self.picture_to_paintable(position);
+        let is_near = |(cursor_x, cursor_y): (f64, f64)| {
+            (cursor_x - x).abs() <= CURSOR_GRAB_DISTANCE
+                || (cursor_y - y).abs() <= CURSOR_GRAB_DISTANCE
+        };
+
+        if imp.show_cursors.get() {
+            if let Some(cursors) = imp.vertical_cursors.get() {
+                for (index, cursor) in cursors.into_iter().enumerate() {
+                    let (cursor_x, _) = pane.chart_to_pixel((pane.x_from_data(cursor), 0.0));
+                    if is_near((cursor_x, f64::INFINITY)) {
+                        return Some((CursorHandle::Vertical(index), pane));
+                    }
+                }
+            }
+        }
+
+        if imp.show_horizontal_cursors.get() && pane_index == 0 {
+            if let Some(cursors) = imp.horizontal_cursors.get() {
+                for (index, cursor) in cursors.into_iter().enumerate() {
+                    let (_, cursor_y) = pane.chart_to_pixel((0.0, cursor));
+                    if is_near((f64::INFINITY, cursor_y)) {
+                        return Some((CursorHandle::Horizontal(index), pane));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the Y axes drawn on the area of `pane`, the primary one first.
+    fn axes_sharing(&self, pane: &Pane) -> Vec<(usize, Pane)> {
+        self.imp()
+            .panes
+            .borrow()
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, other)| other.shares_area(pane))
+            .collect()
+    }
+
+    /// Moves the real vector named `name` to the Y axis at a position on the
+    /// picture, which is the secondary one on the right half of a pane that
+    /// has it. Returns whether it was moved.
+    fn move_vector_to_axis_at(&self, name: &str, position: (f64, f64)) -> bool {
+        let imp = self.imp();
+
+        let Some((_, pane)) = self.pane_at(position) else {
+            return false;
+        };
+        let axes = self.axes_sharing(&pane);
+        let (x, _) = self.picture_to_paintable(position);
+        let is_right_half = x > (pane.x_pixels.0 + pane.x_pixels.1) as f64 / 2.0;
+        let axis = if is_right_half {
+            axes.last()
+        } else {
+            axes.first()
+        };
+        let Some(key) =
+            axis.and_then(|(axis_index, _)| imp.axis_keys.borrow().get(*axis_index).cloned())
+        else {
+            return false;
+        };
+
+        let mut other_vectors = imp.other_vectors.borrow_mut();
+        let Some(styled) = other_vectors
+            .iter_mut()
+            .find(|v| v.vector.name == name && !v.shown().data.is_complex())
+        else {
+            return false;
+        };
+        styled.axis_key =
+            (key != chart::real_axis_key(styled.shown().vtype, styled.unit())).then_some(key);
+        drop(other_vectors);
+
+        self.update_paintable();
+        true
+    }
+
+    /// Returns the pane under a position on the picture, as its primary Y
+    /// axis.
+    fn pane_at(&self, position: (f64, f64)) -> Option<(usize, Pane)> {
+        let position = self.picture_to_paintable(position);
+        self.imp()
+            .panes
+            .borrow()
+            .iter()
+            .copied()
+            .enumerate()
+            .find(|(_, pane)| pane.contains(position))
+    }
+
+    /// Snaps to the sample nearest to the pointer, on the scale within the
+    /// shown range, and redraws the crosshair.
+    fn update_hovered_sample(&self) {
+        let imp = self.imp();
+
+        let hovered_sample = imp
+            .pointer_position
+            .get()
+            .and_then(|position| {
+                let (_, pane) = self.pane_at(position)?;
+                let (x, _) = pane.pixel_to_chart(self.picture_to_paintable(position));
+                let xs = self.chart_data(
+                    self.shown_scale_vector().as_ref()?,
+                    DataPart::Real,
+                    pane.x_is_log,
+                );
+                cursors::nearest_index(&xs, x, pane.x_range, self.is_scale_increasing())
+            })
+            .filter(|_| imp.drag.borrow().is_none());
+
+        if imp.hovered_sample.replace(hovered_sample) != hovered_sample {
+            imp.picture.trigger_tooltip_query();
+        }
+        self.queue_draw();
+    }
+
+    /// Returns the scale value and the values of the visible vectors at the
+    /// hovered sample, one per line.
+    fn hovered_sample_text(&self) -> Option<String> {
+        let imp = self.imp();
+
+        let index = imp.hovered_sample.get()?;
+        let scale_vector = self.shown_scale_vector();
+        let scale_vector = scale_vector.as_ref()?;
+
+        let mut lines = vec![format!(
+            "{}: {}",
+            scale_vector.name,
+            si::format(scale_vector.data.real_at(index)?, scale_vector.vtype.unit())
+        )];
+
+        let complex_mode = imp.complex_mode.get();
+        for styled in imp.other_vectors.borrow().iter().filter(|v| v.is_visible) {
+            let vector = styled.shown();
+            let unit = styled.unit();
+            match &vector.data {
+                VectorData::Real(real) => {
+                    let value = real.get(index).copied();
+                    lines.push(format!("{}: {}", vector.name, format_readout(value, unit)));
+                }
+                VectorData::Complex(complex) => {
+                    let values = complex.get(index).map(|&c| complex_mode.split(c));
+                    let [(upper_name, upper_unit), (lower_name, lower_unit)] =
+                        complex_mode.quantities(unit);
+                    lines.push(format!(
+                        "{} {}: {}",
+                        vector.name,
+                        upper_name,
+                        format_readout(values.map(|(upper, _)| upper), upper_unit)
+                    ));
+                    lines.push(format!(
+                        "{} {}: {}",
+                        vector.name,
+                        lower_name,
+                        format_readout(values.map(|(_, lower)| lower), lower_unit)
+                    ));
+                }
+            }
+        }
+
+        Some(lines.join("\n"))
+    }
+
+    /// Returns how much the paintable is scaled to fit the picture.
+    fn paintable_scale(&self) -> f64 {
+        let imp = self.imp();
+
+        let (width, height) = imp.paintable.size();
+        f64::min(
+            imp.picture.width() as f64 / width as f64,
+            imp.picture.height() as f64 / height as f64,
+        )
+    }
+
+    /// Maps a position on the picture to the paintable's pixels, accounting
+    /// for the picture scaling the paintable to fit and centering it.
+    fn picture_to_paintable(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let imp = self.imp();
+
+        let (width, height) = imp.paintable.size();
+        let scale = self.paintable_scale();
+        let offset_x = (imp.picture.width() as f64 - width as f64 * scale) / 2.0;
+        let offset_y = (imp.picture.height() as f64 - height as f64 * scale) / 2.0;
+        ((x - offset_x) / scale, (y - offset_y) / scale)
+    }
+
+    /// Maps the paintable's pixels to a position on the picture, the inverse
+    /// of [`Self::picture_to_paintable`].
+    fn paintable_to_picture(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        let imp = self.imp();
+
+        let (width, height) = imp.paintable.size();
+        let scale = self.paintable_scale();
+        let offset_x = (imp.picture.width() as f64 - width as f64 * scale) / 2.0;
+        let offset_y = (imp.picture.height() as f64 - height as f64 * scale) / 2.0;
+        (x * scale + offset_x, y * scale + offset_y)
+    }
+
+    /// Returns the size to render the chart at, which is that of the
+    /// picture, and the scale factor of its screen.
+    fn picture_render_size(&self) -> ((u32, u32), i32) {
+        let imp = self.imp();
+
+        (
+            (imp.picture.width() as u32, imp.picture.height() as u32),
+            imp.picture.scale_factor(),
+        )
+    }
+
+    /// Renders the chart at the size of the picture on another thread, and
+    /// shows it once done. Requests made while rendering are merged into one,
+    /// made once the current render is done.
+    fn update_paintable(&self) {
+        let imp = self.imp();
+
+        if imp.is_rendering.get() {
+            imp.needs_render.set(true);
+            return;
+        }
+
+        // The chart is rendered once the picture is allocated.
+        let (size, scale_factor) = self.picture_render_size();
+        imp.render_size.set((size, scale_factor));
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+
+        let Some(mut scene) = self.update_scene() else {
+            imp.paintable.clear();
+            imp.panes.borrow_mut().clear();
+            self.update_cursor_readout();
+            self.update_measurements();
+            self.update_spectrum_readout();
+            self.update_hovered_sample();
+            return;
+        };
+
+        imp.is_rendering.set(true);
+        let generation = imp.render_generation.get();
+        let columns = size.0 as usize * scale_factor as usize;
+        let mut lod_cache = imp.lod_cache.take();
+        glib::spawn_future_local(clone!(@weak self as obj => async move {
+            let rendered = gio::spawn_blocking(move || {
+                // Only the samples that make a difference at the drawn width
+                // are drawn, and those of the last render are reused.
+                chart::decimate(&mut scene, columns, &mut lod_cache);
+                (chart::render(&scene, size, scale_factor), lod_cache)
+            })
+            .await;
+
+            let imp = obj.imp();
+            imp.is_rendering.set(false);
+            match rendered {
+                Ok((rendered, lod_cache)) => {
+                    imp.lod_cache.replace(lod_cache);
+                    match rendered {
+                        Ok(rendered) => obj.show_rendered(generation, rendered),
+                        Err(err) => tracing::error!("Failed to render chart: {:?}", err),
+                    }
+                }
+                Err(panic) => tracing::error!("Rendering chart panicked: {:?}", panic_message(&panic)),
+            }
+            if imp.needs_render.replace(false) {
+                obj.update_paintable();
+            }
+        }));
+    }
+
+    /// Shows a finished render, unless the vectors it was drawn with have
+    /// been replaced since.
+    fn show_rendered(&self, generation: u64, mut rendered: Rendered) {
+        let imp = self.imp();
+
+        if generation != imp.render_generation.get() {
+            return;
+        }
+
+        let size = rendered.size;
+        imp.panes.replace(std::mem::take(&mut rendered.panes));
+        imp.paintable.set_texture(rendered.texture(), size);
+
+        // Cursors are placed within the ranges of the first render.
+        if self.place_missing_cursors() {
+            imp.needs_render.set(true);
+        }
+
+        self.update_readouts();
+        self.update_hovered_sample();
+    }
+
+    /// Fills the readouts again after a render, each only if what it was
+    /// filled from changed since.
+    fn update_readouts(&self) {
+        let imp = self.imp();
+
+        let top_pane = imp.panes.borrow().first().copied();
+        let inputs = ReadoutInputs {
+            vectors_generation: imp.vectors_generation.get(),
+            visible_names: imp
+                .other_vectors
+                .borrow()
+                .iter()
+                .filter(|styled| styled.is_visible)
+                .map(|styled| styled.vector.name.clone())
+                .collect(),
+            complex_mode: imp.complex_mode.get(),
+            vertical_cursors: imp
+                .vertical_cursors
+                .get()
+                .filter(|_| imp.show_cursors.get()),
+            horizontal_cursors: imp
+                .horizontal_cursors
+                .get()
+                .filter(|_| imp.show_horizontal_cursors.get())
+                .zip(top_pane.map(|pane| pane.y_is_log)),
+            measurement_x_range: self.measurement_x_range(),
+        };
+        let old_inputs = imp.readout_inputs.take();
+        let changed = |eq: fn(&ReadoutInputs, &ReadoutInputs) -> bool| {
+            old_inputs.as_ref().is_none_or(|old| !eq(old, &inputs))
+        };
+        let vectors_changed = changed(ReadoutInputs::vectors_eq);
+        let cursors_changed = changed(ReadoutInputs::cursors_eq);
+        let range_changed = changed(|old, new| old.measurement_x_range == new.measurement_x_range);
+        imp.readout_inputs.replace(Some(inputs));
+
+        if vectors_changed || cursors_changed {
+            self.update_cursor_readout();
+        }
+        if vectors_changed || range_changed {
+            self.update_measurements();
+        }
+        if vectors_changed {
+            self.update_spectrum_readout();
+        }
+    }
+
+    /// Places the shown cursors that have no position yet at a third and two
+    /// thirds of the shown ranges. Returns whether any were placed.
+    fn place_missing_cursors(&self) -> bool {
+        let imp = self.imp();
+
+        let Some(pane) = imp.panes.borrow().first().copied() else {
+            return false;
+        };
+        let thirds =
+            |(min, max): (f64, f64)| [min + (max - min) / 3.0, min + (max - min) * 2.0 / 3.0];
+
+        let mut placed = false;
+        if imp.show_cursors.get() && imp.vertical_cursors.get().is_none() {
+            let [a, b] = thirds(pane.x_range);
+            imp.vertical_cursors
+                .set(Some([pane.x_to_data(a), pane.x_to_data(b)]));
+            placed = true;
+        }
+        if imp.show_horizontal_cursors.get() && imp.horizontal_cursors.get().is_none() {
+            imp.horizontal_cursors.set(Some(thirds(pane.y_range)));
+            placed = true;
+        }
+        placed
+    }
+
+    /// Fills the readout with the values of the visible vectors at the
+    /// cursors, and the differences between them.
+    fn update_cursor_readout(&self) {
+        let imp = self.imp();
+
+        let grid = &imp.cursor_readout_grid;
+        while let Some(child) = grid.first_child() {
+            grid.remove(&child);
+        }
+
+        let show_cursors = imp.show_cursors.get();
+        let show_horizontal_cursors = imp.show_horizontal_cursors.get();
+        imp.cursor_readout_revealer
+            .set_reveal_child(show_cursors || show_horizontal_cursors);
+
+        let scale_vector = self.shown_scale_vector();
+        let Some(scale_vector) = scale_vector.as_ref() else {
+            return;
+        };
+
+        let mut row = 0;
+        let mut attach_row = |cells: [String; 5], is_header: bool| {
+            for (column, text) in cells.into_iter().enumerate() {
+                let label = gtk::Label::builder()
+                    .label(text)
+                    .xalign(if column == 0 { 0.0 } else { 1.0 })
+                    .selectable(!is_header)
+                    .build();
+                if is_header {
+                    label.add_css_class("heading");
+                } else if column == 0 {
+                    label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+                    label.set_hexpand(true);
+                }
+                grid.attach(&label, column as i32, row, 1, 1);
+            }
+            row += 1;
+        };
+        let [a_label, b_label] = cursors::VERTICAL_LABELS;
+        let [c_label, d_label] = cursors::HORIZONTAL_LABELS;
+
+        if let Some([xa, xb]) = imp.vertical_cursors.get().filter(|_| show_cursors) {
+            let x_data = self.chart_data(scale_vector, DataPart::Real, false);
+            let is_increasing = self.is_scale_increasing();
+            let x_unit = scale_vector.vtype.unit();
+            let dx = xb - xa;
+
+            attach_row(
+                [
+                    String::new(),
+                    a_label.to_string(),
+                    b_label.to_string(),
+                    format!("{} − {}", b_label, a_label),
+                    gettext("Slope"),
+                ],
+                true,
+            );
+            attach_row(
+                [
+                    scale_vector.name.clone(),
+                    si::format(xa, x_unit),
+                    si::format(xb, x_unit),
+                    si::format(dx, x_unit),
+                    String::new(),
+                ],
+                false,
+            );
+            attach_row(
+                [
+                    format!("1/({} − {})", b_label, a_label),
+                    String::new(),
+                    String::new(),
+                    si::format(1.0 / dx, &cursors::quotient_unit("", x_unit)),
+                    String::new(),
+                ],
+                false,
+            );
+
+            let complex_mode = imp.complex_mode.get();
+            for styled in imp.other_vectors.borrow().iter().filter(|v| v.is_visible) {
+                let vector = styled.shown();
+                let unit = styled.unit();
+                let quantities = match &vector.data {
+                    VectorData::Real(_) => {
+                        vec![(vector.name.clone(), unit, DataPart::Real)]
+                    }
+                    VectorData::Complex(_) => {
+                        let [(upper_name, upper_unit), (lower_name, lower_unit)] =
+                            complex_mode.quantities(unit);
+                        vec![
+                            (
+                                format!("{} {}", vector.name, upper_name),
+                                upper_unit,
+                                DataPart::Upper(complex_mode),
+                            ),
+                            (
+                                format!("{} {}", vector.name, lower_name),
+                                lower_unit,
+                                DataPart::Lower(complex_mode),
+                            ),
+                        ]
+                    }
+                };
+
+                for (name, unit, part) in quantities {
+                    let ys = self.chart_data(vector, part, false);
+                    let ya = cursors::interpolate(&x_data, &ys, xa, is_increasing);
+                    let yb = cursors::interpolate(&x_data, &ys, xb, is_increasing);
+                    let dy = ya.zip(yb).map(|(ya, yb)| yb - ya);
+                    attach_row(
+                        [
+                            name,
+                            format_readout(ya, unit),
+                            format_readout(yb, unit),
+                            format_readout(dy, unit),
+                            format_readout(
+                                dy.map(|dy| dy / dx),
+                                &cursors::quotient_unit(unit, x_unit),
+                            ),
+                        ],
+                        false,
+                    );
+                }
+            }
+        }
+
+        let top_pane = imp.panes.borrow().first().copied();
+        if let Some(([yc, yd], pane)) = imp
+            .horizontal_cursors
+            .get()
+            .filter(|_| show_horizontal_cursors)
+            .zip(top_pane)
+        {
+            let (yc, yd) = (pane.y_to_data(yc), pane.y_to_data(yd));
+            attach_row(
+                [
+                    String::new(),
+                    c_label.to_string(),
+                    d_label.to_string(),
+                    format!("{} − {}", d_label, c_label),
+                    String::new(),
+                ],
+                true,
+            );
+            attach_row(
+                [
+                    "y".to_string(),
+                    format_readout(Some(yc), ""),
+                    format_readout(Some(yd), ""),
+                    format_readout(Some(yd - yc), ""),
+                    String::new(),
+                ],
+                false,
+            );
+        }
+    }
+
+    /// Lists the real vectors to be measured and measured against, keeping
+    /// those chosen selected.
+    fn update_measured_names(&self) {
+        let imp = self.imp();
+
+        let names = imp
+            .other_vectors
+            .borrow()
+            .iter()
+            .filter(|styled| !styled.shown().data.is_complex())
+            .map(|styled| styled.vector.name.clone())
+            .collect::<Vec<_>>();
+        let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+        let position_of = |name: Option<String>| {
+            name.and_then(|name| names.iter().position(|other| *other == name))
+        };
+
+        let measured = position_of(selected_name(&imp.measured_dropdown));
+        imp.measured_names
+            .splice(0, imp.measured_names.n_items(), &names);
+        imp.measured_dropdown
+            .set_selected(measured.unwrap_or(0) as u32);
+
+        // The first one is for measuring no delay.
+        let reference = position_of(selected_name(&imp.delay_reference_dropdown));
+        let none = gettext("None");
+        imp.delay_reference_names.splice(
+            0,
+            imp.delay_reference_names.n_items(),
+            &[&[none.as_str()], names.as_slice()].concat(),
+        );
+        imp.delay_reference_dropdown
+            .set_selected(reference.map_or(0, |position| position + 1) as u32);
+    }
+
+    /// Fills the measurements panel with those of the chosen vector, within
+    /// the chosen range.
+    fn update_measurements(&self) {
+        let imp = self.imp();
+
+        let grid = &imp.measurements_grid;
+        while let Some(child) = grid.first_child() {
+            grid.remove(&child);
+        }
+
+        let show_measurements = imp.show_measurements.get();
+        imp.measurements_revealer
+            .set_reveal_child(show_measurements);
+        if !show_measurements {
+            return;
+        }
+
+        let scale_vector = self.shown_scale_vector();
+        let Some(scale_vector) = scale_vector.as_ref() else {
+            return;
+        };
+        let other_vectors = imp.other_vectors.borrow();
+        let find = |name: Option<String>| {
+            let name = name?;
+            other_vectors
+                .iter()
+                .find(|styled| styled.vector.name == name)
+        };
+        let Some(styled) = find(selected_name(&imp.measured_dropdown)) else {
+            return;
+        };
+        let VectorData::Real(ys) = &styled.shown().data else {
+            return;
+        };
+        let unit = styled.unit();
+
+        let xs = self.chart_data(scale_vector, DataPart::Real, false);
+        let x_unit = scale_vector.vtype.unit();
+        let x_range = self.measurement_x_range();
+        let measurements = measure::measure(&xs, ys, x_range);
+        let delay =
+            find(selected_name(&imp.delay_reference_dropdown)).and_then(
+                |reference| match &reference.shown().data {
+                    VectorData::Real(reference_ys) => {
+                        measure::delay(&xs, ys, reference_ys, x_range)
+                    }
+                    VectorData::Complex(_) => None,
+                },
+            );
+
+        let m = measurements.as_ref();
+        let percent = |value: Option<f64>| format_readout(value.map(|value| value * 100.0), "%");
+        // Levels, timing, and steps, each in a column.
+        let entries = [
+            (gettext("Minimum"), format_readout(m.map(|m| m.min), unit)),
+            (gettext("Maximum"), format_readout(m.map(|m| m.max), unit)),
+            (
+                gettext("Peak-to-Peak"),
+                format_readout(m.map(|m| m.peak_to_peak()), unit),
+            ),
+            (gettext("Mean"), format_readout(m.map(|m| m.mean), unit)),
+            (gettext("RMS"), format_readout(m.map(|m| m.rms), unit)),
+            (
+                gettext("Frequency"),
+                format_readout(
+                    m.and_then(|m| m.frequency()),
+                    &cursors::quotient_unit("", x_unit),
+                ),
+            ),
+            (
+                gettext("Period"),
+                format_readout(m.and_then(|m| m.period), x_unit),
+            ),
+            (gettext("Duty Cycle"), percent(m.and_then(|m| m.duty_cycle))),
+            (
+                gettext("Rise Time"),
+                format_readout(m.and_then(|m| m.rise_time), x_unit),
+            ),
+            (
+                gettext("Fall Time"),
+                format_readout(m.and_then(|m| m.fall_time), x_unit),
+            ),
+            (gettext("Overshoot"), percent(m.and_then(|m| m.overshoot))),
+            (
+                gettext("Settling Time"),
+                format_readout(m.and_then(|m| m.settling_time), x_unit),
+            ),
+            (gettext("Delay"), format_readout(delay, x_unit)),
+        ];
+        for (index, (name, value)) in entries.into_iter().enumerate() {
+            let (row, column) = ((index % 5) as i32, (index / 5) as i32 * 2);
+            let name_label = gtk::Label::builder().label(name).xalign(0.0).build();
+            name_label.add_css_class("dim-label");
+            grid.attach(&name_label, column, row, 1, 1);
+            let value_label = gtk::Label::builder()
+                .label(value)
+                .xalign(1.0)
+                .hexpand(true)
+                .selectable(true)
+                .build();
+            grid.attach(&value_label, column + 1, row, 1, 1);
+        }
+    }
+
+    /// Fills the spectrum panel with the fundamental, THD, and SFDR of each
+    /// visible spectrum.
+    fn update_spectrum_readout(&self) {
+        let imp = self.imp();
+
+        let grid = &imp.spectrum_grid;
+        while let Some(child) = grid.first_child() {
+            grid.remove(&child);
+        }
+
+        let spectrum_scale = imp.spectrum_scale.borrow();
+        imp.spectrum_revealer
+            .set_reveal_child(spectrum_scale.is_some());
+        let Some(spectrum_scale) = spectrum_scale.as_ref() else {
+            return;
+        };
+        let frequencies = self.chart_data(spectrum_scale, DataPart::Real, false);
+        // The bins are evenly spaced from DC on.
+        let Some(&resolution) = frequencies.get(1) else {
+            return;
+        };
+        let fundamental = imp
+            .fundamental
+            .get()
+            .map(|frequency| frequency / resolution);
+        let coefficients = imp.spectrum_window.get().coefficients();
+
+        let mut row = 0;
+        let mut attach_row = |cells: [String; 4], is_header: bool| {
+            for (column, text) in cells.into_iter().enumerate() {
+                let label = gtk::Label::builder()
+                    .label(text)
+                    .xalign(if column == 0 { 0.0 } else { 1.0 })
+                    .selectable(!is_header)
+                    .build();
+                if is_header {
+                    label.add_css_class("heading");
+                } else if column == 0 {
+                    label.set_ellipsize(gtk::pango::EllipsizeMode::Middle);
+                    label.set_hexpand(true);
+                }
+                grid.attach(&label, column as i32, row, 1, 1);
+            }
+            row += 1;
+        };
+
+        attach_row(
+            [
+                String::new(),
+                gettext("Fundamental"),
+                gettext("THD"),
+                gettext("SFDR"),
+            ],
+            true,
+        );
+        for styled in imp.other_vectors.borrow().iter().filter(|v| v.is_visible) {
+            let VectorData::Real(decibels) = &styled.shown().data else {
+                continue;
+            };
+            if decibels.is_empty() {
+                continue;
+            }
+            let magnitudes = decibels
+                .iter()
+                .map(|decibel| 10_f64.powf(decibel / 20.0))
+                .collect::<Vec<_>>();
+            let distortion = spectrum::distortion(&magnitudes, fundamental, coefficients);
+
+            let d = distortion.as_ref();
+            let thd = d.map_or_else(
+                || format_readout(None, ""),
+                |d| {
+                    format!(
+                        "{} ({})",
+                        si::format(20.0 * d.thd.log10(), "dB"),
+                        si::format(d.thd * 100.0, "%")
+                    )
+                },
+            );
+            attach_row(
+                [
+                    styled.vector.name.clone(),
+                    format_readout(
+                        d.map(|d| frequencies[d.fundamental]),
+                        spectrum_scale.vtype.unit(),
+                    ),
+                    thd,
+                    format_readout(d.map(|d| 20.0 * d.sfdr.log10()), "dB"),
+                ],
+                false,
+            );
+        }
+    }
+
+    /// Returns the range of the scale that is measured, or `None` if the
+    /// whole of it is.
+    fn measurement_x_range(&self) -> Option<(f64, f64)> {
+        let imp = self.imp();
+
+        match imp.measurement_range.get() {
+            MeasurementRange::Whole => None,
+            MeasurementRange::Shown => {
+                let to_data = |x: f64| {
+                    if imp.log_x.get() {
+                        10_f64.powf(x)
+                    } else {
+                        x
+                    }
+                };
+                imp.viewport
+                    .borrow()
+                    .x()
+                    .map(|(min, max)| (to_data(min), to_data(max)))
+            }
+            MeasurementRange::Cursors => imp
+                .vertical_cursors
+                .get()
+                .filter(|_| imp.show_cursors.get())
+                .map(|[a, b]| (a.min(b), a.max(b))),
+        }
+    }
+
+    /// Builds the scene to show with [`Self::scene`], and brings the view
+    /// state in line with it.
+    fn update_scene(&self) -> Option<Scene> {
+        let imp = self.imp();
+
+        let SceneUpdate { scene, axis_keys } = self.scene()?;
+
+        // The Y ranges follow their axes as others come and go.
+        let old_axis_keys = imp.axis_keys.replace(axis_keys.clone());
+        if old_axis_keys != axis_keys {
+            let mut viewport = imp.viewport.borrow_mut();
+            let old_ranges = old_axis_keys
+                .iter()
+                .enumerate()
+                .map(|(index, key)| (key, viewport.y(index)))
+                .collect::<Vec<_>>();
+            viewport.reset_y();
+            for (index, key) in axis_keys.iter().enumerate() {
+                if let Some((_, range)) = old_ranges.iter().find(|(old_key, _)| *old_key == key) {
+                    viewport.set_y(index, *range);
+                }
+            }
+
+            if old_axis_keys.first() != axis_keys.first() {
+                imp.horizontal_cursors.set(None);
+            }
+        }
+
+        // Until the chart is rendered, gestures work with the new ranges on
+        // the areas of the last render. Fitted Y ranges are only known once
+        // it is.
+        let mut panes = imp.panes.borrow_mut();
+        if panes.len() == scene.y_axes.len() {
+            for (pane, y_axis) in panes.iter_mut().zip(&scene.y_axes) {
+                pane.x_range = scene.x_range;
+                pane.x_is_log = scene.x_is_log;
+                if let Some(y_range) = y_axis.range {
+                    pane.y_range = y_range;
+                }
+                pane.y_is_log = y_axis.is_log;
+            }
+        }
+        drop(panes);
+
+        Some(scene)
+    }
+
+    /// Gathers what the chart is drawn with, or returns `None` if no vectors
+    /// are shown. The data of the traces is computed once and shared with the
+    /// view, and traces are decimated when drawn, so that this stays cheap
+    /// for each frame.
+    ///
+    /// The view state is only read, so that exports leave it alone.
+    fn scene(&self) -> Option<SceneUpdate> {
+        let imp = self.imp();
+
+        let theme = Theme::current();
+
+        let scale_vector = self.shown_scale_vector();
+        let scale_vector = scale_vector.as_ref()?;
+        let log_x = imp.log_x.get();
+        // The frequency scale of AC plots is complex with a zero imaginary
+        // part, so only its real part is meaningful.
+        let x_data = self.chart_data(scale_vector, DataPart::Real, log_x);
+        let x_unit = scale_vector.vtype.unit();
+        let x_is_increasing = self.is_scale_increasing();
+
+        let other_vectors = imp.other_vectors.borrow();
+        let (axis_keys, mut y_axes) = chart::group_y_axes(
+            other_vectors.iter().filter(|v| v.is_visible),
+            &x_data,
+            imp.complex_mode.get(),
+            imp.log_y.get(),
+            theme,
+            |vector, part, is_log| self.chart_data(vector, part, is_log),
+        );
+
+        if y_axes.is_empty() {
+            y_axes.push(YAxis {
+                name: None,
+                unit: "",
+                is_log: imp.log_y.get(),
+                range: None,
+                traces: Vec::new(),
+            });
+        }
+        // The Y ranges follow their axes as others come and go, the same way
+        // `update_scene()` moves them.
+        let old_axis_keys = imp.axis_keys.borrow();
+        let viewport = imp.viewport.borrow();
+        for (index, y_axis) in y_axes.iter_mut().enumerate() {
+            y_axis.range = if *old_axis_keys == axis_keys {
+                viewport.y(index)
+            } else {
+                axis_keys
+                    .get(index)
+                    .and_then(|key| old_axis_keys.iter().position(|old_key| old_key == key))
+                    .and_then(|old_index| viewport.y(old_index))
+            };
+        }
+        let is_first_axis_kept = old_axis_keys.first() == axis_keys.first();
+        drop(old_axis_keys);
+
+        let x_range = viewport
+            .x()
+            .unwrap_or_else(|| chart::fit_x_range(&x_data, x_is_increasing));
+
+        let x_cursors = imp
+            .vertical_cursors
+            .get()
+            .filter(|_| imp.show_cursors.get())
+            .map_or_else(Vec::new, |cursors| {
+                cursors
+                    .into_iter()
+                    .map(|x| if log_x { x.log10() } else { x })
+                    .collect()
+            });
+        // Horizontal cursors are dropped along with the first Y axis.
+        let y_cursors = imp
+            .horizontal_cursors
+            .get()
+            .filter(|_| imp.show_horizontal_cursors.get() && is_first_axis_kept)
+            .map_or_else(Vec::new, Vec::from);
+
+        let scene = Scene {
+            theme: *theme,
+            x_name: scale_vector.name.clone(),
+            x_unit,
+            x_is_log: log_x,
+            x_is_increasing,
+            x_range,
+            y_axes,
+            axes_per_pane: match imp.unit_layout.get() {
+                UnitLayout::Stacked => 1,
+                UnitLayout::SecondaryAxis => 2,
+            },
+            x_cursors,
+            y_cursors,
+        };
+
+        Some(SceneUpdate { scene, axis_keys })
+    }
+
+    /// Recolors the chart and the trace colors of the rows for the current
+    /// light or dark style.
+    fn update_theme(&self) {
+        let imp = self.imp();
+
+        let theme = Theme::current();
+        let other_vectors = imp.other_vectors.borrow();
+        let mut row = imp.filter_list_box.first_child();
+        while let Some(widget) = row {
+            if let Some(filter_row) = widget.downcast_ref::<PlotViewFilterRow>() {
+                if let Some(styled) = other_vectors
+                    .iter()
+                    .find(|v| v.vector.name == filter_row.name())
+                {
+                    filter_row.set_color(styled.color(theme));
+                }
+            }
+            row = widget.next_sibling();
+        }
+        drop(other_vectors);
+
+        self.update_paintable();
+        self.queue_draw();
+    }
+
+    /// Sets the visibility of the vectors matching the search, or of all of
+    /// them when not searching, to what `f` returns for their current one.
+    fn update_vector_visibility(&self, f: impl Fn(bool) -> bool) {
+        let imp = self.imp();
+
+        let search = self.search_text();
+        for styled in imp.other_vectors.borrow_mut().iter_mut() {
+            if search
+                .as_deref()
+                .is_none_or(|search| matches_search(&styled.vector.name, search))
+            {
+                styled.is_visible = f(styled.is_visible);
+            }
+        }
+
+        // The rows find their vectors already updated, so they don't redraw
+        // one by one.
+        let mut row = imp.filter_list_box.first_child();
+        while let Some(widget) = row {
+            if let Some(filter_row) = widget.downcast_ref::<PlotViewFilterRow>() {
+                let is_visible = imp
+                    .other_vectors
+                    .borrow()
+                    .iter()
+                    .find(|v| v.vector.name == filter_row.name())
+                    .is_some_and(|v| v.is_visible);
+                filter_row.set_is_active(is_visible);
+            }
+            row = widget.next_sibling();
+        }
+
+        self.update_spectra();
+        self.update_paintable();
+    }
+
+    /// Returns the lowercase search text, or `None` if not searching.
+    fn search_text(&self) -> Option<String> {
+        let text = self.imp().search_entry.text().trim().to_lowercase();
+        (!text.is_empty()).then_some(text)
+    }
+
+    /// Filters the rows of the vector list by the search and the collapsed
+    /// groups.
+    fn update_shown_entries(&self) {
+        let imp = self.imp();
+
+        let search = self.search_text();
+        let other_vectors = imp.other_vectors.borrow();
+        let is_match = |index: usize| {
+            search
+                .as_deref()
+                .is_some_and(|search| matches_search(&other_vectors[index].vector.name, search))
+        };
+        let collapsed_groups = imp.collapsed_groups.borrow();
+        let shown_entries = vector_tree::shown(
+            &imp.vector_tree.borrow(),
+            search
+                .is_some()
+                .then_some(&is_match as &dyn Fn(usize) -> bool),
+            |key| collapsed_groups.contains(key),
+        );
+        drop(collapsed_groups);
+        drop(other_vectors);
+
+        imp.shown_entries.replace(shown_entries);
+        imp.filter_list_box.invalidate_filter();
+    }
+
+    /// Rebuilds the vector list, grouped as chosen.
+    fn update_filter_list_box(&self) {
+        let imp = self.imp();
+
+        imp.filter_list_box.remove_all();
+
+        let other_vectors = imp.other_vectors.borrow();
+        let group_by_kind = imp.group_by_kind.get();
+        let group_by_instance = imp.group_by_instance.get();
+        let paths = other_vectors
+            .iter()
+            .map(|styled| {
+                let base_name = styled.base_name();
+                // Expressions are only told apart by their unit, as they
+                // may use vectors of any kind or instance.
+                let is_expression = matches!(styled.origin, Origin::Expression { .. });
+                let mut path = Vec::new();
+                if group_by_kind {
+                    let kind_name = if is_expression { "" } else { base_name };
+                    path.push(kind_title(Kind::of(kind_name, styled.vector.vtype)));
+                }
+                if group_by_instance && !is_expression {
+                    if let Origin::Pinned(pinned) = &styled.origin {
+                        path.push(pinned.plot_name.clone());
+                    }
+                    path.extend(
+                        vector_tree::instance_path(base_name)
+                            .into_iter()
+                            .map(String::from),
+                    );
+                }
+                path
+            })
+            .collect::<Vec<_>>();
+        let tree = vector_tree::build(&paths);
+
+        for entry in &tree {
+            let row = match &entry.item {
+                vector_tree::Item::Group { key, label, len } => {
+                    let is_expanded = !imp.collapsed_groups.borrow().contains(key);
+                    let row = PlotViewGroupRow::new(key, label, *len as u32, is_expanded);
+                    row.connect_is_expanded_notify(clone!(@weak self as obj => move |row| {
+                        let mut collapsed_groups = obj.imp().collapsed_groups.borrow_mut();
+                        if row.is_expanded() {
+                            collapsed_groups.remove(&row.key());
+                        } else {
+                            collapsed_groups.insert(row.key());
+                        }
+                        drop(collapsed_groups);
+                        obj.update_shown_entries();
+                    }));
+                    row.upcast::<gtk::ListBoxRow>()
+                }
+                vector_tree::Item::Vector(index) => {
+                    self.new_filter_row(&other_vectors[*index]).upcast()
+                }
+            };
+            if let Some(child) = row.child() {
+                child.set_margin_start(entry.depth as i32 * TREE_INDENT);
+            }
+            imp.filter_list_box.append(&row);
+        }
+        drop(other_vectors);
+
+        imp.vector_tree.replace(tree);
+        self.update_shown_entries();
+        self.update_measured_names();
+    }
+
+    fn new_filter_row(&self, styled: &StyledVector) -> PlotViewFilterRow {
+        let row = PlotViewFilterRow::new(
+            &styled.vector.name,
+            styled.color(Theme::current()),
+            matches!(styled.origin, Origin::Expression { .. }),
+        );
+        row.set_custom_color(styled.custom_color);
+        row.set_line_style(styled.line_style);
+        row.set_is_active(styled.is_visible);
+
+        row.connect_is_active_notify(clone!(@weak self as obj => move |row| {
+            let mut other_vectors = obj.imp().other_vectors.borrow_mut();
+            let styled = other_vectors
+                .iter_mut()
+                .find(|v| v.vector.name == row.name())
+                .expect("vector must exist");
+            if styled.is_visible == row.is_active() {
+                return;
+            }
+            styled.is_visible = row.is_active();
+            drop(other_vectors);
+            obj.update_spectra();
+            obj.update_paintable();
+        }));
+        row.connect_custom_color_notify(clone!(@weak self as obj => move |row| {
+            let mut other_vectors = obj.imp().other_vectors.borrow_mut();
+            let styled = other_vectors
+                .iter_mut()
+                .find(|v| v.vector.name == row.name())
+                .expect("vector must exist");
+            styled.custom_color = row.custom_color();
+            row.set_color(styled.color(Theme::current()));
+            drop(other_vectors);
+            obj.update_paintable();
+        }));
+        row.connect_line_style_notify(clone!(@weak self as obj => move |row| {
+            obj.imp()
+                .other_vectors
+                .borrow_mut()
+                .iter_mut()
+                .find(|v| v.vector.name == row.name())
+                .expect("vector must exist")
+                .line_style = row.line_style();
+            obj.update_paintable();
+        }));
+        row.connect_remove_requested(clone!(@weak self as obj => move |row| {
+            obj.remove_expression(&row.name());
+        }));
+        row
+    }
+}
+
+/// Returns a spectrum without data, shown for `name` until its spectrum is
+/// taken.
+fn empty_spectrum(name: &str) -> Rc<Vector> {
+    Rc::new(Vector {
+        name: name.to_string(),
+        vtype: VectorType::Decibel,
+        data: VectorData::Real(Vec::new()),
+    })
+}
+
+/// Returns the message a thread panicked with, if any.
+fn panic_message(panic: &Box<dyn Any + Send>) -> Option<&str> {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+}
+
+/// Returns the title of the group of vectors of `kind`.
+fn kind_title(kind: Kind) -> String {
+    match kind {
+        Kind::Voltage => gettext("Voltages"),
+        Kind::Current => gettext("Currents"),
+        Kind::Device => gettext("Device Quantities"),
+        Kind::Other => gettext("Other"),
+    }
+}
+
+/// Returns whether a vector name matches the lowercase search text.
+fn matches_search(name: &str, search: &str) -> bool {
+    name.to_lowercase().contains(search)
+}
+
+/// Returns the name chosen in a dropdown of vector names.
+fn selected_name(dropdown: &gtk::DropDown) -> Option<String> {
+    dropdown
+        .selected_item()
+        .and_downcast::<gtk::StringObject>()
+        .map(|item| item.string().to_string())
 }
 
-fn to_plotters_color(rgba: gdk::RGBA) -> RGBAColor {
-    RGBAColor(
-        (rgba.red() * 255.0) as u8,
-        (rgba.green() * 255.0) as u8,
-        (rgba.blue() * 255.0) as u8,
-        rgba.alpha() as f64,
-    )
+/// Formats a readout value, or a dash if there is none.
+fn format_readout(value: Option<f64>, unit: &str) -> String {
+    value.map_or_else(|| "—".to_string(), |value| si::format(value, unit))
 }
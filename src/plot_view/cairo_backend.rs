@@ -0,0 +1,230 @@
+//! Drawing charts on cairo surfaces, which, unlike GTK snapshots, can be
+//! drawn on any thread and saved as images.
+
+use std::f64::consts::PI;
+
+use gtk::{cairo, pango};
+use plotters_backend::{
+    text_anchor::{HPos, VPos},
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
+    FontStyle, FontTransform,
+};
+
+type Result<T = ()> = std::result::Result<T, DrawingErrorKind<cairo::Error>>;
+
+/// Backend that draws on a cairo context.
+pub struct CairoBackend<'a> {
+    cr: &'a cairo::Context,
+    layout: pango::Layout,
+    size: (u32, u32),
+}
+
+impl<'a> CairoBackend<'a> {
+    /// Creates a backend drawing on `cr`, over `size` units of its user
+    /// space, which may be scaled to the pixels of the surface.
+    pub fn new(cr: &'a cairo::Context, size: (u32, u32)) -> Self {
+        Self {
+            cr,
+            layout: pangocairo::functions::create_layout(cr),
+            size,
+        }
+    }
+
+    fn set_color(&self, color: BackendColor) {
+        self.cr.set_source_rgba(
+            color.rgb.0 as f64 / 255.0,
+            color.rgb.1 as f64 / 255.0,
+            color.rgb.2 as f64 / 255.0,
+            color.alpha,
+        );
+    }
+
+    fn stroke(&self, style: &impl BackendStyle) -> Result {
+        self.set_color(style.color());
+        self.cr.set_line_width(style.stroke_width() as f64);
+        self.cr.stroke().map_err(DrawingErrorKind::DrawingError)
+    }
+
+    fn fill(&self, color: BackendColor) -> Result {
+        self.set_color(color);
+        self.cr.fill().map_err(DrawingErrorKind::DrawingError)
+    }
+
+    fn set_text(&self, text: &str, style: &impl BackendTextStyle) {
+        let mut font_desc = pango::FontDescription::new();
+        font_desc.set_family(style.family().as_str());
+        font_desc.set_absolute_size(style.size() * pango::SCALE as f64);
+        match style.style() {
+            FontStyle::Normal => font_desc.set_style(pango::Style::Normal),
+            FontStyle::Bold => font_desc.set_weight(pango::Weight::Bold),
+            FontStyle::Italic => font_desc.set_style(pango::Style::Italic),
+            FontStyle::Oblique => font_desc.set_style(pango::Style::Oblique),
+        }
+        self.layout.set_font_description(Some(&font_desc));
+        self.layout.set_text(text);
+    }
+}
+
+/// Returns the center of the pixel at `coord`, so that one pixel wide lines
+/// cover whole pixels.
+fn pixel_center((x, y): BackendCoord) -> (f64, f64) {
+    (x as f64 + 0.5, y as f64 + 0.5)
+}
+
+impl DrawingBackend for CairoBackend<'_> {
+    type ErrorType = cairo::Error;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn ensure_prepared(&mut self) -> Result {
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result {
+        Ok(())
+    }
+
+    fn draw_pixel(&mut self, (x, y): BackendCoord, color: BackendColor) -> Result {
+        self.cr.rectangle(x as f64, y as f64, 1.0, 1.0);
+        self.fill(color)
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result {
+        self.draw_path([from, to], style)
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        upper_left: BackendCoord,
+        bottom_right: BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result {
+        if fill {
+            self.cr.rectangle(
+                upper_left.0 as f64,
+                upper_left.1 as f64,
+                (bottom_right.0 - upper_left.0) as f64,
+                (bottom_right.1 - upper_left.1) as f64,
+            );
+            self.fill(style.color())
+        } else {
+            let (x, y) = pixel_center(upper_left);
+            self.cr.rectangle(
+                x,
+                y,
+                (bottom_right.0 - upper_left.0) as f64,
+                (bottom_right.1 - upper_left.1) as f64,
+            );
+            self.stroke(style)
+        }
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result {
+        let mut points = path.into_iter().map(pixel_center);
+        let Some((x, y)) = points.next() else {
+            return Ok(());
+        };
+        self.cr.move_to(x, y);
+        for (x, y) in points {
+            self.cr.line_to(x, y);
+        }
+        self.stroke(style)
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vertices: I,
+        style: &S,
+    ) -> Result {
+        let mut vertices = vertices.into_iter();
+        let Some((x, y)) = vertices.next() else {
+            return Ok(());
+        };
+        self.cr.move_to(x as f64, y as f64);
+        for (x, y) in vertices {
+            self.cr.line_to(x as f64, y as f64);
+        }
+        self.cr.close_path();
+        self.fill(style.color())
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        center: BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result {
+        self.cr.new_sub_path();
+        self.cr.arc(
+            center.0 as f64,
+            center.1 as f64,
+            radius as f64,
+            0.0,
+            2.0 * PI,
+        );
+        if fill {
+            self.fill(style.color())
+        } else {
+            self.stroke(style)
+        }
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32)> {
+        self.set_text(text, style);
+        let (width, height) = self.layout.pixel_size();
+        Ok((width as u32, height as u32))
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        (x, y): BackendCoord,
+    ) -> Result {
+        self.set_text(text, style);
+
+        let (_, extents) = self.layout.pixel_extents();
+        let (width, height) = (extents.width() as f64, extents.height() as f64);
+        let dx = match style.anchor().h_pos {
+            HPos::Left => 0.0,
+            HPos::Center => -width / 2.0,
+            HPos::Right => -width,
+        };
+        let dy = match style.anchor().v_pos {
+            VPos::Top => 0.0,
+            VPos::Center => -height / 2.0,
+            VPos::Bottom => -height,
+        };
+        let degrees = match style.transform() {
+            FontTransform::None => 0.0,
+            FontTransform::Rotate90 => 90.0,
+            FontTransform::Rotate180 => 180.0,
+            FontTransform::Rotate270 => 270.0,
+        };
+
+        self.cr.save().map_err(DrawingErrorKind::DrawingError)?;
+        self.cr.translate(x as f64, y as f64);
+        self.cr.rotate(f64::to_radians(degrees));
+        self.cr.move_to(dx, dy);
+        self.set_color(style.color());
+        pangocairo::functions::show_layout(self.cr, &self.layout);
+        self.cr.restore().map_err(DrawingErrorKind::DrawingError)
+    }
+}
@@ -0,0 +1,770 @@
+//! Drawing charts with plotters, away from the widget state so that it can
+//! happen on another thread.
+
+use std::{collections::HashMap, sync::Arc};
+
+use anyhow::Result;
+use gtk::{gdk, glib, prelude::*};
+use plotters::{
+    coord::{
+        ranged1d::{DefaultFormatting, KeyPointHint, Ranged},
+        types::RangedCoordf64,
+        Shift,
+    },
+    prelude::DrawingArea,
+    style::RGBAColor,
+};
+
+use super::{
+    cairo_backend::CairoBackend, cursors, dash, lod, theme::Theme, ticks, viewport, viewport::Pane,
+    ComplexMode, DataPart, LineStyle, StyledVector,
+};
+use crate::{
+    export::ImageFormat,
+    si,
+    vector::{Vector, VectorType},
+};
+
+/// Maximum number of ticks on each axis.
+const X_LABELS: usize = 10;
+const Y_LABELS: usize = 10;
+
+/// Resolution at which a pixel of the chart is a pixel of an exported image.
+pub const BASE_DPI: f64 = 96.0;
+
+/// Resolution of the PDF and SVG coordinate space, in points per inch.
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// The X axis shared by the panes of a chart.
+struct XAxis<'a> {
+    /// `None` to leave the axis undescribed, as when another pane below
+    /// describes it.
+    name: Option<&'a str>,
+    unit: &'a str,
+    /// Whether the chart coordinates are the logarithm of the scale.
+    is_log: bool,
+    range: (f64, f64),
+}
+
+/// A Y axis of a chart pane, and the traces drawn along it.
+pub struct YAxis {
+    pub name: Option<&'static str>,
+    pub unit: &'static str,
+    /// Whether the trace data is the logarithm of the values.
+    pub is_log: bool,
+    /// `None` to fit the range to the data within the X range.
+    pub range: Option<(f64, f64)>,
+    pub traces: Vec<Trace>,
+}
+
+/// A line to draw on a single chart pane.
+pub struct Trace {
+    pub name: String,
+    pub color: gdk::RGBA,
+    pub line_style: LineStyle,
+    /// The X chart coordinates of the samples, which are those of the scale
+    /// unless the trace is decimated.
+    pub x_data: Arc<[f64]>,
+    pub data: Arc<[f64]>,
+}
+
+/// What the chart is drawn with, shared with the view so that it can be
+/// drawn on another thread.
+pub struct Scene {
+    pub theme: Theme,
+    pub x_name: String,
+    pub x_unit: &'static str,
+    pub x_is_log: bool,
+    /// Whether the X chart coordinates never decrease, which traces can only
+    /// be decimated if they don't.
+    pub x_is_increasing: bool,
+    pub x_range: (f64, f64),
+    /// The Y axes, with the ranges that are `None` fitted once drawn.
+    pub y_axes: Vec<YAxis>,
+    /// Number of Y axes sharing each pane, on either side of it.
+    pub axes_per_pane: usize,
+    /// Vertical cursors in chart coordinates.
+    pub x_cursors: Vec<f64>,
+    /// Horizontal cursors in chart coordinates of the first Y axis.
+    pub y_cursors: Vec<f64>,
+}
+
+/// The chart drawn on an image.
+pub struct Rendered {
+    /// Size of the chart, in chart pixels.
+    pub size: (u32, u32),
+    /// Pixels of the image per chart pixel.
+    pub scale_factor: i32,
+    /// The pixels, in the premultiplied native-endian ARGB format of cairo.
+    pub data: Vec<u8>,
+    pub stride: usize,
+    pub panes: Vec<Pane>,
+}
+
+impl Rendered {
+    pub fn texture(self) -> gdk::Texture {
+        let format = if cfg!(target_endian = "little") {
+            gdk::MemoryFormat::B8g8r8a8Premultiplied
+        } else {
+            gdk::MemoryFormat::A8r8g8b8Premultiplied
+        };
+        gdk::MemoryTexture::new(
+            self.size.0 as i32 * self.scale_factor,
+            self.size.1 as i32 * self.scale_factor,
+            format,
+            &glib::Bytes::from_owned(self.data),
+            self.stride,
+        )
+        .upcast()
+    }
+}
+
+/// Identifies the data of a decimated trace, and the view it was decimated
+/// for.
+#[derive(PartialEq, Eq, Hash)]
+struct LodKey {
+    /// Addresses of the X and Y data, which the cache keeps alive.
+    data: (usize, usize),
+    /// The bits of the X range, which is the zoom level.
+    x_range: (u64, u64),
+    columns: usize,
+}
+
+/// A decimated trace, along with the data it was decimated from.
+struct Decimation {
+    _source: (Arc<[f64]>, Arc<[f64]>),
+    x_data: Arc<[f64]>,
+    data: Arc<[f64]>,
+}
+
+/// The decimated traces of the last render, reused by the next one.
+#[derive(Default)]
+pub struct LodCache(HashMap<LodKey, Decimation>);
+
+/// Reduces the traces of `scene` to the samples that make a difference at a
+/// width of `columns` pixels, reusing those of `cache`. Only the traces of
+/// `scene` are kept in `cache`.
+pub fn decimate(scene: &mut Scene, columns: usize, cache: &mut LodCache) {
+    let mut new_cache = HashMap::new();
+    if scene.x_is_increasing {
+        let x_range = scene.x_range;
+        for trace in scene
+            .y_axes
+            .iter_mut()
+            .flat_map(|y_axis| &mut y_axis.traces)
+        {
+            let key = LodKey {
+                data: (trace.x_data.as_ptr() as usize, trace.data.as_ptr() as usize),
+                x_range: (x_range.0.to_bits(), x_range.1.to_bits()),
+                columns,
+            };
+            let decimation = cache.0.remove(&key).unwrap_or_else(|| {
+                let decimated = lod::min_max(&trace.x_data, &trace.data, x_range, columns);
+                Decimation {
+                    _source: (trace.x_data.clone(), trace.data.clone()),
+                    x_data: decimated.xs.into(),
+                    data: decimated.ys.into(),
+                }
+            });
+            trace.x_data = decimation.x_data.clone();
+            trace.data = decimation.data.clone();
+            new_cache.insert(key, decimation);
+        }
+    }
+    cache.0 = new_cache;
+}
+
+/// Draws `scene` at `size` chart pixels, each `scale_factor` pixels wide.
+pub fn render(scene: &Scene, size: (u32, u32), scale_factor: i32) -> Result<Rendered> {
+    let mut surface = cairo::ImageSurface::create(
+        cairo::Format::ARgb32,
+        size.0 as i32 * scale_factor,
+        size.1 as i32 * scale_factor,
+    )?;
+
+    let cr = cairo::Context::new(&surface)?;
+    cr.scale(scale_factor as f64, scale_factor as f64);
+    let panes = draw_scene(scene, &cr, size)?;
+    drop(cr);
+
+    surface.flush();
+    let stride = surface.stride() as usize;
+    let data = surface.data()?.to_vec();
+
+    Ok(Rendered {
+        size,
+        scale_factor,
+        data,
+        stride,
+        panes,
+    })
+}
+
+/// Draws `scene` as an image of `format`, over `size` chart pixels, with
+/// `dpi` pixels per inch if it is a PNG.
+pub fn render_image(
+    scene: &Scene,
+    format: ImageFormat,
+    size: (u32, u32),
+    dpi: f64,
+) -> Result<Vec<u8>> {
+    let (width, height) = size;
+    let bytes = match format {
+        ImageFormat::Png => {
+            let scale = dpi / BASE_DPI;
+            let surface = cairo::ImageSurface::create(
+                cairo::Format::ARgb32,
+                (width as f64 * scale).ceil() as i32,
+                (height as f64 * scale).ceil() as i32,
+            )?;
+
+            let cr = cairo::Context::new(&surface)?;
+            cr.scale(scale, scale);
+            draw_scene(scene, &cr, (width, height))?;
+            drop(cr);
+
+            let mut bytes = Vec::new();
+            surface.write_to_png(&mut bytes)?;
+            bytes
+        }
+        ImageFormat::Svg | ImageFormat::Pdf => {
+            // Vector formats are sized in points, so that the chart has
+            // the same physical size as on a screen at the base DPI.
+            let scale = POINTS_PER_INCH / BASE_DPI;
+            let (page_width, page_height) = (width as f64 * scale, height as f64 * scale);
+            let surface: cairo::Surface = if format == ImageFormat::Svg {
+                (*cairo::SvgSurface::for_stream(page_width, page_height, Vec::<u8>::new())?).clone()
+            } else {
+                (*cairo::PdfSurface::for_stream(page_width, page_height, Vec::<u8>::new())?).clone()
+            };
+            let cr = cairo::Context::new(&surface)?;
+            cr.scale(scale, scale);
+            draw_scene(scene, &cr, (width, height))?;
+            drop(cr);
+
+            *surface
+                .finish_output_stream()
+                .map_err(std::io::Error::from)?
+                .downcast::<Vec<u8>>()
+                .unwrap()
+        }
+    };
+
+    Ok(bytes)
+}
+
+/// Draws `scene` on `cr`, over `size` units of its user space. Returns where
+/// each of its Y axes was drawn.
+fn draw_scene(scene: &Scene, cr: &cairo::Context, size: (u32, u32)) -> Result<Vec<Pane>> {
+    use plotters::prelude::*;
+
+    let root_area = CairoBackend::new(cr, size).into_drawing_area();
+    root_area.fill(&to_plotters_color(scene.theme.background))?;
+
+    let pane_count = scene.y_axes.len().div_ceil(scene.axes_per_pane);
+    let areas = root_area.split_evenly((pane_count, 1));
+
+    let mut panes = Vec::new();
+    for (pane_index, (area, pane_y_axes)) in areas
+        .iter()
+        .zip(scene.y_axes.chunks(scene.axes_per_pane))
+        .enumerate()
+    {
+        // Panes share the X axis, which is described below the last.
+        let is_bottom = pane_index == pane_count - 1;
+        panes.extend(draw_chart(
+            area,
+            &scene.theme,
+            &XAxis {
+                name: is_bottom.then_some(scene.x_name.as_str()),
+                unit: scene.x_unit,
+                is_log: scene.x_is_log,
+                range: scene.x_range,
+            },
+            &pane_y_axes[0],
+            pane_y_axes.get(1),
+            &scene.x_cursors,
+            if pane_index == 0 {
+                &scene.y_cursors
+            } else {
+                &[]
+            },
+        )?);
+    }
+
+    root_area.present()?;
+
+    Ok(panes)
+}
+
+/// Draws a pane with the traces of its Y axes and the cursors, fitting the
+/// ranges that are `None` to the data. The Y ranges are fitted to the samples
+/// within the X range. Cursors are given in chart coordinates, with the
+/// horizontal ones on the primary Y axis.
+///
+/// Returns where the primary Y axis was drawn, followed by the secondary one
+/// on the right, if any.
+fn draw_chart(
+    area: &DrawingArea<CairoBackend<'_>, Shift>,
+    theme: &Theme,
+    x_axis: &XAxis<'_>,
+    y_axis: &YAxis,
+    secondary_y_axis: Option<&YAxis>,
+    x_cursors: &[f64],
+    y_cursors: &[f64],
+) -> Result<Vec<Pane>> {
+    use plotters::prelude::*;
+
+    let (x_min, x_max) = x_axis.range;
+    let (y_min, y_max) = fit_y_range((x_min, x_max), y_axis);
+    let secondary = secondary_y_axis.map(|axis| (axis, fit_y_range((x_min, x_max), axis)));
+
+    let mut builder = ChartBuilder::on(area);
+    builder
+        .margin_left(10)
+        .margin_right(20)
+        .margin_top(20)
+        .margin_bottom(10)
+        .x_label_area_size(40)
+        .y_label_area_size(50);
+    if secondary.is_some() {
+        builder.right_y_label_area_size(50);
+    }
+    let mut cc = builder.build_cartesian_2d(
+        axis_coord((x_min, x_max), x_axis.is_log, X_LABELS),
+        axis_coord((y_min, y_max), y_axis.is_log, Y_LABELS),
+    )?;
+
+    let x_labels = AxisLabels::new((x_min, x_max), x_axis.is_log, x_axis.unit, X_LABELS);
+    let x_label_formatter = |v: &f64| x_labels.format(*v);
+    let y_labels = AxisLabels::new((y_min, y_max), y_axis.is_log, y_axis.unit, Y_LABELS);
+    let y_label_formatter = |v: &f64| y_labels.format(*v);
+
+    let foreground = to_plotters_color(theme.foreground);
+    let mut mesh = cc.configure_mesh();
+    mesh.x_label_formatter(&x_label_formatter)
+        .y_label_formatter(&y_label_formatter)
+        .axis_style(foreground)
+        .bold_line_style(to_plotters_color(theme.bold_grid))
+        .light_line_style(to_plotters_color(theme.light_grid))
+        .label_style(("sans-serif", 12).into_font().color(&foreground));
+    if let Some(x_name) = x_axis.name {
+        mesh.x_desc(axis_desc(x_name, &x_labels.unit));
+    }
+    if let Some(y_desc) = y_labels.y_desc(y_axis) {
+        mesh.y_desc(y_desc);
+    }
+    mesh.draw()?;
+
+    let (x_pixels, y_pixels) = cc.plotting_area().get_pixel_range();
+    let pixels_per = (
+        (x_pixels.end - x_pixels.start) as f64 / (x_max - x_min),
+        (y_pixels.end - y_pixels.start) as f64 / (y_max - y_min),
+    );
+    let can_dash = pixels_per.0.is_finite() && pixels_per.1.is_finite();
+
+    // The traces of the secondary axis are drawn in the coordinates of the
+    // primary one, which only the labels tell apart.
+    let secondary_traces = secondary.map_or_else(Vec::new, |(axis, (s_min, s_max))| {
+        let scale = if s_max > s_min {
+            (y_max - y_min) / (s_max - s_min)
+        } else {
+            0.0
+        };
+        let offset = if s_max > s_min {
+            y_min
+        } else {
+            (y_min + y_max) / 2.0
+        };
+        axis.traces
+            .iter()
+            .map(|trace| Trace {
+                name: trace.name.clone(),
+                color: trace.color,
+                line_style: trace.line_style,
+                x_data: trace.x_data.clone(),
+                data: trace
+                    .data
+                    .iter()
+                    .map(|&v| {
+                        // Non-finite values are kept, as they break the line.
+                        if v.is_finite() {
+                            offset + (v - s_min) * scale
+                        } else {
+                            v
+                        }
+                    })
+                    .collect(),
+            })
+            .collect()
+    });
+
+    for trace in y_axis.traces.iter().chain(&secondary_traces) {
+        let style = ShapeStyle {
+            color: to_plotters_color(trace.color),
+            filled: true,
+            stroke_width: 1,
+        };
+        let dash_pattern = trace.line_style.dash_pattern();
+        // Plotters doesn't clip to the plotting area, so lines outside a
+        // zoomed-in range would be drawn over the axes.
+        let mut lines = viewport::clip_line(
+            trace.x_data.iter().copied().zip(trace.data.iter().copied()),
+            (x_min, x_max),
+            (y_min, y_max),
+        );
+        if !dash_pattern.is_empty() && can_dash {
+            lines = lines
+                .iter()
+                .flat_map(|line| dash::dash(line, dash_pattern, pixels_per))
+                .collect();
+        }
+        cc.draw_series(lines.into_iter().map(|line| PathElement::new(line, style)))?
+            .label(&trace.name)
+            .legend(move |(x, y)| {
+                // The sample is short enough for four dashes of any pattern.
+                let mut dashes = dash::dash(&[(0.0, 0.0), (20.0, 0.0)], dash_pattern, (1.0, 1.0))
+                    .into_iter()
+                    .map(|dash| {
+                        dash.into_iter()
+                            .map(|(dx, _)| (dx.round() as i32, 0))
+                            .collect::<Vec<_>>()
+                    });
+                let mut next_dash = || PathElement::new(dashes.next().unwrap_or_default(), style);
+                EmptyElement::at((x, y)) + next_dash() + next_dash() + next_dash() + next_dash()
+            });
+    }
+
+    if !y_axis.traces.is_empty() || !secondary_traces.is_empty() {
+        cc.configure_series_labels()
+            .position(SeriesLabelPosition::UpperRight)
+            .background_style(to_plotters_color(theme.legend_background))
+            .border_style(to_plotters_color(theme.bold_grid))
+            .label_font(("sans-serif", 12).into_font().color(&foreground))
+            .draw()?;
+    }
+
+    let vertical_cursor_style = ShapeStyle::from(to_plotters_color(theme.vertical_cursor));
+    let vertical_cursor_font = ("sans-serif", 14)
+        .into_font()
+        .color(&to_plotters_color(theme.vertical_cursor));
+    for (label, &x) in cursors::VERTICAL_LABELS.iter().zip(x_cursors) {
+        if !(x_min..=x_max).contains(&x) {
+            continue;
+        }
+        cc.draw_series([PathElement::new(
+            [(x, y_min), (x, y_max)],
+            vertical_cursor_style,
+        )])?;
+        cc.draw_series([
+            EmptyElement::at((x, y_max)) + Text::new(*label, (4, 2), vertical_cursor_font.clone())
+        ])?;
+    }
+
+    let horizontal_cursor_style = ShapeStyle::from(to_plotters_color(theme.horizontal_cursor));
+    let horizontal_cursor_font = ("sans-serif", 14)
+        .into_font()
+        .color(&to_plotters_color(theme.horizontal_cursor));
+    for (label, &y) in cursors::HORIZONTAL_LABELS.iter().zip(y_cursors) {
+        if !(y_min..=y_max).contains(&y) {
+            continue;
+        }
+        cc.draw_series([PathElement::new(
+            [(x_min, y), (x_max, y)],
+            horizontal_cursor_style,
+        )])?;
+        cc.draw_series([EmptyElement::at((x_min, y))
+            + Text::new(*label, (4, -16), horizontal_cursor_font.clone())])?;
+    }
+
+    let pane = Pane {
+        x_pixels: (x_pixels.start, x_pixels.end),
+        y_pixels: (y_pixels.start, y_pixels.end),
+        x_range: (x_min, x_max),
+        y_range: (y_min, y_max),
+        x_is_log: x_axis.is_log,
+        y_is_log: y_axis.is_log,
+    };
+    let Some((secondary_y_axis, (s_min, s_max))) = secondary else {
+        return Ok(vec![pane]);
+    };
+
+    let s_labels = AxisLabels::new(
+        (s_min, s_max),
+        secondary_y_axis.is_log,
+        secondary_y_axis.unit,
+        Y_LABELS,
+    );
+    let s_label_formatter = |v: &f64| s_labels.format(*v);
+    let mut cc = cc.set_secondary_coord(
+        axis_coord((x_min, x_max), x_axis.is_log, X_LABELS),
+        axis_coord((s_min, s_max), secondary_y_axis.is_log, Y_LABELS),
+    );
+    let mut secondary_mesh = cc.configure_secondary_axes();
+    secondary_mesh
+        .y_label_formatter(&s_label_formatter)
+        .axis_style(foreground)
+        .label_style(("sans-serif", 12).into_font().color(&foreground));
+    if let Some(y_desc) = s_labels.y_desc(secondary_y_axis) {
+        secondary_mesh.y_desc(y_desc);
+    }
+    secondary_mesh.draw()?;
+
+    Ok(vec![
+        pane,
+        Pane {
+            y_range: (s_min, s_max),
+            y_is_log: secondary_y_axis.is_log,
+            ..pane
+        },
+    ])
+}
+
+/// Returns the Y range of `y_axis`, fitting it to the samples within the X
+/// range if it is `None`.
+fn fit_y_range(x_range: (f64, f64), y_axis: &YAxis) -> (f64, f64) {
+    let traces = &y_axis.traces;
+    y_axis.range.unwrap_or_else(|| {
+        traces
+            .iter()
+            .filter_map(|trace| {
+                viewport::y_range_within(&trace.x_data, trace.data.iter().copied(), x_range)
+            })
+            .reduce(|(a_min, a_max), (b_min, b_max)| {
+                (f64::min(a_min, b_min), f64::max(a_max, b_max))
+            })
+            .unwrap_or_else(|| {
+                finite_min_max(traces.iter().flat_map(|trace| trace.data.iter().copied()))
+            })
+    })
+}
+
+/// Formats the tick labels of an axis, and its unit with the SI prefix they
+/// share. Log axes span decades, so each tick gets its own prefix instead.
+struct AxisLabels {
+    scale: si::AxisScale,
+    is_log: bool,
+    unit: String,
+}
+
+impl AxisLabels {
+    fn new(range: (f64, f64), is_log: bool, unit: &str, max_ticks: usize) -> Self {
+        let scale = si::AxisScale::new(range, max_ticks, unit);
+        Self {
+            unit: if is_log {
+                unit.to_string()
+            } else {
+                scale.unit(unit)
+            },
+            scale,
+            is_log,
+        }
+    }
+
+    fn format(&self, v: f64) -> String {
+        if self.is_log {
+            format_log_tick(v)
+        } else {
+            self.scale.format(v)
+        }
+    }
+
+    /// Returns the description of `y_axis`, or `None` if there is nothing to
+    /// describe it with.
+    fn y_desc(&self, y_axis: &YAxis) -> Option<String> {
+        match y_axis.name {
+            Some(name) => Some(axis_desc(name, &self.unit)),
+            None => (!self.unit.is_empty()).then(|| self.unit.clone()),
+        }
+    }
+}
+
+/// Groups the traces of `vectors` into Y axes, one for each unit, or for
+/// each key traces were moved to. Returns the keys of the axes, and the axes
+/// with their ranges yet to be set.
+///
+/// The data of the traces is the `chart_data` of the part of a vector they
+/// draw, on a log axis or not.
+pub fn group_y_axes<'a>(
+    vectors: impl Iterator<Item = &'a StyledVector>,
+    x_data: &Arc<[f64]>,
+    complex_mode: ComplexMode,
+    log_y: bool,
+    theme: &Theme,
+    mut chart_data: impl FnMut(&Vector, DataPart, bool) -> Arc<[f64]>,
+) -> (Vec<String>, Vec<YAxis>) {
+    let mut keys = Vec::new();
+    let mut y_axes: Vec<YAxis> = Vec::new();
+    // The data of the traces is only taken once it is known whether their
+    // axis is a log one.
+    let mut sources: Vec<Vec<(&'a StyledVector, DataPart)>> = Vec::new();
+    let mut add_trace = |key: String,
+                         name: Option<&'static str>,
+                         unit: &'static str,
+                         source: (&'a StyledVector, DataPart)| {
+        match keys.iter().position(|k| *k == key) {
+            Some(index) => {
+                // Axes shared by different quantities are only described by
+                // what they have in common.
+                let y_axis: &mut YAxis = &mut y_axes[index];
+                if y_axis.name != name {
+                    y_axis.name = None;
+                }
+                if y_axis.unit != unit {
+                    y_axis.unit = "";
+                }
+                sources[index].push(source);
+            }
+            None => {
+                keys.push(key);
+                y_axes.push(YAxis {
+                    name,
+                    unit,
+                    is_log: false,
+                    range: None,
+                    traces: Vec::new(),
+                });
+                sources.push(vec![source]);
+            }
+        }
+    };
+
+    for styled in vectors {
+        let vector = styled.shown();
+        let unit = styled.unit();
+        if vector.data.is_complex() {
+            let parts = [DataPart::Upper(complex_mode), DataPart::Lower(complex_mode)];
+            for ((name, unit), part) in complex_mode.y_axes(unit).into_iter().zip(parts) {
+                add_trace(
+                    format!("{} {}", name, unit),
+                    Some(name),
+                    unit,
+                    (styled, part),
+                );
+            }
+        } else {
+            let key = styled
+                .axis_key
+                .clone()
+                .unwrap_or_else(|| real_axis_key(vector.vtype, unit));
+            // Units derived by expressions may have no type to be named by.
+            let name = (vector.vtype.unit() == unit).then(|| vector.vtype.name());
+            add_trace(key, name, unit, (styled, DataPart::Real));
+        }
+    }
+
+    for (y_axis, sources) in y_axes.iter_mut().zip(sources) {
+        // Neither decibels nor angles have a meaningful logarithm.
+        y_axis.is_log = log_y && !matches!(y_axis.unit, "dB" | "°");
+        y_axis.traces = sources
+            .into_iter()
+            .map(|(styled, part)| Trace {
+                name: styled.shown().name.clone(),
+                color: styled.color(theme),
+                line_style: styled.line_style,
+                x_data: x_data.clone(),
+                data: chart_data(styled.shown(), part, y_axis.is_log),
+            })
+            .collect();
+    }
+
+    (keys, y_axes)
+}
+
+/// Returns the key of the Y axis real vectors of `vtype` in `unit` are
+/// drawn on.
+pub fn real_axis_key(vtype: VectorType, unit: &str) -> String {
+    match unit {
+        "" => vtype.name().to_string(),
+        unit => unit.to_string(),
+    }
+}
+
+/// A linear axis with the ticks of [`ticks`], as plotters can't place them
+/// at the decades of log axes drawn over the logarithm of the data.
+struct TickedCoord {
+    inner: RangedCoordf64,
+    ticks: ticks::Ticks,
+}
+
+impl Ranged for TickedCoord {
+    type FormatOption = DefaultFormatting;
+    type ValueType = f64;
+
+    fn map(&self, value: &f64, limit: (i32, i32)) -> i32 {
+        self.inner.map(value, limit)
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f64> {
+        if hint.weight().allow_light_points() {
+            self.ticks.light.clone()
+        } else {
+            self.ticks.bold.clone()
+        }
+    }
+
+    fn range(&self) -> std::ops::Range<f64> {
+        self.inner.range()
+    }
+}
+
+/// Returns the coordinate of an axis over `range`, with ticks at round
+/// values, or at decades on log axes.
+fn axis_coord((min, max): (f64, f64), is_log: bool, max_ticks: usize) -> TickedCoord {
+    TickedCoord {
+        inner: (min..max).into(),
+        ticks: if is_log {
+            ticks::log((min, max), max_ticks)
+        } else {
+            ticks::linear((min, max), max_ticks)
+        },
+    }
+}
+
+/// Formats a tick of a log axis, given as the logarithm of its value.
+fn format_log_tick(v: f64) -> String {
+    si::format_with_precision(10_f64.powf(v), "", 3)
+}
+
+/// Returns the range of the finite values of `x_data`, which are those at
+/// its ends if it `is_increasing`.
+pub fn fit_x_range(x_data: &[f64], is_increasing: bool) -> (f64, f64) {
+    if is_increasing {
+        let mut finite = x_data.iter().copied().filter(|x| x.is_finite());
+        if let Some(min) = finite.next() {
+            let max = x_data.iter().copied().rfind(|x| x.is_finite());
+            return (min, max.unwrap_or(min));
+        }
+    }
+    finite_min_max(x_data.iter().copied())
+}
+
+/// Returns the minimum and maximum of the finite values, or `(0.0, 0.0)` if
+/// there are none.
+fn finite_min_max(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values
+        .filter(|v| v.is_finite())
+        .fold(None, |acc, v| match acc {
+            None => Some((v, v)),
+            Some((min, max)) => Some((f64::min(min, v), f64::max(max, v))),
+        })
+        .unwrap_or((0.0, 0.0))
+}
+
+/// Returns the description of an axis, e.g., `time (ns)`.
+fn axis_desc(name: &str, unit: &str) -> String {
+    if unit.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} ({})", name, unit)
+    }
+}
+
+fn to_plotters_color(rgba: gdk::RGBA) -> RGBAColor {
+    RGBAColor(
+        (rgba.red() * 255.0) as u8,
+        (rgba.green() * 255.0) as u8,
+        (rgba.blue() * 255.0) as u8,
+        rgba.alpha() as f64,
+    )
+}
@@ -0,0 +1,70 @@
+//! The paintable the rendered chart is shown with.
+
+use gtk::{gdk, glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::{Cell, RefCell};
+
+    use super::*;
+
+    #[derive(Debug, Default)]
+    pub struct ChartPaintable {
+        pub(super) size: Cell<(u32, u32)>,
+        pub(super) texture: RefCell<Option<gdk::Texture>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ChartPaintable {
+        const NAME: &'static str = "SpicyChartPaintable";
+        type Type = super::ChartPaintable;
+        type Interfaces = (gdk::Paintable,);
+    }
+
+    impl ObjectImpl for ChartPaintable {}
+
+    impl PaintableImpl for ChartPaintable {
+        fn snapshot(&self, snapshot: &gdk::Snapshot, width: f64, height: f64) {
+            if let Some(texture) = self.texture.borrow().as_ref() {
+                texture.snapshot(snapshot, width, height);
+            }
+        }
+
+        fn intrinsic_width(&self) -> i32 {
+            self.size.get().0 as i32
+        }
+
+        fn intrinsic_height(&self) -> i32 {
+            self.size.get().1 as i32
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Shows a texture of the chart, which is larger than the chart on
+    /// screens that scale it, with the size of the chart.
+    pub struct ChartPaintable(ObjectSubclass<imp::ChartPaintable>)
+        @implements gdk::Paintable;
+}
+
+impl ChartPaintable {
+    /// Returns the size of the shown chart, in chart pixels.
+    pub fn size(&self) -> (u32, u32) {
+        self.imp().size.get()
+    }
+
+    /// Shows `texture`, which is the chart drawn at `size` chart pixels.
+    pub fn set_texture(&self, texture: gdk::Texture, size: (u32, u32)) {
+        let imp = self.imp();
+
+        imp.texture.replace(Some(texture));
+        if imp.size.replace(size) != size {
+            self.invalidate_size();
+        }
+        self.invalidate_contents();
+    }
+
+    pub fn clear(&self) {
+        self.imp().texture.replace(None);
+        self.invalidate_contents();
+    }
+}
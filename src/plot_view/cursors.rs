@@ -0,0 +1,140 @@
+//! Reading vectors at arbitrary positions of the scale, for measurement
+//! cursors and hovering.
+
+/// Labels of the vertical cursors, then of the horizontal ones.
+pub const VERTICAL_LABELS: [&str; 2] = ["A", "B"];
+pub const HORIZONTAL_LABELS: [&str; 2] = ["C", "D"];
+
+/// Linearly interpolates `ys` at `x`, between the first pair of consecutive
+/// samples whose `xs` enclose it. Returns `None` if `x` is outside the data.
+///
+/// The scale doesn't need to be increasing, as with sweeps that go down or
+/// restart, but if `is_increasing` says it never decreases, the samples are
+/// searched in logarithmic time.
+pub fn interpolate(xs: &[f64], ys: &[f64], x: f64, is_increasing: bool) -> Option<f64> {
+    if is_increasing {
+        return interpolate_increasing(xs, ys, x);
+    }
+
+    let points = xs.iter().copied().zip(ys.iter().copied());
+
+    if let Some((_, y)) = points.clone().find(|&(px, _)| px == x) {
+        return Some(y);
+    }
+
+    points
+        .clone()
+        .zip(points.skip(1))
+        .find(|&((x0, _), (x1, _))| (x0 < x && x < x1) || (x1 < x && x < x0))
+        .map(|((x0, y0), (x1, y1))| y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+}
+
+/// Like [`interpolate`], but in logarithmic time, for scales that never
+/// decrease.
+fn interpolate_increasing(xs: &[f64], ys: &[f64], x: f64) -> Option<f64> {
+    let len = xs.len().min(ys.len());
+    let (xs, ys) = (&xs[..len], &ys[..len]);
+
+    let index = xs.partition_point(|&px| px < x);
+    if index < len && xs[index] == x {
+        return Some(ys[index]);
+    }
+    if index == 0 || index == len {
+        return None;
+    }
+
+    let (x0, x1) = (xs[index - 1], xs[index]);
+    let (y0, y1) = (ys[index - 1], ys[index]);
+    Some(y0 + (y1 - y0) * (x - x0) / (x1 - x0))
+}
+
+/// Returns the index of the `x` nearest to `target` among those within
+/// `range`, or `None` if there are none. If `xs` never decreases, only the
+/// two around `target` are compared.
+pub fn nearest_index(
+    xs: &[f64],
+    target: f64,
+    (min, max): (f64, f64),
+    is_increasing: bool,
+) -> Option<usize> {
+    // The nearest within the range is also the nearest to the clamped target.
+    let target = target.max(min).min(max);
+    let candidates = if is_increasing {
+        // NaNs, as the logarithms of negative values, can only come first.
+        let index = xs.partition_point(|&x| x < target || x.is_nan());
+        index.saturating_sub(1)..(index + 1).min(xs.len())
+    } else {
+        0..xs.len()
+    };
+
+    candidates
+        .map(|index| (index, xs[index]))
+        .filter(|&(_, x)| (min..=max).contains(&x))
+        .min_by(|(_, a), (_, b)| (a - target).abs().total_cmp(&(b - target).abs()))
+        .map(|(index, _)| index)
+}
+
+/// Returns the unit of a quotient of quantities (e.g., `V/s`), with `1/`
+/// for a dimensionless numerator, and the usual `Hz` for `1/s`.
+pub fn quotient_unit(numerator: &str, denominator: &str) -> String {
+    match (numerator, denominator) {
+        (_, "") => numerator.to_string(),
+        ("", "s") => "Hz".to_string(),
+        ("", _) => format!("1/{}", denominator),
+        _ => format!("{}/{}", numerator, denominator),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolate_increasing() {
+        let xs = [0.0, 1.0, 3.0];
+        let ys = [0.0, 10.0, 30.0];
+        for is_increasing in [false, true] {
+            assert_eq!(interpolate(&xs, &ys, 0.5, is_increasing), Some(5.0));
+            assert_eq!(interpolate(&xs, &ys, 2.0, is_increasing), Some(20.0));
+            assert_eq!(interpolate(&xs, &ys, 3.0, is_increasing), Some(30.0));
+            assert_eq!(interpolate(&xs, &ys, -1.0, is_increasing), None);
+            assert_eq!(interpolate(&xs, &ys, 4.0, is_increasing), None);
+        }
+    }
+
+    #[test]
+    fn interpolate_decreasing() {
+        let xs = [2.0, 1.0, 0.0];
+        let ys = [4.0, 2.0, 0.0];
+        assert_eq!(interpolate(&xs, &ys, 1.5, false), Some(3.0));
+    }
+
+    #[test]
+    fn nearest_index_within_range() {
+        let xs = [0.0, 1.0, 2.0, f64::NAN, 3.0];
+        assert_eq!(nearest_index(&xs, 1.4, (0.0, 3.0), false), Some(1));
+        assert_eq!(nearest_index(&xs, 2.9, (0.0, 3.0), false), Some(4));
+        assert_eq!(nearest_index(&xs, 2.9, (0.0, 2.5), false), Some(2));
+        assert_eq!(nearest_index(&xs, 1.0, (3.5, 4.0), false), None);
+    }
+
+    #[test]
+    fn nearest_index_increasing() {
+        let xs = [f64::NAN, 0.0, 1.0, 2.0, 3.0];
+        assert_eq!(nearest_index(&xs, 1.4, (0.0, 3.0), true), Some(2));
+        assert_eq!(nearest_index(&xs, 1.6, (0.0, 3.0), true), Some(3));
+        assert_eq!(nearest_index(&xs, 2.9, (0.0, 2.5), true), Some(3));
+        assert_eq!(nearest_index(&xs, -1.0, (-2.0, 3.0), true), Some(1));
+        assert_eq!(nearest_index(&xs, 5.0, (0.0, 9.0), true), Some(4));
+        assert_eq!(nearest_index(&xs, 1.0, (3.5, 4.0), true), None);
+        assert_eq!(nearest_index(&xs, 1.2, (1.1, 1.9), true), None);
+    }
+
+    #[test]
+    fn quotient_units() {
+        assert_eq!(quotient_unit("V", "s"), "V/s");
+        assert_eq!(quotient_unit("", "s"), "Hz");
+        assert_eq!(quotient_unit("", "V"), "1/V");
+        assert_eq!(quotient_unit("A", ""), "A");
+    }
+}
@@ -0,0 +1,92 @@
+//! Splitting lines into dashes, as plotters only draws solid ones.
+
+/// Splits a line into the dashes of `pattern`, which alternates the lengths
+/// of dashes and gaps in pixels. `pixels_per` is how many pixels a unit of
+/// each coordinate spans, so that dashes keep their length on screen.
+pub fn dash(
+    points: &[(f64, f64)],
+    pattern: &[f64],
+    (x_pixels_per, y_pixels_per): (f64, f64),
+) -> Vec<Vec<(f64, f64)>> {
+    if pattern.is_empty() || pattern.iter().sum::<f64>() <= 0.0 {
+        return vec![points.to_vec()];
+    }
+
+    let mut dashes = Vec::new();
+    let mut current = Vec::new();
+    let mut pattern_index = 0;
+    // Length left of the current dash or gap.
+    let mut left = pattern[0];
+    let is_dash = |index: usize| index.is_multiple_of(2);
+
+    if let Some(&first) = points.first() {
+        current.push(first);
+    }
+
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let length = f64::hypot(
+            (end.0 - start.0) * x_pixels_per,
+            (end.1 - start.1) * y_pixels_per,
+        );
+
+        let mut covered = 0.0;
+        while length - covered > left {
+            covered += left;
+            let t = covered / length;
+            let point = (
+                start.0 + (end.0 - start.0) * t,
+                start.1 + (end.1 - start.1) * t,
+            );
+
+            // Ends a dash, or starts one after a gap.
+            current.push(point);
+            if is_dash(pattern_index) {
+                dashes.push(std::mem::take(&mut current));
+            }
+
+            pattern_index = (pattern_index + 1) % pattern.len();
+            left = pattern[pattern_index];
+        }
+        left -= length - covered;
+
+        if is_dash(pattern_index) {
+            current.push(end);
+        }
+    }
+
+    if current.len() > 1 {
+        dashes.push(current);
+    }
+
+    dashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dashes_across_segments() {
+        let points = [(0.0, 0.0), (5.0, 0.0), (5.0, 5.0)];
+        assert_eq!(
+            dash(&points, &[4.0, 2.0], (1.0, 1.0)),
+            vec![vec![(0.0, 0.0), (4.0, 0.0)], vec![(5.0, 1.0), (5.0, 5.0)]]
+        );
+    }
+
+    #[test]
+    fn dashes_in_pixels() {
+        let points = [(0.0, 0.0), (1.0, 0.0)];
+        assert_eq!(
+            dash(&points, &[5.0, 5.0], (10.0, 1.0)),
+            vec![vec![(0.0, 0.0), (0.5, 0.0)]]
+        );
+    }
+
+    #[test]
+    fn solid_without_pattern() {
+        let points = [(0.0, 0.0), (1.0, 1.0)];
+        assert_eq!(dash(&points, &[], (1.0, 1.0)), vec![points.to_vec()]);
+    }
+}
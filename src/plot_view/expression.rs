@@ -0,0 +1,925 @@
+//! Traces derived from the fetched vectors by math expressions, like
+//! `v(out)-v(in)` or `db(v(out)/v(in))`, which are evaluated locally and
+//! carry the units of what they are computed from.
+
+use std::sync::Mutex;
+
+use anyhow::{anyhow, bail, ensure, Result};
+
+use crate::vector::{Complex, Vector, VectorData, VectorType};
+
+/// SPICE scale factors, which are case-insensitive, longest first so that
+/// `meg` isn't taken for milli.
+const SUFFIXES: [(&str, f64); 10] = [
+    ("meg", 1e6),
+    ("f", 1e-15),
+    ("p", 1e-12),
+    ("n", 1e-9),
+    ("u", 1e-6),
+    ("µ", 1e-6),
+    ("m", 1e-3),
+    ("k", 1e3),
+    ("g", 1e9),
+    ("t", 1e12),
+];
+
+/// A parsed expression.
+#[derive(Debug)]
+pub struct Expression {
+    source: String,
+    node: Node,
+}
+
+#[derive(Debug)]
+enum Node {
+    Number(f64),
+    /// A vector, by the names it may have, in order of preference.
+    Vector(Vec<String>),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Function {
+    Deriv,
+    Integ,
+    Db,
+    Abs,
+    Sqrt,
+    Avg,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "deriv" => Some(Self::Deriv),
+            "integ" => Some(Self::Integ),
+            "db" => Some(Self::Db),
+            "abs" => Some(Self::Abs),
+            "sqrt" => Some(Self::Sqrt),
+            "avg" => Some(Self::Avg),
+            _ => None,
+        }
+    }
+
+    /// Returns the lowest and highest number of arguments it takes.
+    fn arity(self) -> (usize, usize) {
+        match self {
+            Self::Avg => (1, 2),
+            _ => (1, 1),
+        }
+    }
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self> {
+        let source = source.trim();
+        let mut parser = Parser { source, pos: 0 };
+        let node = parser.expression()?;
+        parser.skip_whitespace();
+        if let Some(c) = parser.peek() {
+            bail!("Unexpected “{}” at position {}", c, parser.pos + 1);
+        }
+        Ok(Self {
+            source: source.to_string(),
+            node,
+        })
+    }
+
+    /// Returns the text the expression was parsed from, without surrounding
+    /// whitespace.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Evaluates the expression over `vectors`, which are functions of
+    /// `scale_vector`. Returns the data, which is complex if any vector it
+    /// uses is, and its unit.
+    pub fn evaluate(
+        &self,
+        scale_vector: &Vector,
+        vectors: &[&Vector],
+    ) -> Result<(VectorData, Unit)> {
+        let scope = Scope {
+            xs: scale_vector.data.to_real(),
+            x_unit: Unit::of(scale_vector.vtype),
+            vectors: std::iter::once(scale_vector)
+                .chain(vectors.iter().copied())
+                .collect(),
+        };
+        let (value, unit) = scope.evaluate(&self.node)?;
+        let data = match value {
+            Value::Scalar(value) => VectorData::Real(vec![value; scope.xs.len()]),
+            Value::Real(real) => VectorData::Real(real),
+            Value::Complex(complex) => VectorData::Complex(complex),
+        };
+        Ok((data, unit))
+    }
+}
+
+/// Parses a number as SPICE writes them, like `1.5k` or `10meg`.
+pub fn parse_number(text: &str) -> Result<f64> {
+    let text = text.trim();
+    let mut parser = Parser {
+        source: text,
+        pos: 0,
+    };
+    let Node::Number(value) = parser.number()? else {
+        unreachable!();
+    };
+    ensure!(parser.pos == text.len(), "Invalid number “{}”", text);
+    Ok(value)
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    /// Byte offset of the next character.
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.source[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    /// Skips whitespace and `c` if it comes next. Returns whether it did.
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        ensure!(self.eat(c), "Expected “{}” at position {}", c, self.pos + 1);
+        Ok(())
+    }
+
+    fn expression(&mut self) -> Result<Node> {
+        let mut node = self.term()?;
+        loop {
+            let operator = if self.eat('+') {
+                Operator::Add
+            } else if self.eat('-') {
+                Operator::Subtract
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(operator, Box::new(node), Box::new(self.term()?));
+        }
+    }
+
+    fn term(&mut self) -> Result<Node> {
+        let mut node = self.unary()?;
+        loop {
+            let operator = if self.eat('*') {
+                Operator::Multiply
+            } else if self.eat('/') {
+                Operator::Divide
+            } else {
+                return Ok(node);
+            };
+            node = Node::Binary(operator, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        if self.eat('-') {
+            Ok(Node::Negate(Box::new(self.unary()?)))
+        } else if self.eat('+') {
+            self.unary()
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let node = self.expression()?;
+                self.expect(')')?;
+                Ok(node)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => self.number(),
+            Some(c) if is_name_char(c) => {
+                let name = self.take_while(is_name_char);
+                if !self.eat('(') {
+                    return Ok(Node::Vector(vec![name.to_string()]));
+                }
+                match name.to_lowercase().as_str() {
+                    "v" => self.vector_reference(|node| vec![format!("v({})", node), node.into()]),
+                    "i" => self.vector_reference(|device| {
+                        vec![format!("i({})", device), format!("{}#branch", device)]
+                    }),
+                    _ => self.call(name),
+                }
+            }
+            Some(c) => bail!("Unexpected “{}” at position {}", c, self.pos + 1),
+            None => bail!("Unexpected end of expression"),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.source[self.pos..];
+        let len = rest.find(|c| !predicate(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    fn number(&mut self) -> Result<Node> {
+        let start = self.pos;
+        self.take_while(|c| c.is_ascii_digit() || c == '.');
+        // An exponent, unless the `e` starts a name, as in `2e`.
+        let rest = &self.source[self.pos..];
+        if let Some(exponent) = rest.strip_prefix(['e', 'E']) {
+            let digits = exponent.strip_prefix(['+', '-']).unwrap_or(exponent);
+            if digits.starts_with(|c: char| c.is_ascii_digit()) {
+                self.pos += rest.len() - digits.len();
+                self.take_while(|c| c.is_ascii_digit());
+            }
+        }
+        let number = &self.source[start..self.pos];
+        let value = number
+            .parse::<f64>()
+            .map_err(|_| anyhow!("Invalid number “{}”", number))?;
+
+        let suffix = self.take_while(char::is_alphabetic).to_lowercase();
+        let factor = if suffix.is_empty() {
+            1.0
+        } else {
+            SUFFIXES
+                .iter()
+                .find(|(prefix, _)| suffix.starts_with(prefix))
+                .map(|(_, factor)| *factor)
+                .ok_or_else(|| anyhow!("Unknown scale factor “{}”", suffix))?
+        };
+        Ok(Node::Number(value * factor))
+    }
+
+    /// Parses the nodes or devices of `v(…)` or `i(…)`, which are taken as
+    /// they are written. Two of them are the difference of their vectors.
+    fn vector_reference(&mut self, names: impl Fn(&str) -> Vec<String>) -> Result<Node> {
+        let reference = |parser: &mut Self| {
+            parser.skip_whitespace();
+            let name = parser.take_while(|c| !matches!(c, ',' | ')')).trim_end();
+            ensure!(
+                !name.is_empty(),
+                "Expected a name at position {}",
+                parser.pos + 1
+            );
+            Ok(Node::Vector(names(name)))
+        };
+        let mut node = reference(self)?;
+        if self.eat(',') {
+            node = Node::Binary(
+                Operator::Subtract,
+                Box::new(node),
+                Box::new(reference(self)?),
+            );
+        }
+        self.expect(')')?;
+        Ok(node)
+    }
+
+    fn call(&mut self, name: &str) -> Result<Node> {
+        let function =
+            Function::from_name(name).ok_or_else(|| anyhow!("Unknown function “{}”", name))?;
+
+        let mut arguments = vec![self.expression()?];
+        while self.eat(',') {
+            arguments.push(self.expression()?);
+        }
+        self.expect(')')?;
+
+        let (min, max) = function.arity();
+        ensure!(
+            (min..=max).contains(&arguments.len()),
+            "“{}” takes {} arguments, not {}",
+            name,
+            if min == max {
+                min.to_string()
+            } else {
+                format!("{} to {}", min, max)
+            },
+            arguments.len()
+        );
+        Ok(Node::Call(function, arguments))
+    }
+}
+
+/// Returns whether `c` may be part of a vector name, as in `x1.out`,
+/// `vdd#branch` or `@m1[id]`.
+fn is_name_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '.' | '#' | '@' | '[' | ']' | ':')
+}
+
+/// The unit of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    /// Powers of volts, amperes and seconds, in halves, so that densities
+    /// like V/√Hz can be told.
+    Dimensions([i8; 3]),
+    /// A unit that isn't made of others, like dB or °.
+    Named(&'static str),
+    /// The unit of a value made of values whose units don't combine, like
+    /// the sum of a voltage and a current.
+    Unknown,
+}
+
+const DIMENSIONLESS: Unit = Unit::Dimensions([0; 3]);
+
+const BASE_SYMBOLS: [&str; 3] = ["V", "A", "s"];
+
+/// The types whose units are made of volts, amperes and seconds, with those
+/// of the units that are taken for a type first.
+const TYPE_DIMENSIONS: &[(VectorType, [i8; 3])] = &[
+    (VectorType::Time, [0, 0, 2]),
+    (VectorType::Frequency, [0, 0, -2]),
+    (VectorType::Voltage, [2, 0, 0]),
+    (VectorType::Current, [0, 2, 0]),
+    (VectorType::VoltageDensity, [2, 0, 1]),
+    (VectorType::CurrentDensity, [0, 2, 1]),
+    (VectorType::SqrVoltageDensity, [4, 0, 2]),
+    (VectorType::SqrCurrentDensity, [0, 4, 2]),
+    (VectorType::SqrVoltage, [4, 0, 0]),
+    (VectorType::SqrCurrent, [0, 4, 0]),
+    (VectorType::Impedance, [2, -2, 0]),
+    (VectorType::Resistance, [2, -2, 0]),
+    (VectorType::Admittance, [-2, 2, 0]),
+    (VectorType::Power, [2, 2, 0]),
+    (VectorType::Capacitance, [-2, 2, 2]),
+    (VectorType::Charge, [0, 2, 2]),
+];
+
+impl Unit {
+    /// Returns the unit of vectors of `vtype`.
+    pub fn of(vtype: VectorType) -> Self {
+        match vtype {
+            VectorType::Temperature | VectorType::Phase | VectorType::Decibel => {
+                Self::Named(vtype.unit())
+            }
+            _ => TYPE_DIMENSIONS
+                .iter()
+                .find(|(other, _)| *other == vtype)
+                .map_or(DIMENSIONLESS, |(_, dimensions)| {
+                    Self::Dimensions(*dimensions)
+                }),
+        }
+    }
+
+    /// Returns the type of vectors in this unit, which is
+    /// [`VectorType::NoType`] if no type has it.
+    pub fn vector_type(self) -> VectorType {
+        match self {
+            Self::Dimensions(dimensions) => TYPE_DIMENSIONS
+                .iter()
+                .find(|(_, other)| *other == dimensions)
+                .map_or(VectorType::NoType, |(vtype, _)| *vtype),
+            Self::Named(symbol) => [
+                VectorType::Temperature,
+                VectorType::Phase,
+                VectorType::Decibel,
+            ]
+            .into_iter()
+            .find(|vtype| vtype.unit() == symbol)
+            .unwrap_or(VectorType::NoType),
+            Self::Unknown => VectorType::NoType,
+        }
+    }
+
+    /// Returns the symbol of the unit, e.g., `V/s`, which is empty if it is
+    /// dimensionless or unknown.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Dimensions(dimensions) => match self.vector_type() {
+                VectorType::NoType => intern(compose(dimensions)),
+                vtype => vtype.unit(),
+            },
+            Self::Named(symbol) => symbol,
+            Self::Unknown => "",
+        }
+    }
+
+    fn sum(self, other: Self) -> Self {
+        // Numbers take the unit of what they are added to.
+        if self == other || other == DIMENSIONLESS {
+            self
+        } else if self == DIMENSIONLESS {
+            other
+        } else {
+            Self::Unknown
+        }
+    }
+
+    fn product(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Dimensions(a), Self::Dimensions(b)) => {
+                Self::Dimensions([0, 1, 2].map(|i| a[i].saturating_add(b[i])))
+            }
+            (unit, DIMENSIONLESS) | (DIMENSIONLESS, unit) => unit,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn quotient(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Dimensions(a), Self::Dimensions(b)) => {
+                Self::Dimensions([0, 1, 2].map(|i| a[i].saturating_sub(b[i])))
+            }
+            (unit, DIMENSIONLESS) => unit,
+            (Self::Named(a), Self::Named(b)) if a == b => DIMENSIONLESS,
+            _ => Self::Unknown,
+        }
+    }
+
+    fn sqrt(self) -> Self {
+        match self {
+            Self::Dimensions(dimensions) if dimensions.iter().all(|d| d % 2 == 0) => {
+                Self::Dimensions(dimensions.map(|d| d / 2))
+            }
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Writes out a unit of volts, amperes and seconds, e.g., `V/(A·s)`.
+fn compose(dimensions: [i8; 3]) -> String {
+    let power = |symbol: &str, halves: i8| match halves {
+        1 => format!("√{}", symbol),
+        2 => symbol.to_string(),
+        4 => format!("{}²", symbol),
+        6 => format!("{}³", symbol),
+        _ => format!("{}^{}", symbol, halves as f64 / 2.0),
+    };
+    let (mut numerator, mut denominator) = (Vec::new(), Vec::new());
+    for (symbol, halves) in BASE_SYMBOLS.into_iter().zip(dimensions) {
+        match halves {
+            0 => {}
+            1.. => numerator.push(power(symbol, halves)),
+            _ => denominator.push(power(symbol, -halves)),
+        }
+    }
+
+    let numerator = if numerator.is_empty() && !denominator.is_empty() {
+        "1".to_string()
+    } else {
+        numerator.join("·")
+    };
+    match denominator.len() {
+        0 => numerator,
+        1 => format!("{}/{}", numerator, denominator[0]),
+        _ => format!("{}/({})", numerator, denominator.join("·")),
+    }
+}
+
+/// Returns `symbol` as a static string, like the units of vector types are,
+/// leaking each distinct symbol once.
+fn intern(symbol: String) -> &'static str {
+    static SYMBOLS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    let mut symbols = SYMBOLS.lock().unwrap();
+    if let Some(interned) = symbols.iter().find(|interned| **interned == symbol) {
+        return interned;
+    }
+    let interned = Box::leak(symbol.into_boxed_str());
+    symbols.push(interned);
+    interned
+}
+
+enum Value {
+    Scalar(f64),
+    Real(Vec<f64>),
+    Complex(Vec<Complex>),
+}
+
+impl Value {
+    fn len(&self) -> Option<usize> {
+        match self {
+            Self::Scalar(_) => None,
+            Self::Real(real) => Some(real.len()),
+            Self::Complex(complex) => Some(complex.len()),
+        }
+    }
+
+    /// Returns the first `len` samples, repeating a scalar. Complex values
+    /// lose their imaginary part.
+    fn into_real(self, len: usize) -> Vec<f64> {
+        match self {
+            Self::Scalar(value) => vec![value; len],
+            Self::Real(mut real) => {
+                real.truncate(len);
+                real
+            }
+            Self::Complex(complex) => complex.iter().take(len).map(|c| c.re).collect(),
+        }
+    }
+
+    fn into_complex(self, len: usize) -> Vec<Complex> {
+        match self {
+            Self::Complex(mut complex) => {
+                complex.truncate(len);
+                complex
+            }
+            real => real
+                .into_real(len)
+                .into_iter()
+                .map(|re| Complex::new(re, 0.0))
+                .collect(),
+        }
+    }
+
+    fn map(self, real: impl Fn(f64) -> f64, complex: impl Fn(Complex) -> Complex) -> Self {
+        match self {
+            Self::Scalar(value) => Self::Scalar(real(value)),
+            Self::Real(values) => Self::Real(values.into_iter().map(real).collect()),
+            Self::Complex(values) => Self::Complex(values.into_iter().map(complex).collect()),
+        }
+    }
+
+    /// Like [`Self::map`], but to real values.
+    fn map_to_real(self, real: impl Fn(f64) -> f64, complex: impl Fn(Complex) -> f64) -> Self {
+        match self {
+            Self::Scalar(value) => Self::Scalar(real(value)),
+            Self::Real(values) => Self::Real(values.into_iter().map(real).collect()),
+            Self::Complex(values) => Self::Real(values.into_iter().map(complex).collect()),
+        }
+    }
+}
+
+fn binary(operator: Operator, a: Value, b: Value) -> Value {
+    let len = match (a.len(), b.len()) {
+        (None, None) => {
+            let (Value::Scalar(a), Value::Scalar(b)) = (a, b) else {
+                unreachable!();
+            };
+            return Value::Scalar(real_operation(operator)(a, b));
+        }
+        (Some(len), None) | (None, Some(len)) => len,
+        (Some(a), Some(b)) => a.min(b),
+    };
+
+    if matches!(a, Value::Complex(_)) || matches!(b, Value::Complex(_)) {
+        let operation = complex_operation(operator);
+        Value::Complex(
+            a.into_complex(len)
+                .into_iter()
+                .zip(b.into_complex(len))
+                .map(|(a, b)| operation(a, b))
+                .collect(),
+        )
+    } else {
+        let operation = real_operation(operator);
+        Value::Real(
+            a.into_real(len)
+                .into_iter()
+                .zip(b.into_real(len))
+                .map(|(a, b)| operation(a, b))
+                .collect(),
+        )
+    }
+}
+
+fn real_operation(operator: Operator) -> fn(f64, f64) -> f64 {
+    match operator {
+        Operator::Add => |a, b| a + b,
+        Operator::Subtract => |a, b| a - b,
+        Operator::Multiply => |a, b| a * b,
+        Operator::Divide => |a, b| a / b,
+    }
+}
+
+fn complex_operation(operator: Operator) -> fn(Complex, Complex) -> Complex {
+    match operator {
+        Operator::Add => |a, b| Complex::new(a.re + b.re, a.im + b.im),
+        Operator::Subtract => |a, b| Complex::new(a.re - b.re, a.im - b.im),
+        Operator::Multiply => {
+            |a, b| Complex::new(a.re * b.re - a.im * b.im, a.re * b.im + a.im * b.re)
+        }
+        Operator::Divide => |a, b| {
+            let norm_sqr = b.re * b.re + b.im * b.im;
+            Complex::new(
+                (a.re * b.re + a.im * b.im) / norm_sqr,
+                (a.im * b.re - a.re * b.im) / norm_sqr,
+            )
+        },
+    }
+}
+
+/// Returns the principal square root of `c`.
+fn complex_sqrt(c: Complex) -> Complex {
+    let norm = c.norm();
+    Complex::new(
+        ((norm + c.re) / 2.0).sqrt(),
+        ((norm - c.re) / 2.0).sqrt().copysign(c.im),
+    )
+}
+
+struct Scope<'a> {
+    xs: Vec<f64>,
+    x_unit: Unit,
+    /// The vectors that can be referred to, starting with the scale vector.
+    vectors: Vec<&'a Vector>,
+}
+
+impl Scope<'_> {
+    fn evaluate(&self, node: &Node) -> Result<(Value, Unit)> {
+        match node {
+            Node::Number(value) => Ok((Value::Scalar(*value), DIMENSIONLESS)),
+            Node::Vector(names) => {
+                let vector = names
+                    .iter()
+                    .find_map(|name| {
+                        self.vectors
+                            .iter()
+                            .find(|vector| vector.name.eq_ignore_ascii_case(name))
+                    })
+                    .ok_or_else(|| anyhow!("No vector named “{}”", names[0]))?;
+                let value = match &vector.data {
+                    VectorData::Real(real) => Value::Real(real.clone()),
+                    VectorData::Complex(complex) => Value::Complex(complex.clone()),
+                };
+                Ok((value, Unit::of(vector.vtype)))
+            }
+            Node::Negate(node) => {
+                let (value, unit) = self.evaluate(node)?;
+                Ok((value.map(|x| -x, |c| Complex::new(-c.re, -c.im)), unit))
+            }
+            Node::Binary(operator, a, b) => {
+                let (a, a_unit) = self.evaluate(a)?;
+                let (b, b_unit) = self.evaluate(b)?;
+                let unit = match operator {
+                    Operator::Add | Operator::Subtract => a_unit.sum(b_unit),
+                    Operator::Multiply => a_unit.product(b_unit),
+                    Operator::Divide => a_unit.quotient(b_unit),
+                };
+                Ok((binary(*operator, a, b), unit))
+            }
+            Node::Call(function, arguments) => self.call(*function, arguments),
+        }
+    }
+
+    fn call(&self, function: Function, arguments: &[Node]) -> Result<(Value, Unit)> {
+        let (value, unit) = self.evaluate(&arguments[0])?;
+        Ok(match function {
+            Function::Deriv => (self.along_scale(value, deriv), unit.quotient(self.x_unit)),
+            Function::Integ => (self.along_scale(value, integ), unit.product(self.x_unit)),
+            Function::Db => (
+                value.map_to_real(|x| 20.0 * x.abs().log10(), Complex::db),
+                Unit::Named("dB"),
+            ),
+            Function::Abs => (value.map_to_real(f64::abs, Complex::norm), unit),
+            Function::Sqrt => (value.map(f64::sqrt, complex_sqrt), unit.sqrt()),
+            Function::Avg => {
+                let window = match arguments.get(1) {
+                    Some(node) => match self.evaluate(node)? {
+                        (Value::Scalar(window), _) if window > 0.0 => Some(window),
+                        _ => bail!("The window of “avg” must be a positive number"),
+                    },
+                    None => None,
+                };
+                (self.along_scale(value, |xs, ys| avg(xs, ys, window)), unit)
+            }
+        })
+    }
+
+    /// Applies `f` to the real and imaginary parts of `value` as functions
+    /// of the scale.
+    fn along_scale(&self, value: Value, f: impl Fn(&[f64], &[f64]) -> Vec<f64>) -> Value {
+        let len = value
+            .len()
+            .map_or(self.xs.len(), |len| len.min(self.xs.len()));
+        let xs = &self.xs[..len];
+        match value {
+            Value::Complex(complex) => {
+                let re = complex.iter().map(|c| c.re).collect::<Vec<_>>();
+                let im = complex.iter().map(|c| c.im).collect::<Vec<_>>();
+                Value::Complex(
+                    f(xs, &re[..len])
+                        .into_iter()
+                        .zip(f(xs, &im[..len]))
+                        .map(|(re, im)| Complex::new(re, im))
+                        .collect(),
+                )
+            }
+            real => Value::Real(f(xs, &real.into_real(len))),
+        }
+    }
+}
+
+/// Returns the derivative of `ys` by central differences, and by one-sided
+/// ones at the ends.
+fn deriv(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let len = ys.len();
+    if len < 2 {
+        return vec![f64::NAN; len];
+    }
+    (0..len)
+        .map(|i| {
+            let (a, b) = (i.saturating_sub(1), (i + 1).min(len - 1));
+            (ys[b] - ys[a]) / (xs[b] - xs[a])
+        })
+        .collect()
+}
+
+/// Returns the integral of `ys` from the first sample on, by the
+/// trapezoidal rule.
+fn integ(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let mut sum = 0.0;
+    (0..ys.len())
+        .map(|i| {
+            if i > 0 {
+                sum += (ys[i] + ys[i - 1]) / 2.0 * (xs[i] - xs[i - 1]);
+            }
+            sum
+        })
+        .collect()
+}
+
+/// Returns the mean of `ys` over the `window` of the scale up to each
+/// sample, or from the first sample if there is no window or the window
+/// reaches before it.
+fn avg(xs: &[f64], ys: &[f64], window: Option<f64>) -> Vec<f64> {
+    let integral = integ(xs, ys);
+    (0..ys.len())
+        .map(|i| {
+            let start = window.map_or(xs[0], |window| xs[i] - window);
+            if start <= xs[0] {
+                return if i == 0 {
+                    ys[0]
+                } else {
+                    integral[i] / (xs[i] - xs[0])
+                };
+            }
+            // The integral up to `start`, between the samples around it.
+            let j = xs[..i].partition_point(|&x| x < start).max(1);
+            let integral_at_start = integral[j - 1]
+                + (ys[j - 1]
+                    + (ys[j] - ys[j - 1]) * (start - xs[j - 1]) / (xs[j] - xs[j - 1])
+                    + ys[j - 1])
+                    / 2.0
+                    * (start - xs[j - 1]);
+            (integral[i] - integral_at_start) / (xs[i] - start)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector(name: &str, vtype: VectorType, data: VectorData) -> Vector {
+        Vector {
+            name: name.to_string(),
+            vtype,
+            data,
+        }
+    }
+
+    fn evaluate(source: &str, scale: &Vector, vectors: &[&Vector]) -> (VectorData, &'static str) {
+        let (data, unit) = Expression::parse(source)
+            .unwrap()
+            .evaluate(scale, vectors)
+            .unwrap();
+        (data, unit.symbol())
+    }
+
+    #[test]
+    fn arithmetic_with_units() {
+        let time = vector(
+            "time",
+            VectorType::Time,
+            VectorData::Real(vec![0.0, 1.0, 2.0]),
+        );
+        let out = vector(
+            "v(out)",
+            VectorType::Voltage,
+            VectorData::Real(vec![1.0, 2.0, 4.0]),
+        );
+        let input = vector(
+            "in",
+            VectorType::Voltage,
+            VectorData::Real(vec![1.0, 1.0, 1.0]),
+        );
+        let vdd = vector(
+            "vdd#branch",
+            VectorType::Current,
+            VectorData::Real(vec![2.0; 3]),
+        );
+        let vectors = [&out, &input, &vdd];
+
+        assert_eq!(
+            evaluate("v(out) - v(in)", &time, &vectors),
+            (VectorData::Real(vec![0.0, 1.0, 3.0]), "V")
+        );
+        assert_eq!(
+            evaluate("V(out,in)", &time, &vectors),
+            (VectorData::Real(vec![0.0, 1.0, 3.0]), "V")
+        );
+        assert_eq!(
+            evaluate("v(out)*i(vdd)", &time, &vectors),
+            (VectorData::Real(vec![2.0, 4.0, 8.0]), "W")
+        );
+        assert_eq!(
+            evaluate("-(2k * v(out)) + 1", &time, &vectors).0,
+            VectorData::Real(vec![-1999.0, -3999.0, -7999.0])
+        );
+        assert_eq!(evaluate("v(out)/i(vdd)", &time, &vectors).1, "Ω");
+        assert_eq!(evaluate("v(out)+i(vdd)", &time, &vectors).1, "");
+        assert_eq!(evaluate("v(out)/time/i(vdd)", &time, &vectors).1, "V/(A·s)");
+        assert_eq!(evaluate("1meg/time", &time, &vectors).1, "Hz");
+    }
+
+    #[test]
+    fn functions_of_the_scale() {
+        let time = vector(
+            "time",
+            VectorType::Time,
+            VectorData::Real(vec![0.0, 1.0, 2.0, 3.0]),
+        );
+        let c = vector(
+            "v(c)",
+            VectorType::Voltage,
+            VectorData::Real(vec![0.0, 2.0, 4.0, 6.0]),
+        );
+
+        assert_eq!(
+            evaluate("deriv(v(c))", &time, &[&c]),
+            (VectorData::Real(vec![2.0; 4]), "V/s")
+        );
+        assert_eq!(
+            evaluate("integ(v(c))", &time, &[&c]),
+            (VectorData::Real(vec![0.0, 1.0, 4.0, 9.0]), "V·s")
+        );
+        assert_eq!(
+            evaluate("avg(v(c))", &time, &[&c]),
+            (VectorData::Real(vec![0.0, 1.0, 2.0, 3.0]), "V")
+        );
+        assert_eq!(
+            evaluate("avg(v(c), 1.5)", &time, &[&c]).0,
+            VectorData::Real(vec![0.0, 1.0, 2.5, 4.5])
+        );
+    }
+
+    #[test]
+    fn complex_data() {
+        let frequency = vector(
+            "frequency",
+            VectorType::Frequency,
+            VectorData::Real(vec![1.0]),
+        );
+        let out = vector(
+            "v(out)",
+            VectorType::Voltage,
+            VectorData::Complex(vec![Complex::new(0.0, 10.0)]),
+        );
+        let input = vector("v(in)", VectorType::Voltage, VectorData::Real(vec![1.0]));
+
+        assert_eq!(
+            evaluate("db(v(out)/v(in))", &frequency, &[&out, &input]),
+            (VectorData::Real(vec![20.0]), "dB")
+        );
+        assert_eq!(
+            evaluate("abs(v(out)) * v(in)", &frequency, &[&out, &input]),
+            (VectorData::Real(vec![10.0]), "V²")
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for source in [
+            "",
+            "v(out",
+            "v(out) +",
+            "foo(v(out))",
+            "2x",
+            "abs(1, 2)",
+            "1 2",
+        ] {
+            assert!(Expression::parse(source).is_err(), "{}", source);
+        }
+        let time = vector("time", VectorType::Time, VectorData::Real(vec![0.0]));
+        assert!(Expression::parse("v(missing)")
+            .unwrap()
+            .evaluate(&time, &[])
+            .is_err());
+    }
+}
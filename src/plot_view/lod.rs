@@ -0,0 +1,131 @@
+//! Reducing traces to the samples that make a difference at the width they
+//! are drawn at, so that drawing doesn't slow down with the sample count.
+
+/// The samples of a trace that are drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decimated {
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+}
+
+/// Reduces the samples within `x_range` to the first, the minimum, the
+/// maximum, and the last of each of `columns` columns, which draws the same
+/// line at a width of `columns` pixels. The samples next to the range are
+/// kept, so that the line still reaches its bounds.
+///
+/// `xs` must never decrease, so that the samples of a column are next to
+/// each other and those within the range can be searched for.
+pub fn min_max(xs: &[f64], ys: &[f64], (x_min, x_max): (f64, f64), columns: usize) -> Decimated {
+    let len = xs.len().min(ys.len());
+    let (xs, ys) = (&xs[..len], &ys[..len]);
+
+    let start = xs.partition_point(|&x| x < x_min);
+    let end = xs.partition_point(|&x| x <= x_max);
+    let (lo, hi) = (start.saturating_sub(1), (end + 1).min(len));
+
+    if hi - lo <= 4 * columns || columns == 0 || x_max <= x_min {
+        return Decimated {
+            xs: xs[lo..hi].to_vec(),
+            ys: ys[lo..hi].to_vec(),
+        };
+    }
+
+    let mut decimated = Decimated {
+        xs: Vec::with_capacity(4 * columns + 2),
+        ys: Vec::with_capacity(4 * columns + 2),
+    };
+    let mut push = |index: usize| {
+        decimated.xs.push(xs[index]);
+        decimated.ys.push(ys[index]);
+    };
+
+    if lo < start {
+        push(lo);
+    }
+
+    let column_of =
+        |x: f64| (((x - x_min) / (x_max - x_min) * columns as f64) as usize).min(columns - 1);
+    // The first, minimum, maximum, and last samples of the current column.
+    let mut current: Option<(usize, [usize; 4])> = None;
+    for index in start..end {
+        let y = ys[index];
+        // Non-finite samples break the line, so they are kept as is.
+        if !y.is_finite() || !xs[index].is_finite() {
+            flush(&mut current, &mut push);
+            push(index);
+            continue;
+        }
+
+        let column = column_of(xs[index]);
+        match &mut current {
+            Some((current_column, [_, min, max, last])) if *current_column == column => {
+                if y < ys[*min] {
+                    *min = index;
+                }
+                if y > ys[*max] {
+                    *max = index;
+                }
+                *last = index;
+            }
+            _ => {
+                flush(&mut current, &mut push);
+                current = Some((column, [index; 4]));
+            }
+        }
+    }
+    flush(&mut current, &mut push);
+
+    if end < hi {
+        push(end);
+    }
+
+    decimated
+}
+
+/// Pushes the samples of the column, if any, in order and once each.
+fn flush(column: &mut Option<(usize, [usize; 4])>, push: &mut impl FnMut(usize)) {
+    let Some((_, mut indices)) = column.take() else {
+        return;
+    };
+    indices.sort_unstable();
+    let mut last = None;
+    for index in indices {
+        if last != Some(index) {
+            push(index);
+            last = Some(index);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_extremes_of_each_column() {
+        let xs = (0..100).map(f64::from).collect::<Vec<_>>();
+        let ys = xs
+            .iter()
+            .map(|&x| if x == 30.0 { 10.0 } else { x % 2.0 })
+            .collect::<Vec<_>>();
+
+        let decimated = min_max(&xs, &ys, (0.0, 99.0), 2);
+        assert_eq!(decimated.xs, [0.0, 30.0, 49.0, 50.0, 51.0, 99.0]);
+        assert_eq!(decimated.ys, [0.0, 10.0, 1.0, 0.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn keeps_neighbors_and_breaks() {
+        let xs = (0..100).map(f64::from).collect::<Vec<_>>();
+        let mut ys = vec![1.0; 100];
+        ys[50] = f64::NAN;
+
+        let decimated = min_max(&xs, &ys, (10.5, 89.5), 1);
+        assert_eq!(decimated.xs, [10.0, 11.0, 49.0, 50.0, 51.0, 89.0, 90.0]);
+        assert!(decimated.ys[3].is_nan());
+
+        // Few samples are kept as they are.
+        let decimated = min_max(&xs[..4], &ys[..4], (1.0, 2.0), 1);
+        assert_eq!(decimated.xs, [0.0, 1.0, 2.0, 3.0]);
+    }
+}
@@ -0,0 +1,295 @@
+//! Measurements of waveforms, like those otherwise written as `.meas`
+//! statements.
+
+/// Fraction of the peak-to-peak value a trace has to get past its middle
+/// level by for an edge to count, so that noise isn't taken for edges.
+const HYSTERESIS: f64 = 0.1;
+
+/// Fractions of the peak-to-peak value rise and fall times are measured
+/// between.
+const TRANSITION_LEVELS: (f64, f64) = (0.1, 0.9);
+
+/// Fraction of the step a trace has to stay within of its final value to be
+/// settled.
+const SETTLING_BAND: f64 = 0.02;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Measurements {
+    pub min: f64,
+    pub max: f64,
+    /// Mean over the scale, rather than over the samples, which are
+    /// unevenly spaced by the simulator.
+    pub mean: f64,
+    pub rms: f64,
+    /// Mean time between rising edges, through the middle level.
+    pub period: Option<f64>,
+    /// Mean fraction of each period spent above the middle level.
+    pub duty_cycle: Option<f64>,
+    /// Time of the first rise from 10 % to 90 % of the peak-to-peak value.
+    pub rise_time: Option<f64>,
+    /// Time of the first fall from 90 % to 10 % of the peak-to-peak value.
+    pub fall_time: Option<f64>,
+    /// How far the trace goes past its final value, as a fraction of the
+    /// step from its initial value.
+    pub overshoot: Option<f64>,
+    /// Time from the first sample until the trace stays within 2 % of the
+    /// step of its final value.
+    pub settling_time: Option<f64>,
+}
+
+impl Measurements {
+    pub fn peak_to_peak(&self) -> f64 {
+        self.max - self.min
+    }
+
+    pub fn frequency(&self) -> Option<f64> {
+        self.period.map(|period| 1.0 / period)
+    }
+}
+
+/// Measures the samples of `ys`, sampled at `xs`, within `x_range`, or all
+/// of them if there is none. Returns `None` if there are no finite samples
+/// to measure.
+pub fn measure(xs: &[f64], ys: &[f64], x_range: Option<(f64, f64)>) -> Option<Measurements> {
+    let samples = samples(xs, ys, x_range);
+    let (&(x_first, y_first), &(x_last, y_last)) = (samples.first()?, samples.last()?);
+
+    let (min, max) = samples
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &(_, y)| {
+            (min.min(y), max.max(y))
+        });
+    let span = x_last - x_first;
+    let mean_of = |f: fn(f64) -> f64| {
+        if span > 0.0 {
+            samples
+                .windows(2)
+                .map(|pair| (f(pair[0].1) + f(pair[1].1)) / 2.0 * (pair[1].0 - pair[0].0))
+                .sum::<f64>()
+                / span
+        } else {
+            samples.iter().map(|&(_, y)| f(y)).sum::<f64>() / samples.len() as f64
+        }
+    };
+
+    let peak_to_peak = max - min;
+    let edges = edges(&samples, (min + max) / 2.0, HYSTERESIS * peak_to_peak);
+    let rising_edges = edges
+        .iter()
+        .filter(|(_, is_rising)| *is_rising)
+        .map(|(x, _)| *x)
+        .collect::<Vec<_>>();
+    let period = (rising_edges.len() >= 2).then(|| {
+        (rising_edges[rising_edges.len() - 1] - rising_edges[0]) / (rising_edges.len() - 1) as f64
+    });
+    // Edges alternate, so each period is a rising, a falling, and a rising
+    // edge.
+    let duty_cycles = edges
+        .windows(3)
+        .filter(|edges| edges[0].1)
+        .map(|edges| (edges[1].0 - edges[0].0) / (edges[2].0 - edges[0].0))
+        .collect::<Vec<_>>();
+    let duty_cycle = (!duty_cycles.is_empty())
+        .then(|| duty_cycles.iter().sum::<f64>() / duty_cycles.len() as f64);
+
+    let (low, high) = (
+        min + TRANSITION_LEVELS.0 * peak_to_peak,
+        min + TRANSITION_LEVELS.1 * peak_to_peak,
+    );
+    let has_transitions = peak_to_peak > 0.0;
+
+    let step = y_last - y_first;
+    let overshoot = (step != 0.0).then(|| {
+        let peak = if step > 0.0 { max } else { min };
+        ((peak - y_last) / step).max(0.0)
+    });
+
+    Some(Measurements {
+        min,
+        max,
+        mean: mean_of(|y| y),
+        rms: mean_of(|y| y * y).sqrt(),
+        period,
+        duty_cycle,
+        rise_time: transition_time(&samples, low, high).filter(|_| has_transitions),
+        fall_time: transition_time(&samples, high, low).filter(|_| has_transitions),
+        overshoot,
+        settling_time: (step != 0.0)
+            .then(|| settling_time(&samples, SETTLING_BAND * step.abs()) - x_first),
+    })
+}
+
+/// Returns how much later `ys` first crosses its middle level than
+/// `reference_ys` does, both sampled at `xs` and measured within `x_range`.
+pub fn delay(
+    xs: &[f64],
+    ys: &[f64],
+    reference_ys: &[f64],
+    x_range: Option<(f64, f64)>,
+) -> Option<f64> {
+    let first_edge = |ys: &[f64]| {
+        let samples = samples(xs, ys, x_range);
+        let (min, max) = samples
+            .iter()
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), &(_, y)| {
+                (min.min(y), max.max(y))
+            });
+        edges(&samples, (min + max) / 2.0, HYSTERESIS * (max - min))
+            .first()
+            .map(|(x, _)| *x)
+    };
+    Some(first_edge(ys)? - first_edge(reference_ys)?)
+}
+
+/// Returns the finite samples within `x_range`.
+fn samples(xs: &[f64], ys: &[f64], x_range: Option<(f64, f64)>) -> Vec<(f64, f64)> {
+    xs.iter()
+        .copied()
+        .zip(ys.iter().copied())
+        .filter(|(x, y)| {
+            x.is_finite()
+                && y.is_finite()
+                && x_range.is_none_or(|(min, max)| (min..=max).contains(x))
+        })
+        .collect()
+}
+
+/// Returns where the line between `(x0, y0)` and `(x1, y1)` is at `level`.
+fn crossing((x0, y0): (f64, f64), (x1, y1): (f64, f64), level: f64) -> f64 {
+    if y1 == y0 {
+        x1
+    } else {
+        x0 + (level - y0) / (y1 - y0) * (x1 - x0)
+    }
+}
+
+/// Returns when the trace crosses `level`, and whether it rises through it.
+/// A crossing only counts once the trace gets `hysteresis` past the level,
+/// after having been as far past it on the other side.
+fn edges(samples: &[(f64, f64)], level: f64, hysteresis: f64) -> Vec<(f64, bool)> {
+    let state_of = |y: f64| {
+        if y >= level + hysteresis {
+            Some(true)
+        } else if y <= level - hysteresis {
+            Some(false)
+        } else {
+            None
+        }
+    };
+
+    let mut edges = Vec::new();
+    let mut is_high = samples.first().and_then(|&(_, y)| state_of(y));
+    let mut last_crossing = None;
+    for pair in samples.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        if (start.1 < level) != (end.1 < level) {
+            last_crossing = Some(crossing(start, end, level));
+        }
+
+        let Some(now_high) = state_of(end.1) else {
+            continue;
+        };
+        if is_high == Some(!now_high) {
+            edges.push((last_crossing.unwrap_or(end.0), now_high));
+        }
+        is_high = Some(now_high);
+    }
+    edges
+}
+
+/// Returns how long the trace first takes to go from `from` to `to`, from
+/// when it last leaves `from` until it reaches `to`.
+fn transition_time(samples: &[(f64, f64)], from: f64, to: f64) -> Option<f64> {
+    let is_rising = to > from;
+    let is_before = |y: f64, level: f64| if is_rising { y < level } else { y > level };
+
+    let mut start = None;
+    for pair in samples.windows(2) {
+        let (a, b) = (pair[0], pair[1]);
+        if is_before(a.1, from) && !is_before(b.1, from) {
+            start = Some(crossing(a, b, from));
+        }
+        if let Some(start) = start {
+            if is_before(a.1, to) && !is_before(b.1, to) {
+                return Some(crossing(a, b, to) - start);
+            }
+        }
+    }
+    None
+}
+
+/// Returns when the trace enters the `band` around its final value for the
+/// last time.
+fn settling_time(samples: &[(f64, f64)], band: f64) -> f64 {
+    let (x_first, y_final) = (samples[0].0, samples[samples.len() - 1].1);
+    let Some(last_outside) = samples
+        .iter()
+        .rposition(|&(_, y)| (y - y_final).abs() > band)
+    else {
+        return x_first;
+    };
+
+    let (outside, inside) = (samples[last_outside], samples[last_outside + 1]);
+    let edge = y_final + band.copysign(outside.1 - y_final);
+    crossing(outside, inside, edge)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(a: Option<f64>, b: f64) {
+        let a = a.unwrap();
+        assert!((a - b).abs() < 1e-9, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn square_wave() {
+        // A period of 10 s, high for 3 s, with edges of 1 s.
+        let xs = (0..=400).map(|i| i as f64 / 10.0).collect::<Vec<_>>();
+        let ys = xs
+            .iter()
+            .map(|&x| match x % 10.0 {
+                x if x < 1.0 => x,
+                x if x < 3.0 => 1.0,
+                x if x < 4.0 => 4.0 - x,
+                _ => 0.0,
+            })
+            .collect::<Vec<_>>();
+
+        let measurements = measure(&xs, &ys, None).unwrap();
+        assert_eq!((measurements.min, measurements.max), (0.0, 1.0));
+        assert_near(Some(measurements.mean), 0.3);
+        assert_near(measurements.period, 10.0);
+        assert_near(measurements.frequency(), 0.1);
+        assert_near(measurements.duty_cycle, 0.3);
+        assert_near(measurements.rise_time, 0.8);
+        assert_near(measurements.fall_time, 0.8);
+
+        // Within one high level, there are no edges.
+        let measurements = measure(&xs, &ys, Some((11.0, 13.0))).unwrap();
+        assert_near(Some(measurements.rms), 1.0);
+        assert_eq!(measurements.period, None);
+        assert_eq!(measurements.rise_time, None);
+
+        let delayed = xs
+            .iter()
+            .map(|&x| ys[((x - 2.5).max(0.0) * 10.0).round() as usize])
+            .collect::<Vec<_>>();
+        assert_near(delay(&xs, &delayed, &ys, None), 2.5);
+    }
+
+    #[test]
+    fn step_response() {
+        let xs = [0.0, 1.0, 2.0, 3.0, 4.0, 5.0];
+        let ys = [0.0, 1.2, 0.9, 1.05, 1.01, 1.0];
+
+        let measurements = measure(&xs, &ys, None).unwrap();
+        assert_near(measurements.overshoot, 0.2);
+        // The trace enters 1 ± 0.02 between 3 s and 4 s.
+        assert_near(measurements.settling_time, 3.75);
+        assert_eq!(measurements.period, None);
+
+        assert_eq!(measure(&xs, &[f64::NAN; 6], None), None);
+    }
+}
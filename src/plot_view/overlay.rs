@@ -0,0 +1,94 @@
+//! Comparing vectors of different plots, which are sampled at different
+//! points of their scales.
+
+use super::cursors;
+use crate::vector::{Complex, VectorData};
+
+/// Resamples `data`, sampled at `xs`, at `new_xs` by linear interpolation.
+/// Samples outside of `xs` are NaN, which breaks the line there.
+pub fn resample(xs: &[f64], data: &VectorData, new_xs: &[f64]) -> VectorData {
+    match data {
+        VectorData::Real(ys) => VectorData::Real(resample_real(xs, ys, new_xs)),
+        VectorData::Complex(complex) => {
+            let re = complex.iter().map(|c| c.re).collect::<Vec<_>>();
+            let im = complex.iter().map(|c| c.im).collect::<Vec<_>>();
+            VectorData::Complex(
+                resample_real(xs, &re, new_xs)
+                    .into_iter()
+                    .zip(resample_real(xs, &im, new_xs))
+                    .map(|(re, im)| Complex::new(re, im))
+                    .collect(),
+            )
+        }
+    }
+}
+
+fn resample_real(xs: &[f64], ys: &[f64], new_xs: &[f64]) -> Vec<f64> {
+    if xs == new_xs {
+        return ys.to_vec();
+    }
+
+    let is_increasing = xs.windows(2).all(|pair| pair[0] <= pair[1]);
+    new_xs
+        .iter()
+        .map(|&x| cursors::interpolate(xs, ys, x, is_increasing).unwrap_or(f64::NAN))
+        .collect()
+}
+
+/// Returns `a − b` sample by sample, which is complex if either is.
+pub fn difference(a: &VectorData, b: &VectorData) -> VectorData {
+    match (a, b) {
+        (VectorData::Real(a), VectorData::Real(b)) => {
+            VectorData::Real(a.iter().zip(b).map(|(a, b)| a - b).collect())
+        }
+        _ => VectorData::Complex(
+            to_complex(a)
+                .zip(to_complex(b))
+                .map(|(a, b)| Complex::new(a.re - b.re, a.im - b.im))
+                .collect(),
+        ),
+    }
+}
+
+fn to_complex(data: &VectorData) -> Box<dyn Iterator<Item = Complex> + '_> {
+    match data {
+        VectorData::Real(real) => Box::new(real.iter().map(|&re| Complex::new(re, 0.0))),
+        VectorData::Complex(complex) => Box::new(complex.iter().copied()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_onto_other_timesteps() {
+        let xs = [0.0, 1.0, 3.0];
+        let data = VectorData::Real(vec![0.0, 10.0, 30.0]);
+        let VectorData::Real(resampled) = resample(&xs, &data, &[-1.0, 0.5, 1.0, 2.0, 4.0]) else {
+            unreachable!();
+        };
+        assert!(resampled[0].is_nan());
+        assert_eq!(resampled[1..4], [5.0, 10.0, 20.0]);
+        assert!(resampled[4].is_nan());
+
+        let data = VectorData::Complex(vec![Complex::new(0.0, 2.0), Complex::new(2.0, 0.0)]);
+        assert_eq!(
+            resample(&[0.0, 1.0], &data, &[0.5]),
+            VectorData::Complex(vec![Complex::new(1.0, 1.0)])
+        );
+    }
+
+    #[test]
+    fn difference_of_mixed_data() {
+        let a = VectorData::Real(vec![3.0, 4.0]);
+        let b = VectorData::Real(vec![1.0, 1.0]);
+        assert_eq!(difference(&a, &b), VectorData::Real(vec![2.0, 3.0]));
+
+        let b = VectorData::Complex(vec![Complex::new(1.0, 1.0), Complex::new(0.0, -1.0)]);
+        assert_eq!(
+            difference(&a, &b),
+            VectorData::Complex(vec![Complex::new(2.0, -1.0), Complex::new(4.0, 1.0)])
+        );
+    }
+}
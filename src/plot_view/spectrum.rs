@@ -0,0 +1,285 @@
+//! Spectra of transient traces, by the FFT of their samples resampled onto
+//! a uniform grid, as the simulator steps time unevenly.
+
+use std::f64::consts::PI;
+
+use crate::vector::Complex;
+
+/// Coefficients of the cosine-sum windows the samples are weighted by,
+/// which trade frequency resolution for less leakage and truer amplitudes.
+pub const RECTANGULAR: &[f64] = &[1.0];
+pub const HANN: &[f64] = &[0.5, 0.5];
+pub const BLACKMAN_HARRIS: &[f64] = &[0.35875, 0.48829, 0.14128, 0.01168];
+pub const FLAT_TOP: &[f64] = &[
+    0.215_578_95,
+    0.416_631_58,
+    0.277_263_158,
+    0.083_578_947,
+    0.006_947_368,
+];
+
+/// Most points traces are resampled onto, which bounds the time the FFT
+/// takes.
+const MAX_LEN: usize = 1 << 18;
+
+/// Highest harmonic counted in the THD.
+const MAX_HARMONIC: usize = 10;
+
+/// Uniformly spaced points of the scale, which traces are resampled onto.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    start: f64,
+    step: f64,
+    len: usize,
+}
+
+impl Grid {
+    /// Returns a grid over the span of `xs`, with as many points as it has
+    /// rounded up to a power of two. Returns `None` if `xs` spans nothing
+    /// or ever decreases.
+    pub fn new(xs: &[f64]) -> Option<Self> {
+        let (&start, &end) = (xs.first()?, xs.last()?);
+        let span = end - start;
+        if !span.is_finite() || span <= 0.0 || xs.windows(2).any(|pair| pair[1] < pair[0]) {
+            return None;
+        }
+
+        let len = xs.len().next_power_of_two().clamp(2, MAX_LEN);
+        // The end is left out, as the samples are taken to repeat from the
+        // start on.
+        Some(Self {
+            start,
+            step: span / len as f64,
+            len,
+        })
+    }
+
+    /// Returns the frequency of each bin, from DC to the Nyquist frequency.
+    pub fn frequencies(&self) -> Vec<f64> {
+        let resolution = 1.0 / (self.step * self.len as f64);
+        (0..=self.len / 2)
+            .map(|bin| bin as f64 * resolution)
+            .collect()
+    }
+}
+
+/// Returns the single-sided amplitude spectrum of `ys`, sampled at `xs`,
+/// once resampled onto `grid` and weighted by the window of `coefficients`.
+/// The gain of the window is made up for, so that a sine at the frequency
+/// of a bin shows with its amplitude.
+pub fn magnitudes(xs: &[f64], ys: &[f64], grid: &Grid, coefficients: &[f64]) -> Vec<f64> {
+    let len = xs.len().min(ys.len());
+    let n = grid.len;
+    if len == 0 {
+        return vec![f64::NAN; n / 2 + 1];
+    }
+
+    let window = (0..n)
+        .map(|k| {
+            coefficients
+                .iter()
+                .enumerate()
+                .map(|(j, a)| {
+                    let sign = if j % 2 == 0 { 1.0 } else { -1.0 };
+                    sign * a * (2.0 * PI * (j * k) as f64 / n as f64).cos()
+                })
+                .sum::<f64>()
+        })
+        .collect::<Vec<_>>();
+    let gain = window.iter().sum::<f64>() / n as f64;
+
+    let mut index = 0;
+    let mut buffer = window
+        .iter()
+        .enumerate()
+        .map(|(k, w)| {
+            let x = grid.start + k as f64 * grid.step;
+            while index + 2 < len && xs[index + 1] <= x {
+                index += 1;
+            }
+            let y = if index + 1 < len && xs[index + 1] > xs[index] {
+                let (x0, x1) = (xs[index], xs[index + 1]);
+                let (y0, y1) = (ys[index], ys[index + 1]);
+                y0 + (y1 - y0) * ((x - x0) / (x1 - x0)).clamp(0.0, 1.0)
+            } else {
+                ys[index]
+            };
+            Complex::new(y * w, 0.0)
+        })
+        .collect::<Vec<_>>();
+    fft(&mut buffer);
+
+    (0..=n / 2)
+        .map(|bin| {
+            // Bins other than DC and Nyquist have a mirror image of the same
+            // amplitude.
+            let sides = if bin == 0 || bin == n / 2 { 1.0 } else { 2.0 };
+            buffer[bin].norm() * sides / (n as f64 * gain)
+        })
+        .collect()
+}
+
+/// Transforms `buffer`, whose length is a power of two, in place.
+fn fft(buffer: &mut [Complex]) {
+    let n = buffer.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            buffer.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let half = len / 2;
+        for k in 0..half {
+            let angle = -2.0 * PI * k as f64 / len as f64;
+            let (sin, cos) = angle.sin_cos();
+            for start in (0..n).step_by(len) {
+                let a = buffer[start + k];
+                let b = buffer[start + k + half];
+                let b = Complex::new(b.re * cos - b.im * sin, b.re * sin + b.im * cos);
+                buffer[start + k] = Complex::new(a.re + b.re, a.im + b.im);
+                buffer[start + k + half] = Complex::new(a.re - b.re, a.im - b.im);
+            }
+        }
+        len *= 2;
+    }
+}
+
+/// How far a tone is from a pure sine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Distortion {
+    /// The bin of the fundamental.
+    pub fundamental: usize,
+    /// Total harmonic distortion, as the ratio of the amplitude of the
+    /// harmonics to that of the fundamental.
+    pub thd: f64,
+    /// Spurious-free dynamic range, as the ratio of the amplitude of the
+    /// fundamental to that of the largest other tone.
+    pub sfdr: f64,
+}
+
+/// Measures the distortion of the tone nearest to `fundamental`, given in
+/// bins, or of the strongest tone if there is none. Tones are spread by the
+/// window of `coefficients` over as many bins on each side as it has
+/// coefficients.
+pub fn distortion(
+    magnitudes: &[f64],
+    fundamental: Option<f64>,
+    coefficients: &[f64],
+) -> Option<Distortion> {
+    let len = magnitudes.len();
+    let spread = coefficients.len();
+    let strongest = |bins: std::ops::RangeInclusive<usize>| {
+        bins.filter(|bin| magnitudes[*bin].is_finite())
+            .max_by(|a, b| magnitudes[*a].total_cmp(&magnitudes[*b]))
+    };
+    let peak_near = |bin: usize, reach: usize| {
+        strongest(bin.saturating_sub(reach).max(spread + 1)..=(bin + reach).min(len - 1))
+    };
+    // The amplitude of the tone spread around `bin`.
+    let tone = |bin: usize| {
+        (bin.saturating_sub(spread)..=(bin + spread).min(len - 1))
+            .map(|bin| magnitudes[bin].powi(2))
+            .filter(|power| power.is_finite())
+            .sum::<f64>()
+            .sqrt()
+    };
+
+    // Tones within the spread of DC are taken for offsets.
+    let fundamental = match fundamental {
+        Some(bin) if bin >= 0.0 && bin < len as f64 => peak_near(bin.round() as usize, spread)?,
+        Some(_) => return None,
+        None => strongest(spread + 1..=len.checked_sub(1)?)?,
+    };
+    let amplitude = tone(fundamental);
+    if amplitude <= 0.0 {
+        return None;
+    }
+
+    let harmonic_power = (2..=MAX_HARMONIC)
+        .map(|harmonic| (harmonic, harmonic * fundamental))
+        .take_while(|(_, bin)| *bin < len)
+        .filter_map(|(harmonic, bin)| peak_near(bin, spread + harmonic / 2))
+        .map(|bin| tone(bin).powi(2))
+        .sum::<f64>();
+
+    let spur = (spread + 1..len)
+        .filter(|bin| bin.abs_diff(fundamental) > spread)
+        .map(|bin| magnitudes[bin])
+        .filter(|magnitude| magnitude.is_finite())
+        .fold(0.0, f64::max);
+
+    Some(Distortion {
+        fundamental,
+        thd: harmonic_power.sqrt() / amplitude,
+        sfdr: magnitudes[fundamental] / spur,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns 1 s of a 1 V sine at 8 Hz with 10 mV of its third harmonic.
+    fn distorted_sine() -> (Vec<f64>, Vec<f64>) {
+        let xs = (0..=4096).map(|i| i as f64 / 4096.0).collect::<Vec<_>>();
+        let ys = xs
+            .iter()
+            .map(|x| (2.0 * PI * 8.0 * x).sin() + 0.01 * (2.0 * PI * 24.0 * x).sin())
+            .collect::<Vec<_>>();
+        (xs, ys)
+    }
+
+    fn assert_near(a: f64, b: f64, tolerance: f64) {
+        assert!((a - b).abs() <= tolerance, "{} != {}", a, b);
+    }
+
+    #[test]
+    fn amplitudes_of_tones() {
+        let (xs, ys) = distorted_sine();
+        let grid = Grid::new(&xs).unwrap();
+        let frequencies = grid.frequencies();
+        assert_eq!(frequencies.len(), 4097);
+        assert_near(frequencies[8], 8.0, 1e-9);
+
+        for window in [RECTANGULAR, HANN, BLACKMAN_HARRIS, FLAT_TOP] {
+            let magnitudes = magnitudes(&xs, &ys, &grid, window);
+            assert_near(magnitudes[8], 1.0, 1e-3);
+            assert_near(magnitudes[24], 0.01, 1e-4);
+            assert!(magnitudes[100] < 1e-4);
+        }
+    }
+
+    #[test]
+    fn harmonic_distortion() {
+        let (xs, ys) = distorted_sine();
+        let grid = Grid::new(&xs).unwrap();
+
+        for window in [RECTANGULAR, HANN] {
+            let magnitudes = magnitudes(&xs, &ys, &grid, window);
+            for fundamental in [None, Some(7.6)] {
+                let distortion = distortion(&magnitudes, fundamental, window).unwrap();
+                assert_eq!(distortion.fundamental, 8);
+                assert_near(distortion.thd, 0.01, 1e-4);
+                assert_near(distortion.sfdr, 100.0, 1.0);
+            }
+        }
+
+        let magnitudes = magnitudes(&xs, &ys, &grid, HANN);
+        for fundamental in [-1.0, 1e30, f64::INFINITY, f64::NAN] {
+            assert_eq!(distortion(&magnitudes, Some(fundamental), HANN), None);
+        }
+
+        assert_eq!(Grid::new(&[0.0]), None);
+        assert_eq!(Grid::new(&[0.0, 2.0, 1.0]), None);
+    }
+}
@@ -0,0 +1,102 @@
+//! Colors of the chart, following the light or dark Adwaita style.
+
+use gtk::gdk;
+
+use crate::colors;
+
+/// Hue offset between generated colors, the golden angle as a fraction of a
+/// turn, which keeps any number of consecutive hues apart.
+const GOLDEN_ANGLE: f32 = 0.381_966;
+
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    pub background: gdk::RGBA,
+    /// Axes, labels, and legend text.
+    pub foreground: gdk::RGBA,
+    pub bold_grid: gdk::RGBA,
+    pub light_grid: gdk::RGBA,
+    pub legend_background: gdk::RGBA,
+    pub accent: gdk::RGBA,
+    pub vertical_cursor: gdk::RGBA,
+    pub horizontal_cursor: gdk::RGBA,
+    pub crosshair: gdk::RGBA,
+    /// Trace colors, assigned to vectors in order.
+    pub palette: [gdk::RGBA; 7],
+    /// Saturation and value of the colors generated past the palette.
+    pub generated_saturation: f32,
+    pub generated_value: f32,
+}
+
+/// Matches the light Adwaita view colors.
+const LIGHT: Theme = Theme {
+    background: colors::LIGHT_1,
+    foreground: gdk::RGBA::new(0.0, 0.0, 0.0, 0.8),
+    bold_grid: gdk::RGBA::new(0.0, 0.0, 0.0, 0.2),
+    light_grid: gdk::RGBA::new(0.0, 0.0, 0.0, 0.07),
+    legend_background: gdk::RGBA::new(1.0, 1.0, 1.0, 0.8),
+    accent: colors::BLUE_3,
+    vertical_cursor: colors::DARK_3,
+    horizontal_cursor: colors::DARK_1,
+    crosshair: colors::DARK_1,
+    palette: [
+        colors::BLUE_3,
+        colors::GREEN_3,
+        colors::YELLOW_3,
+        colors::ORANGE_3,
+        colors::RED_3,
+        colors::PURPLE_3,
+        colors::BROWN_3,
+    ],
+    generated_saturation: 0.8,
+    generated_value: 0.7,
+};
+
+/// Matches the dark Adwaita view colors, with lighter traces so they stand
+/// out from the background.
+const DARK: Theme = Theme {
+    background: gdk::RGBA::new(0.117_647_06, 0.117_647_06, 0.117_647_06, 1.0), // #1e1e1e
+    foreground: colors::LIGHT_1,
+    bold_grid: gdk::RGBA::new(1.0, 1.0, 1.0, 0.2),
+    light_grid: gdk::RGBA::new(1.0, 1.0, 1.0, 0.07),
+    legend_background: gdk::RGBA::new(0.117_647_06, 0.117_647_06, 0.117_647_06, 0.8),
+    accent: colors::BLUE_2,
+    vertical_cursor: colors::LIGHT_3,
+    horizontal_cursor: colors::LIGHT_5,
+    crosshair: colors::LIGHT_5,
+    palette: [
+        colors::BLUE_2,
+        colors::GREEN_2,
+        colors::YELLOW_2,
+        colors::ORANGE_2,
+        colors::RED_1,
+        colors::PURPLE_1,
+        colors::BROWN_1,
+    ],
+    generated_saturation: 0.55,
+    generated_value: 0.95,
+};
+
+impl Theme {
+    /// Returns the theme for the current style of the application.
+    pub fn current() -> &'static Self {
+        if adw::StyleManager::default().is_dark() {
+            &DARK
+        } else {
+            &LIGHT
+        }
+    }
+
+    /// Returns the color of the trace at `index` in the order vectors are
+    /// assigned colors, generating distinct hues once the palette runs out.
+    pub fn trace_color(&self, index: usize) -> gdk::RGBA {
+        if let Some(color) = self.palette.get(index) {
+            return *color;
+        }
+
+        let nth_generated = (index - self.palette.len()) as f32;
+        let hue = (nth_generated * GOLDEN_ANGLE).fract();
+        let (red, green, blue) =
+            gtk::hsv_to_rgb(hue, self.generated_saturation, self.generated_value);
+        gdk::RGBA::new(red, green, blue, 1.0)
+    }
+}
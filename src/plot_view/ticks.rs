@@ -0,0 +1,153 @@
+//! Where the tick marks and grid lines of an axis go.
+//!
+//! Positions are in chart coordinates, like the ranges in
+//! [`viewport`](super::viewport), so on log axes they are the logarithm of
+//! the values they mark.
+
+/// Tick positions with labels and bold grid lines, and the positions of the
+/// light grid lines between them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Ticks {
+    pub bold: Vec<f64>,
+    pub light: Vec<f64>,
+}
+
+/// Returns ticks at multiples of 1, 2 or 5 times a power of ten, with at
+/// most `max_count` of them within `range`.
+pub fn linear((min, max): (f64, f64), max_count: usize) -> Ticks {
+    // A range split into `n` parts has up to `n + 1` ticks, and a step of
+    // twice the range leaves room for only one.
+    let step = match max_count {
+        0 => None,
+        1 => nice_step(2.0 * (max - min), 1),
+        _ => nice_step(max - min, max_count - 1),
+    };
+    let Some(step) = step else {
+        return Ticks::default();
+    };
+    let light_step = if mantissa(step) == 2.0 {
+        step / 4.0
+    } else {
+        step / 5.0
+    };
+
+    Ticks {
+        bold: multiples_within((min, max), step),
+        light: multiples_within((min, max), light_step)
+            .into_iter()
+            .filter(|v| !is_multiple(*v, step))
+            .collect(),
+    }
+}
+
+/// Returns ticks at whole decades, with light lines at the multiples of each
+/// decade in between. Ranges spanning less than two decades fall back to
+/// linear ticks of the values.
+pub fn log((min, max): (f64, f64), max_count: usize) -> Ticks {
+    if !min.is_finite() || !max.is_finite() || min >= max {
+        return Ticks::default();
+    }
+
+    let decades = (min.ceil() as i32..=max.floor() as i32).collect::<Vec<_>>();
+    if decades.len() < 2 {
+        let values = linear((10_f64.powf(min), 10_f64.powf(max)), max_count);
+        return Ticks {
+            bold: values.bold.into_iter().map(f64::log10).collect(),
+            light: values.light.into_iter().map(f64::log10).collect(),
+        };
+    }
+
+    // Skip decades when there are too many to label.
+    let decade_step = decades.len().div_ceil(max_count.max(1));
+    let bold = decades
+        .iter()
+        .filter(|decade| decade.rem_euclid(decade_step as i32) == 0)
+        .map(|&decade| decade as f64)
+        .collect();
+    let light = if decade_step == 1 {
+        (min.floor() as i32..=max.floor() as i32)
+            .flat_map(|decade| (2..10).map(move |m| decade as f64 + f64::log10(m as f64)))
+            .filter(|v| (min..=max).contains(v))
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    Ticks { bold, light }
+}
+
+/// Returns the smallest step of 1, 2 or 5 times a power of ten that divides
+/// `span` into at most `max_count` parts.
+fn nice_step(span: f64, max_count: usize) -> Option<f64> {
+    if !span.is_finite() || span <= 0.0 {
+        return None;
+    }
+
+    let rough = span / max_count.max(1) as f64;
+    let magnitude = 10_f64.powf(rough.log10().floor());
+    [1.0, 2.0, 5.0, 10.0]
+        .into_iter()
+        .map(|m| m * magnitude)
+        .find(|&step| step >= rough)
+}
+
+fn mantissa(value: f64) -> f64 {
+    (value / 10_f64.powf(value.log10().floor())).round()
+}
+
+fn multiples_within((min, max): (f64, f64), step: f64) -> Vec<f64> {
+    let first = (min / step).ceil() as i64;
+    let last = (max / step).floor() as i64;
+    (first..=last).map(|i| i as f64 * step).collect()
+}
+
+fn is_multiple(value: f64, step: f64) -> bool {
+    let ratio = value / step;
+    (ratio - ratio.round()).abs() < 1e-9
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linear_ticks() {
+        let ticks = linear((0.0, 1.0), 11);
+        assert_eq!(ticks.bold.len(), 11);
+        assert_eq!(ticks.bold[2], 0.2);
+        assert_eq!(ticks.light.len(), 40);
+
+        let ticks = linear((0.0, 1.0), 10);
+        assert_eq!(ticks.bold.len(), 6);
+        assert_eq!(ticks.bold[2], 0.4);
+
+        let ticks = linear((-3.0, 7.0), 5);
+        assert_eq!(ticks.bold, vec![0.0, 5.0]);
+        assert_eq!(ticks.light.len(), 9);
+
+        for max_count in 0..30 {
+            for range in [(-3.0, 7.0), (0.0, 10.0), (0.1, 0.35)] {
+                assert!(linear(range, max_count).bold.len() <= max_count);
+            }
+        }
+
+        assert_eq!(linear((1.0, 1.0), 10), Ticks::default());
+    }
+
+    #[test]
+    fn log_ticks() {
+        let ticks = log((0.5, 3.0), 10);
+        assert_eq!(ticks.bold, vec![1.0, 2.0, 3.0]);
+        assert_eq!(ticks.light.len(), 6 + 8 + 8);
+
+        let ticks = log((0.0, 20.0), 5);
+        assert_eq!(ticks.bold, vec![0.0, 5.0, 10.0, 15.0, 20.0]);
+        assert!(ticks.light.is_empty());
+
+        let ticks = log((2.0, 2.5), 10);
+        assert_eq!(
+            ticks.bold.first().map(|v| 10_f64.powf(*v).round()),
+            Some(100.0)
+        );
+    }
+}
@@ -0,0 +1,239 @@
+//! Grouping of the vector list into a tree, by kind of quantity or by the
+//! subcircuit instances vectors are in.
+
+use crate::vector::VectorType;
+
+/// What kind of quantity a vector is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Voltage,
+    Current,
+    /// A device parameter or operating point, like `@m1[id]`.
+    Device,
+    Other,
+}
+
+impl Kind {
+    pub fn of(name: &str, vtype: VectorType) -> Self {
+        if name.starts_with('@') {
+            Self::Device
+        } else if vtype == VectorType::Current
+            || name.starts_with("i(")
+            || name.ends_with("#branch")
+        {
+            Self::Current
+        } else if vtype == VectorType::Voltage || name.starts_with("v(") {
+            Self::Voltage
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Returns the subcircuit instances a vector is in, outermost first, e.g.,
+/// `["x1", "x2"]` for `v(x1.x2.out)` or `@m.x1.m1[id]`.
+pub fn instance_path(name: &str) -> Vec<&str> {
+    let mut inner = name.strip_prefix('@').unwrap_or(name);
+    for prefix in ["v(", "i("] {
+        if let Some(wrapped) = inner.strip_prefix(prefix).and_then(|s| s.strip_suffix(')')) {
+            inner = wrapped;
+        }
+    }
+    inner = inner.split('[').next().unwrap_or(inner);
+    inner = inner.strip_suffix("#branch").unwrap_or(inner);
+
+    let mut segments = inner.split('.').collect::<Vec<_>>();
+    segments.pop();
+    // Devices within subcircuits are prefixed with their type letter, as in
+    // `v.x1.vdd`.
+    if segments.len() > 1
+        && segments[0].len() == 1
+        && segments[1].starts_with(['x', 'X'])
+        && !segments[0].eq_ignore_ascii_case("x")
+    {
+        segments.remove(0);
+    }
+    segments
+}
+
+/// An entry of the flattened tree, listed depth first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub depth: usize,
+    /// Index of the group entry this is in.
+    pub parent: Option<usize>,
+    pub item: Item,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Group {
+        /// The path to the group, which identifies it across rebuilds.
+        key: String,
+        label: String,
+        /// Number of vectors within the group and its subgroups.
+        len: usize,
+    },
+    /// The index of a vector in the list the tree was built from.
+    Vector(usize),
+}
+
+/// Builds the tree where each vector is within the groups of its path,
+/// outermost first. Vectors come before the subgroups next to them, and
+/// both keep the order they first appear in.
+pub fn build(paths: &[Vec<String>]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+    let indices = (0..paths.len()).collect::<Vec<_>>();
+    build_level(paths, &indices, 0, None, &mut entries);
+    entries
+}
+
+fn build_level(
+    paths: &[Vec<String>],
+    indices: &[usize],
+    depth: usize,
+    parent: Option<usize>,
+    entries: &mut Vec<Entry>,
+) {
+    let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+    for &index in indices {
+        let Some(label) = paths[index].get(depth) else {
+            entries.push(Entry {
+                depth,
+                parent,
+                item: Item::Vector(index),
+            });
+            continue;
+        };
+        match groups.iter_mut().find(|(l, _)| l == label) {
+            Some((_, members)) => members.push(index),
+            None => groups.push((label, vec![index])),
+        }
+    }
+
+    for (label, members) in groups {
+        let group_index = entries.len();
+        entries.push(Entry {
+            depth,
+            parent,
+            item: Item::Group {
+                key: paths[members[0]][..=depth].join("/"),
+                label: label.to_string(),
+                len: members.len(),
+            },
+        });
+        build_level(paths, &members, depth + 1, Some(group_index), entries);
+    }
+}
+
+/// Returns which entries are listed. While searching, those are the vectors
+/// that `matches` and the groups containing them, and otherwise the entries
+/// whose groups are all expanded.
+pub fn shown(
+    entries: &[Entry],
+    matches: Option<&dyn Fn(usize) -> bool>,
+    is_collapsed: impl Fn(&str) -> bool,
+) -> Vec<bool> {
+    let mut shown = vec![false; entries.len()];
+
+    if let Some(matches) = matches {
+        for (index, entry) in entries.iter().enumerate() {
+            let Item::Vector(vector_index) = entry.item else {
+                continue;
+            };
+            if !matches(vector_index) {
+                continue;
+            }
+            shown[index] = true;
+            let mut parent = entry.parent;
+            while let Some(parent_index) = parent.filter(|&i| !shown[i]) {
+                shown[parent_index] = true;
+                parent = entries[parent_index].parent;
+            }
+        }
+        return shown;
+    }
+
+    // Parents come before their children, so they are already decided.
+    for (index, entry) in entries.iter().enumerate() {
+        shown[index] = entry.parent.is_none_or(|parent_index| {
+            let Item::Group { key, .. } = &entries[parent_index].item else {
+                unreachable!("parents are groups");
+            };
+            shown[parent_index] && !is_collapsed(key)
+        });
+    }
+    shown
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds() {
+        assert_eq!(Kind::of("v(out)", VectorType::Voltage), Kind::Voltage);
+        assert_eq!(Kind::of("i(v1)", VectorType::Current), Kind::Current);
+        assert_eq!(Kind::of("v1#branch", VectorType::NoType), Kind::Current);
+        assert_eq!(Kind::of("@m1[id]", VectorType::Current), Kind::Device);
+        assert_eq!(Kind::of("time", VectorType::Time), Kind::Other);
+    }
+
+    #[test]
+    fn instance_paths() {
+        assert_eq!(instance_path("v(x1.x2.out)"), ["x1", "x2"]);
+        assert_eq!(instance_path("x1.out"), ["x1"]);
+        assert_eq!(instance_path("v.x1.vdd#branch"), ["x1"]);
+        assert_eq!(instance_path("i(v.x1.vdd)"), ["x1"]);
+        assert_eq!(instance_path("@m.x1.m1[id]"), ["x1"]);
+        assert!(instance_path("v(out)").is_empty());
+        assert!(instance_path("@r1[i]").is_empty());
+    }
+
+    #[test]
+    fn build_and_collapse() {
+        let paths = [
+            vec!["x1".to_string()],
+            vec![],
+            vec!["x1".to_string(), "x2".to_string()],
+            vec!["x3".to_string()],
+        ];
+        let entries = build(&paths);
+        let items = entries.iter().map(|e| &e.item).collect::<Vec<_>>();
+        assert_eq!(
+            items,
+            [
+                &Item::Vector(1),
+                &Item::Group {
+                    key: "x1".to_string(),
+                    label: "x1".to_string(),
+                    len: 2
+                },
+                &Item::Vector(0),
+                &Item::Group {
+                    key: "x1/x2".to_string(),
+                    label: "x2".to_string(),
+                    len: 1
+                },
+                &Item::Vector(2),
+                &Item::Group {
+                    key: "x3".to_string(),
+                    label: "x3".to_string(),
+                    len: 1
+                },
+                &Item::Vector(3),
+            ]
+        );
+        assert_eq!(entries[4].depth, 2);
+        assert_eq!(entries[4].parent, Some(3));
+
+        assert_eq!(
+            shown(&entries, None, |key| key == "x1"),
+            [true, true, false, false, false, true, true]
+        );
+        assert_eq!(
+            shown(&entries, Some(&|index| index == 2), |_| true),
+            [false, true, false, true, true, false, false]
+        );
+    }
+}
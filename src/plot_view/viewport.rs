@@ -0,0 +1,377 @@
+//! The part of the data that is shown, and the mapping between it and the
+//! pixels of the chart.
+//!
+//! Ranges are `(min, max)` pairs in chart coordinates, which are the data
+//! values except on log axes, where they are the logarithm of them.
+
+/// Smallest range width allowed, relative to the magnitude of its bounds, so
+/// that zooming in doesn't run into the precision limits of `f64`.
+const MIN_RELATIVE_WIDTH: f64 = 1e-12;
+
+/// The ranges chosen by the user, where `None` means fitting to the data.
+///
+/// Ranges that are too narrow or not finite are ignored, keeping the
+/// previous one.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Viewport {
+    x: Option<(f64, f64)>,
+    /// Y ranges of each pane, from top to bottom.
+    y: Vec<Option<(f64, f64)>>,
+}
+
+impl Viewport {
+    pub fn x(&self) -> Option<(f64, f64)> {
+        self.x
+    }
+
+    pub fn set_x(&mut self, range: Option<(f64, f64)>) {
+        match range {
+            Some(range) => {
+                if let Some(range) = valid_range(range) {
+                    self.x = Some(range);
+                }
+            }
+            None => self.x = None,
+        }
+    }
+
+    pub fn y(&self, pane_index: usize) -> Option<(f64, f64)> {
+        self.y.get(pane_index).copied().flatten()
+    }
+
+    pub fn set_y(&mut self, pane_index: usize, range: Option<(f64, f64)>) {
+        if self.y.len() <= pane_index {
+            self.y.resize(pane_index + 1, None);
+        }
+        match range {
+            Some(range) => {
+                if let Some(range) = valid_range(range) {
+                    self.y[pane_index] = Some(range);
+                }
+            }
+            None => self.y[pane_index] = None,
+        }
+    }
+
+    /// Makes the Y ranges of every pane fit the data again.
+    pub fn reset_y(&mut self) {
+        self.y.clear();
+    }
+
+    pub fn reset(&mut self) {
+        self.x = None;
+        self.y.clear();
+    }
+}
+
+/// Where a pane was drawn, and the ranges it was drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pane {
+    /// Horizontal pixel bounds of the plotting area, left to right.
+    pub x_pixels: (i32, i32),
+    /// Vertical pixel bounds of the plotting area, top to bottom.
+    pub y_pixels: (i32, i32),
+    pub x_range: (f64, f64),
+    pub y_range: (f64, f64),
+    /// Whether the chart X coordinates are the logarithm of the data.
+    pub x_is_log: bool,
+    /// Whether the chart Y coordinates are the logarithm of the data.
+    pub y_is_log: bool,
+}
+
+impl Pane {
+    pub fn contains(&self, (x, y): (f64, f64)) -> bool {
+        (self.x_pixels.0 as f64..=self.x_pixels.1 as f64).contains(&x)
+            && (self.y_pixels.0 as f64..=self.y_pixels.1 as f64).contains(&y)
+    }
+
+    /// Returns whether `other` was drawn on the same area, as the axes on
+    /// either side of a pane are.
+    pub fn shares_area(&self, other: &Pane) -> bool {
+        self.x_pixels == other.x_pixels && self.y_pixels == other.y_pixels
+    }
+
+    /// Maps a pixel position to chart coordinates.
+    pub fn pixel_to_chart(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.x_range.0 + (x - self.x_pixels.0 as f64) * self.x_per_pixel(),
+            self.y_range.1 - (y - self.y_pixels.0 as f64) * self.y_per_pixel(),
+        )
+    }
+
+    /// Maps chart coordinates to a pixel position.
+    pub fn chart_to_pixel(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (
+            self.x_pixels.0 as f64 + pixels_per(self.x_range, self.x_pixels) * (x - self.x_range.0),
+            self.y_pixels.0 as f64 + pixels_per(self.y_range, self.y_pixels) * (self.y_range.1 - y),
+        )
+    }
+
+    /// Maps a chart X coordinate to the value of the scale.
+    pub fn x_to_data(&self, x: f64) -> f64 {
+        if self.x_is_log {
+            10_f64.powf(x)
+        } else {
+            x
+        }
+    }
+
+    /// Maps a value of the scale to a chart X coordinate.
+    pub fn x_from_data(&self, x: f64) -> f64 {
+        if self.x_is_log {
+            x.log10()
+        } else {
+            x
+        }
+    }
+
+    /// Maps a chart Y coordinate to the value of the data.
+    pub fn y_to_data(&self, y: f64) -> f64 {
+        if self.y_is_log {
+            10_f64.powf(y)
+        } else {
+            y
+        }
+    }
+
+    /// Returns the chart X distance that a pixel spans.
+    pub fn x_per_pixel(&self) -> f64 {
+        per_pixel(self.x_range, self.x_pixels)
+    }
+
+    /// Returns the chart Y distance that a pixel spans.
+    pub fn y_per_pixel(&self) -> f64 {
+        per_pixel(self.y_range, self.y_pixels)
+    }
+}
+
+fn per_pixel((min, max): (f64, f64), (start, end): (i32, i32)) -> f64 {
+    if start == end {
+        0.0
+    } else {
+        (max - min) / (end - start) as f64
+    }
+}
+
+fn pixels_per((min, max): (f64, f64), (start, end): (i32, i32)) -> f64 {
+    if min == max {
+        0.0
+    } else {
+        (end - start) as f64 / (max - min)
+    }
+}
+
+/// Scales `range` by `factor` around `center`, keeping `center` at the same
+/// place. Factors less than one zoom in.
+pub fn zoom((min, max): (f64, f64), center: f64, factor: f64) -> (f64, f64) {
+    (
+        center - (center - min) * factor,
+        center + (max - center) * factor,
+    )
+}
+
+/// Moves `range` by `delta`.
+pub fn pan((min, max): (f64, f64), delta: f64) -> (f64, f64) {
+    (min + delta, max + delta)
+}
+
+/// Returns the range with the bounds ordered, or `None` if it is too narrow
+/// or not finite.
+fn valid_range((a, b): (f64, f64)) -> Option<(f64, f64)> {
+    let (min, max) = (f64::min(a, b), f64::max(a, b));
+    let min_width = f64::max(min.abs(), max.abs()) * MIN_RELATIVE_WIDTH;
+    (min.is_finite() && max.is_finite() && max - min > min_width).then_some((min, max))
+}
+
+/// Returns the minimum and maximum of the finite `ys` whose `x` is within
+/// `x_range`, or `None` if there are none.
+pub fn y_range_within(
+    xs: &[f64],
+    ys: impl Iterator<Item = f64>,
+    (x_min, x_max): (f64, f64),
+) -> Option<(f64, f64)> {
+    xs.iter()
+        .zip(ys)
+        .filter(|&(&x, y)| (x_min..=x_max).contains(&x) && y.is_finite())
+        .fold(None, |acc, (_, y)| match acc {
+            None => Some((y, y)),
+            Some((min, max)) => Some((f64::min(min, y), f64::max(max, y))),
+        })
+}
+
+/// Splits a line into the parts that are within the given ranges, cutting
+/// the segments that cross the bounds. Non-finite points break the line.
+pub fn clip_line(
+    points: impl Iterator<Item = (f64, f64)>,
+    x_range: (f64, f64),
+    y_range: (f64, f64),
+) -> Vec<Vec<(f64, f64)>> {
+    let mut lines = Vec::new();
+    let mut current = Vec::new();
+    let mut prev: Option<(f64, f64)> = None;
+
+    for point in points {
+        if !point.0.is_finite() || !point.1.is_finite() {
+            prev = None;
+            continue;
+        }
+
+        let Some(prev_point) = prev.replace(point) else {
+            flush(&mut lines, &mut current);
+            continue;
+        };
+
+        match clip_segment(prev_point, point, x_range, y_range) {
+            Some((start, end)) => {
+                if current.last() != Some(&start) {
+                    flush(&mut lines, &mut current);
+                    current.push(start);
+                }
+                current.push(end);
+            }
+            None => flush(&mut lines, &mut current),
+        }
+    }
+    flush(&mut lines, &mut current);
+
+    lines
+}
+
+fn flush(lines: &mut Vec<Vec<(f64, f64)>>, current: &mut Vec<(f64, f64)>) {
+    if current.len() > 1 {
+        lines.push(std::mem::take(current));
+    } else {
+        current.clear();
+    }
+}
+
+/// Clips a segment to a rectangle using the Liang-Barsky algorithm.
+fn clip_segment(
+    (x0, y0): (f64, f64),
+    (x1, y1): (f64, f64),
+    (x_min, x_max): (f64, f64),
+    (y_min, y_max): (f64, f64),
+) -> Option<((f64, f64), (f64, f64))> {
+    let (dx, dy) = (x1 - x0, y1 - y0);
+
+    let mut t0 = 0.0_f64;
+    let mut t1 = 1.0_f64;
+    for (p, q) in [
+        (-dx, x0 - x_min),
+        (dx, x_max - x0),
+        (-dy, y0 - y_min),
+        (dy, y_max - y0),
+    ] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+
+    if t0 > t1 {
+        return None;
+    }
+
+    let start = if t0 == 0.0 {
+        (x0, y0)
+    } else {
+        (x0 + t0 * dx, y0 + t0 * dy)
+    };
+    let end = if t1 == 1.0 {
+        (x1, y1)
+    } else {
+        (x0 + t1 * dx, y0 + t1 * dy)
+    };
+    Some((start, end))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_rejects_degenerate_ranges() {
+        let mut viewport = Viewport::default();
+
+        viewport.set_x(Some((2.0, 1.0)));
+        assert_eq!(viewport.x(), Some((1.0, 2.0)));
+
+        viewport.set_x(Some((1.0, 1.0)));
+        assert_eq!(viewport.x(), Some((1.0, 2.0)));
+
+        viewport.set_x(None);
+        assert_eq!(viewport.x(), None);
+
+        viewport.set_y(1, Some((0.0, f64::NAN)));
+        assert_eq!(viewport.y(1), None);
+        viewport.set_y(1, Some((0.0, 1.0)));
+        assert_eq!(viewport.y(0), None);
+        assert_eq!(viewport.y(1), Some((0.0, 1.0)));
+    }
+
+    #[test]
+    fn pane_pixel_to_chart() {
+        let pane = Pane {
+            x_pixels: (10, 110),
+            y_pixels: (20, 70),
+            x_range: (0.0, 1.0),
+            y_range: (-1.0, 1.0),
+            x_is_log: false,
+            y_is_log: false,
+        };
+        assert!(pane.contains((10.0, 70.0)));
+        assert!(!pane.contains((9.0, 70.0)));
+        assert_eq!(pane.pixel_to_chart((10.0, 20.0)), (0.0, 1.0));
+        assert_eq!(pane.pixel_to_chart((60.0, 45.0)), (0.5, 0.0));
+        assert_eq!(pane.pixel_to_chart((110.0, 70.0)), (1.0, -1.0));
+        assert_eq!(pane.chart_to_pixel((0.5, 0.0)), (60.0, 45.0));
+    }
+
+    #[test]
+    fn zoom_and_pan() {
+        assert_eq!(zoom((0.0, 10.0), 2.0, 0.5), (1.0, 6.0));
+        assert_eq!(zoom((0.0, 10.0), 5.0, 2.0), (-5.0, 15.0));
+        assert_eq!(pan((0.0, 10.0), -1.0), (-1.0, 9.0));
+    }
+
+    #[test]
+    fn y_range_within_window() {
+        let xs = [0.0, 1.0, 2.0, 3.0];
+        let ys = [5.0, -1.0, f64::NAN, 3.0];
+        assert_eq!(
+            y_range_within(&xs, ys.into_iter(), (0.5, 3.0)),
+            Some((-1.0, 3.0))
+        );
+        assert_eq!(y_range_within(&xs, ys.into_iter(), (1.5, 2.5)), None);
+    }
+
+    #[test]
+    fn clip_line_cuts_crossing_segments() {
+        let points = [
+            (-1.0, 0.5),
+            (0.5, 0.5),
+            (1.0, 2.0),
+            (2.0, 2.0),
+            (2.0, 0.5),
+            (f64::NAN, 0.5),
+            (2.5, 0.5),
+            (3.0, 0.5),
+        ];
+        assert_eq!(
+            clip_line(points.into_iter(), (0.0, 3.0), (0.0, 1.0)),
+            vec![
+                vec![(0.0, 0.5), (0.5, 0.5), (2.0 / 3.0, 1.0)],
+                vec![(2.0, 1.0), (2.0, 0.5)],
+                vec![(2.5, 0.5), (3.0, 0.5)],
+            ]
+        );
+    }
+}
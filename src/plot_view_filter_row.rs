@@ -1,14 +1,20 @@
+use gettextrs::gettext;
 use gtk::{
     gdk,
-    glib::{self, clone},
+    glib::{self, clone, closure_local},
     prelude::*,
     subclass::prelude::*,
 };
 
-use crate::color_widget::ColorWidget;
+use crate::{color_widget::ColorWidget, plot_view::LineStyle};
 
 mod imp {
-    use std::{cell::OnceCell, marker::PhantomData};
+    use std::{
+        cell::{Cell, OnceCell, RefCell},
+        marker::PhantomData,
+    };
+
+    use gtk::glib::{once_cell::sync::Lazy, subclass::Signal};
 
     use super::*;
 
@@ -18,10 +24,19 @@ mod imp {
     pub struct PlotViewFilterRow {
         #[property(get, set, construct_only)]
         pub(super) name: OnceCell<String>,
-        #[property(get, set, construct_only)]
-        pub(super) color: OnceCell<gdk::RGBA>,
-        #[property(get = Self::is_active)]
+        /// The color the trace is drawn with.
+        #[property(get = Self::color, set = Self::set_color)]
+        pub(super) color: PhantomData<gdk::RGBA>,
+        /// The color chosen by the user, if any.
+        #[property(get, set = Self::set_custom_color, explicit_notify, nullable)]
+        pub(super) custom_color: RefCell<Option<gdk::RGBA>>,
+        #[property(get, set, builder(LineStyle::default()))]
+        pub(super) line_style: Cell<LineStyle>,
+        #[property(get = Self::is_active, set = Self::set_is_active)]
         pub(super) is_active: PhantomData<bool>,
+        /// Whether the trace can be removed, as those of expressions can.
+        #[property(get, set, construct_only)]
+        pub(super) is_removable: Cell<bool>,
 
         #[template_child]
         pub(super) color_widget: TemplateChild<ColorWidget>,
@@ -40,6 +55,24 @@ mod imp {
         fn class_init(klass: &mut Self::Class) {
             klass.set_css_name("plotviewfilterrow");
             klass.bind_template();
+
+            klass.install_action_async("row.choose-color", None, |obj, _, _| async move {
+                if let Err(err) = obj.choose_color().await {
+                    if !err.matches(gtk::DialogError::Dismissed) {
+                        tracing::error!("Failed to choose color: {:?}", err);
+                    }
+                }
+            });
+
+            klass.install_action("row.reset-color", None, |obj, _, _| {
+                obj.set_custom_color(None::<gdk::RGBA>);
+            });
+
+            klass.install_property_action("row.line-style", "line-style");
+
+            klass.install_action("row.remove", None, |obj, _, _| {
+                obj.emit_by_name::<()>("remove-requested", &[]);
+            });
         }
 
         fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
@@ -49,18 +82,39 @@ mod imp {
 
     #[glib::derived_properties]
     impl ObjectImpl for PlotViewFilterRow {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: Lazy<Vec<Signal>> =
+                Lazy::new(|| vec![Signal::builder("remove-requested").build()]);
+
+            SIGNALS.as_ref()
+        }
+
         fn constructed(&self) {
             self.parent_constructed();
 
             let obj = self.obj();
 
             self.title_label.set_label(&obj.name());
-            self.color_widget.set_color(obj.color());
+            obj.action_set_enabled("row.reset-color", false);
+            obj.action_set_enabled("row.remove", obj.is_removable());
 
             self.check_button
                 .connect_active_notify(clone!(@weak obj => move |_| {
                     obj.notify_is_active();
                 }));
+
+            // Rows can be dropped on the chart to move the trace to another
+            // Y axis.
+            let drag_source = gtk::DragSource::new();
+            drag_source.set_actions(gdk::DragAction::MOVE);
+            drag_source.connect_prepare(clone!(@weak obj => @default-return None, move |_, _, _| {
+                Some(gdk::ContentProvider::for_value(&obj.name().to_value()))
+            }));
+            drag_source.connect_drag_begin(clone!(@weak obj => move |source, _| {
+                let icon = gtk::WidgetPaintable::new(Some(&obj));
+                source.set_icon(Some(&icon), 0, 0);
+            }));
+            obj.add_controller(drag_source);
         }
     }
 
@@ -68,9 +122,33 @@ mod imp {
     impl ListBoxRowImpl for PlotViewFilterRow {}
 
     impl PlotViewFilterRow {
+        fn color(&self) -> gdk::RGBA {
+            self.color_widget.color()
+        }
+
+        fn set_color(&self, color: gdk::RGBA) {
+            self.color_widget.set_color(color);
+        }
+
+        fn set_custom_color(&self, custom_color: Option<gdk::RGBA>) {
+            if *self.custom_color.borrow() == custom_color {
+                return;
+            }
+
+            let obj = self.obj();
+
+            self.custom_color.replace(custom_color);
+            obj.action_set_enabled("row.reset-color", custom_color.is_some());
+            obj.notify_custom_color();
+        }
+
         fn is_active(&self) -> bool {
             self.check_button.is_active()
         }
+
+        fn set_is_active(&self, is_active: bool) {
+            self.check_button.set_active(is_active);
+        }
     }
 }
 
@@ -80,13 +158,40 @@ glib::wrapper! {
 }
 
 impl PlotViewFilterRow {
-    pub fn new(name: &str, color: gdk::RGBA) -> Self {
+    pub fn new(name: &str, color: gdk::RGBA, is_removable: bool) -> Self {
         glib::Object::builder()
             .property("name", name)
             .property("color", color)
+            .property("is-removable", is_removable)
             .build()
     }
 
+    pub fn connect_remove_requested(&self, cb: impl Fn(&Self) + 'static) -> glib::SignalHandlerId {
+        self.connect_closure(
+            "remove-requested",
+            true,
+            closure_local!(|obj: &Self| {
+                cb(obj);
+            }),
+        )
+    }
+
+    async fn choose_color(&self) -> Result<(), glib::Error> {
+        let dialog = gtk::ColorDialog::builder()
+            .title(gettext("Trace Color"))
+            .modal(true)
+            .with_alpha(false)
+            .build();
+        let color = dialog
+            .choose_rgba_future(
+                self.root().and_downcast_ref::<gtk::Window>(),
+                Some(&self.color()),
+            )
+            .await?;
+        self.set_custom_color(Some(color));
+        Ok(())
+    }
+
     pub fn handle_activation(&self) {
         let was_activated = self.imp().check_button.activate();
         debug_assert!(was_activated);
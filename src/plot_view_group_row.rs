@@ -0,0 +1,103 @@
+use gtk::{glib, prelude::*, subclass::prelude::*};
+
+mod imp {
+    use std::cell::{Cell, OnceCell};
+
+    use super::*;
+
+    #[derive(Default, glib::Properties, gtk::CompositeTemplate)]
+    #[properties(wrapper_type = super::PlotViewGroupRow)]
+    #[template(resource = "/io/github/seadve/Spicy/ui/plot_view_group_row.ui")]
+    pub struct PlotViewGroupRow {
+        /// Identifies the group across rebuilds of the list.
+        #[property(get, set, construct_only)]
+        pub(super) key: OnceCell<String>,
+        #[property(get, set, construct_only)]
+        pub(super) title: OnceCell<String>,
+        /// Number of vectors within the group.
+        #[property(get, set, construct_only)]
+        pub(super) count: Cell<u32>,
+        #[property(get, set = Self::set_is_expanded, explicit_notify)]
+        pub(super) is_expanded: Cell<bool>,
+
+        #[template_child]
+        pub(super) expander_icon: TemplateChild<gtk::Image>,
+        #[template_child]
+        pub(super) title_label: TemplateChild<gtk::Label>,
+        #[template_child]
+        pub(super) count_label: TemplateChild<gtk::Label>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for PlotViewGroupRow {
+        const NAME: &'static str = "SpicyPlotViewGroupRow";
+        type Type = super::PlotViewGroupRow;
+        type ParentType = gtk::ListBoxRow;
+
+        fn class_init(klass: &mut Self::Class) {
+            klass.set_css_name("plotviewgrouprow");
+            klass.bind_template();
+        }
+
+        fn instance_init(obj: &glib::subclass::InitializingObject<Self>) {
+            obj.init_template();
+        }
+    }
+
+    #[glib::derived_properties]
+    impl ObjectImpl for PlotViewGroupRow {
+        fn constructed(&self) {
+            self.parent_constructed();
+
+            let obj = self.obj();
+
+            self.title_label.set_label(&obj.title());
+            self.count_label.set_label(&obj.count().to_string());
+            self.update_expander_icon();
+        }
+    }
+
+    impl WidgetImpl for PlotViewGroupRow {}
+    impl ListBoxRowImpl for PlotViewGroupRow {}
+
+    impl PlotViewGroupRow {
+        fn set_is_expanded(&self, is_expanded: bool) {
+            if self.is_expanded.get() == is_expanded {
+                return;
+            }
+
+            self.is_expanded.set(is_expanded);
+            self.update_expander_icon();
+            self.obj().notify_is_expanded();
+        }
+
+        fn update_expander_icon(&self) {
+            self.expander_icon
+                .set_icon_name(Some(if self.is_expanded.get() {
+                    "pan-down-symbolic"
+                } else {
+                    "pan-end-symbolic"
+                }));
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct PlotViewGroupRow(ObjectSubclass<imp::PlotViewGroupRow>)
+        @extends gtk::Widget, gtk::ListBoxRow;
+}
+
+impl PlotViewGroupRow {
+    pub fn new(key: &str, title: &str, count: u32, is_expanded: bool) -> Self {
+        glib::Object::builder()
+            .property("key", key)
+            .property("title", title)
+            .property("count", count)
+            .property("is-expanded", is_expanded)
+            .build()
+    }
+
+    pub fn handle_activation(&self) {
+        self.set_is_expanded(!self.is_expanded());
+    }
+}
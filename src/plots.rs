@@ -1,7 +1,7 @@
 use anyhow::Result;
 use gtk::{gio, glib, prelude::*, subclass::prelude::*};
 
-use crate::{ngspice::NgSpice, plot::Plot};
+use crate::{backend::Backend, plot::Plot};
 
 mod imp {
     use std::cell::RefCell;
@@ -10,7 +10,9 @@ mod imp {
 
     #[derive(Default)]
     pub struct Plots {
+        /// Plots of the simulator, listed first.
         pub(super) inner: RefCell<Vec<Plot>>,
+        pub(super) imported: RefCell<Vec<Plot>>,
     }
 
     #[glib::object_subclass]
@@ -28,13 +30,15 @@ mod imp {
         }
 
         fn n_items(&self) -> u32 {
-            self.inner.borrow().len() as u32
+            (self.inner.borrow().len() + self.imported.borrow().len()) as u32
         }
 
         fn item(&self, position: u32) -> Option<glib::Object> {
             self.inner
                 .borrow()
-                .get(position as usize)
+                .iter()
+                .chain(self.imported.borrow().iter())
+                .nth(position as usize)
                 .map(|plot| plot.clone().upcast())
         }
     }
@@ -50,15 +54,15 @@ impl Plots {
         glib::Object::new()
     }
 
-    pub async fn update(&self, ngspice: &NgSpice) -> Result<()> {
+    pub async fn update(&self, backend: &impl Backend) -> Result<()> {
         let imp = self.imp();
 
         let prev_len = imp.inner.borrow().len() as u32;
 
         imp.inner.borrow_mut().clear();
 
-        let current_plot_name = ngspice.current_plot_name().await?;
-        let new_plots = ngspice
+        let current_plot_name = backend.current_plot_name().await?;
+        let new_plots = backend
             .all_plot_names()
             .await?
             .into_iter()
@@ -80,6 +84,19 @@ impl Plots {
 
         Ok(())
     }
+
+    /// Appends plots that don't come from the simulator, and are thus kept
+    /// across [`Self::update`].
+    pub fn add_imported(&self, plots: impl IntoIterator<Item = Plot>) {
+        let imp = self.imp();
+
+        let position = self.n_items();
+
+        imp.imported.borrow_mut().extend(plots);
+
+        let n_added = self.n_items() - position;
+        self.items_changed(position, 0, n_added);
+    }
 }
 
 impl Default for Plots {
@@ -87,3 +104,103 @@ impl Default for Plots {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use gtk::glib;
+
+    use super::*;
+    use crate::{
+        backend::mock::MockBackend,
+        vector::{Vector, VectorData, VectorType},
+    };
+
+    fn plot_names(plots: &Plots) -> Vec<(String, bool)> {
+        plots
+            .iter::<Plot>()
+            .map(|plot| {
+                let plot = plot.unwrap();
+                (plot.name(), plot.is_current())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn update() {
+        let backend = MockBackend::new();
+        backend.expect_command("tran 1u 1m", |backend| {
+            backend.add_plot(
+                "tran1",
+                vec![Vector {
+                    name: "time".to_string(),
+                    vtype: VectorType::Time,
+                    data: VectorData::Real(vec![0.0, 1e-3]),
+                }],
+            );
+            Ok(())
+        });
+
+        let plots = Plots::new();
+        let main_context = glib::MainContext::new();
+
+        main_context.block_on(plots.update(&backend)).unwrap();
+        assert_eq!(plot_names(&plots), [("const".to_string(), true)]);
+
+        main_context
+            .block_on(backend.command("tran 1u 1m"))
+            .unwrap();
+        main_context.block_on(plots.update(&backend)).unwrap();
+        assert_eq!(
+            plot_names(&plots),
+            [("tran1".to_string(), true), ("const".to_string(), false)]
+        );
+        assert!(backend.is_script_done());
+    }
+
+    #[test]
+    fn update_items_changed() {
+        let backend = MockBackend::new();
+        backend.add_plot("tran1", Vec::new());
+
+        let plots = Plots::new();
+        let changes = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        plots.connect_items_changed({
+            let changes = changes.clone();
+            move |_, position, removed, added| {
+                changes.borrow_mut().push((position, removed, added));
+            }
+        });
+
+        let main_context = glib::MainContext::new();
+        main_context.block_on(plots.update(&backend)).unwrap();
+        main_context.block_on(plots.update(&backend)).unwrap();
+
+        assert_eq!(*changes.borrow(), [(0, 0, 2), (0, 2, 2)]);
+    }
+
+    #[test]
+    fn imported_kept_across_update() {
+        let backend = MockBackend::new();
+
+        let plots = Plots::new();
+        plots.add_imported([Plot::imported(
+            "Transient Analysis (rc.raw)",
+            "Transient Analysis",
+            Vec::new(),
+        )]);
+
+        let main_context = glib::MainContext::new();
+        main_context.block_on(plots.update(&backend)).unwrap();
+
+        assert_eq!(
+            plot_names(&plots),
+            [
+                ("const".to_string(), true),
+                ("Transient Analysis (rc.raw)".to_string(), false)
+            ]
+        );
+
+        let imported = plots.item(1).and_downcast::<Plot>().unwrap();
+        assert!(imported.imported_vectors().is_some());
+    }
+}
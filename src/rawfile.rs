@@ -0,0 +1,476 @@
+//! Reading and writing of SPICE rawfiles, the format ngspice's `write`
+//! command and `-r` option produce.
+//!
+//! A rawfile is a sequence of plots, each made of a text header describing
+//! its vectors followed by their values, stored point by point either as
+//! text (ASCII) or as doubles (binary).
+
+use std::fmt::Write;
+
+use anyhow::{bail, ensure, Context, Result};
+
+use crate::vector::{Complex, Vector, VectorData, VectorType};
+
+/// How the values of a rawfile are stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Ascii,
+    Binary,
+}
+
+/// A plot read from or to be written to a rawfile.
+#[derive(Debug)]
+pub struct RawPlot {
+    pub title: String,
+    pub date: String,
+    /// The analysis that produced the plot, e.g., `Transient Analysis`.
+    pub name: String,
+    /// Vectors with the same length, the first being the scale by convention.
+    pub vectors: Vec<Vector>,
+}
+
+/// Returns the position of the scale among the `vectors` of a plot, which
+/// the format puts first. Plots of a single point, like operating points,
+/// have none.
+pub fn scale_index(vectors: &[Vector]) -> Option<usize> {
+    vectors
+        .first()
+        .filter(|scale| data_len(&scale.data) > 1)
+        .map(|_| 0)
+}
+
+/// Parses every plot in a rawfile, in either format.
+pub fn parse(bytes: &[u8]) -> Result<Vec<RawPlot>> {
+    let mut reader = Reader { bytes, pos: 0 };
+
+    let mut plots = Vec::new();
+    while reader.skip_whitespace() {
+        let plot = parse_plot(&mut reader)
+            .with_context(|| format!("Failed to parse plot {}", plots.len() + 1))?;
+        plots.push(plot);
+    }
+
+    Ok(plots)
+}
+
+/// Serializes plots into a rawfile.
+pub fn write(plots: &[RawPlot], format: Format) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for plot in plots {
+        let n_points = plot.vectors.first().map_or(0, |vector| data_len(&vector.data));
+        ensure!(
+            plot.vectors
+                .iter()
+                .all(|vector| data_len(&vector.data) == n_points),
+            "Vectors of plot `{}` have different lengths",
+            plot.name
+        );
+
+        // Variables are whitespace-separated fields.
+        if let Some(vector) = plot
+            .vectors
+            .iter()
+            .find(|vector| vector.name.contains(char::is_whitespace))
+        {
+            bail!("Vector name `{}` contains whitespace", vector.name);
+        }
+
+        let is_complex = plot.vectors.iter().any(|vector| vector.data.is_complex());
+
+        let mut header = String::new();
+        writeln!(header, "Title: {}", plot.title).unwrap();
+        writeln!(header, "Date: {}", plot.date).unwrap();
+        writeln!(header, "Plotname: {}", plot.name).unwrap();
+        writeln!(header, "Flags: {}", if is_complex { "complex" } else { "real" }).unwrap();
+        writeln!(header, "No. Variables: {}", plot.vectors.len()).unwrap();
+        writeln!(header, "No. Points: {}", n_points).unwrap();
+        writeln!(header, "Variables:").unwrap();
+        for (index, vector) in plot.vectors.iter().enumerate() {
+            writeln!(header, "\t{}\t{}\t{}", index, vector.name, vector.vtype.name()).unwrap();
+        }
+        bytes.extend_from_slice(header.as_bytes());
+
+        match format {
+            Format::Ascii => {
+                let mut values = String::from("Values:\n");
+                for point in 0..n_points {
+                    write!(values, " {}", point).unwrap();
+                    for vector in &plot.vectors {
+                        let value = sample(&vector.data, point);
+                        if is_complex {
+                            writeln!(values, "\t{:.15e},{:.15e}", value.re, value.im).unwrap();
+                        } else {
+                            writeln!(values, "\t{:.15e}", value.re).unwrap();
+                        }
+                    }
+                    values.push('\n');
+                }
+                bytes.extend_from_slice(values.as_bytes());
+            }
+            Format::Binary => {
+                bytes.extend_from_slice(b"Binary:\n");
+                for point in 0..n_points {
+                    for vector in &plot.vectors {
+                        let value = sample(&vector.data, point);
+                        bytes.extend_from_slice(&value.re.to_le_bytes());
+                        if is_complex {
+                            bytes.extend_from_slice(&value.im.to_le_bytes());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(bytes)
+}
+
+fn parse_plot(reader: &mut Reader<'_>) -> Result<RawPlot> {
+    let mut title = String::new();
+    let mut date = String::new();
+    let mut name = String::new();
+    let mut is_complex = false;
+    let mut n_variables = None;
+    let mut n_points = None;
+    let mut variables = Vec::new();
+
+    let format = loop {
+        let line = reader.read_line()?;
+        let Some((key, value)) = line.split_once(':') else {
+            bail!("Expected a header line, got `{}`", line);
+        };
+
+        match key.trim() {
+            "Title" => title = value.trim().to_string(),
+            "Date" => date = value.trim().to_string(),
+            "Plotname" => name = value.trim().to_string(),
+            "Flags" => is_complex = value.split_whitespace().any(|flag| flag == "complex"),
+            "No. Variables" => n_variables = Some(value.trim().parse::<usize>()?),
+            "No. Points" => n_points = Some(value.trim().parse::<usize>()?),
+            "Variables" => {
+                let n_variables = n_variables.context("Variables listed before their count")?;
+
+                // Some writers put the first variable on the same line.
+                let mut first_line = Some(value.trim().to_string()).filter(|v| !v.is_empty());
+                for _ in 0..n_variables {
+                    let line = match first_line.take() {
+                        Some(line) => line,
+                        None => reader.read_line()?,
+                    };
+                    variables.push(parse_variable(&line)?);
+                }
+            }
+            "Values" => break Format::Ascii,
+            "Binary" => break Format::Binary,
+            // Unused lines, such as `Command` and `Option`
+            _ => {}
+        }
+    };
+
+    let n_points = n_points.context("Missing number of points")?;
+    ensure!(
+        Some(variables.len()) == n_variables,
+        "Missing variable declarations"
+    );
+
+    let mut vectors = variables
+        .into_iter()
+        .map(|(name, vtype)| Vector {
+            name,
+            vtype,
+            data: VectorData::empty(!is_complex),
+        })
+        .collect::<Vec<_>>();
+
+    for point in 0..n_points {
+        if format == Format::Ascii {
+            let index = reader.read_token()?;
+            ensure!(
+                index.parse::<usize>().ok() == Some(point),
+                "Expected point {}, got `{}`",
+                point,
+                index
+            );
+        }
+
+        for vector in &mut vectors {
+            let value = match format {
+                Format::Ascii => parse_ascii_value(&reader.read_token()?)?,
+                Format::Binary if is_complex => {
+                    Complex::new(reader.read_f64()?, reader.read_f64()?)
+                }
+                Format::Binary => Complex::new(reader.read_f64()?, 0.0),
+            };
+            vector.data.push(value);
+        }
+    }
+
+    Ok(RawPlot {
+        title,
+        date,
+        name,
+        vectors,
+    })
+}
+
+/// Parses a variable declaration, `<index> <name> <type> [<param>=<value>...]`.
+fn parse_variable(line: &str) -> Result<(String, VectorType)> {
+    let mut fields = line.split_whitespace();
+    let (Some(_index), Some(name), Some(vtype)) = (fields.next(), fields.next(), fields.next())
+    else {
+        bail!("Invalid variable declaration `{}`", line);
+    };
+    Ok((name.to_string(), VectorType::from_name(vtype)))
+}
+
+/// Parses an ASCII value, written as `<re>,<im>` if complex.
+fn parse_ascii_value(token: &str) -> Result<Complex> {
+    let value = if let Some((re, im)) = token.split_once(',') {
+        Complex::new(re.parse()?, im.parse()?)
+    } else {
+        Complex::new(token.parse()?, 0.0)
+    };
+    Ok(value)
+}
+
+fn data_len(data: &VectorData) -> usize {
+    match data {
+        VectorData::Real(real) => real.len(),
+        VectorData::Complex(complex) => complex.len(),
+    }
+}
+
+fn sample(data: &VectorData, index: usize) -> Complex {
+    match data {
+        VectorData::Real(real) => Complex::new(real[index], 0.0),
+        VectorData::Complex(complex) => complex[index],
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    /// Skips whitespace, and returns whether there is anything left to read.
+    fn skip_whitespace(&mut self) -> bool {
+        while let Some(&byte) = self.bytes.get(self.pos) {
+            if !byte.is_ascii_whitespace() {
+                return true;
+            }
+            self.pos += 1;
+        }
+        false
+    }
+
+    fn read_line(&mut self) -> Result<String> {
+        let rest = &self.bytes[self.pos..];
+        ensure!(!rest.is_empty(), "Unexpected end of file");
+
+        let len = rest.iter().position(|&b| b == b'\n').unwrap_or(rest.len());
+        self.pos += (len + 1).min(rest.len());
+
+        let line = String::from_utf8_lossy(&rest[..len]);
+        Ok(line.trim_end_matches('\r').to_string())
+    }
+
+    /// Reads the next whitespace-separated word.
+    fn read_token(&mut self) -> Result<String> {
+        ensure!(self.skip_whitespace(), "Unexpected end of file");
+
+        let rest = &self.bytes[self.pos..];
+        let len = rest
+            .iter()
+            .position(|b| b.is_ascii_whitespace())
+            .unwrap_or(rest.len());
+        self.pos += len;
+
+        Ok(String::from_utf8_lossy(&rest[..len]).into_owned())
+    }
+
+    /// Reads a binary double, which ngspice writes in the machine's byte
+    /// order, which is little-endian on every platform it supports.
+    fn read_f64(&mut self) -> Result<f64> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos + 8)
+            .context("Unexpected end of file")?;
+        self.pos += 8;
+        Ok(f64::from_le_bytes(bytes.try_into().unwrap()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn binary(header: &str, values: &[f64]) -> Vec<u8> {
+        let mut bytes = header.as_bytes().to_vec();
+        for value in values {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    fn sample_plots() -> Vec<RawPlot> {
+        vec![
+            RawPlot {
+                title: "rc".to_string(),
+                date: "today".to_string(),
+                name: "AC Analysis".to_string(),
+                vectors: vec![
+                    Vector {
+                        name: "frequency".to_string(),
+                        vtype: VectorType::Frequency,
+                        data: VectorData::Real(vec![10.0, 100.0]),
+                    },
+                    Vector {
+                        name: "v(out)".to_string(),
+                        vtype: VectorType::Voltage,
+                        data: VectorData::Complex(vec![
+                            Complex::new(0.5, -0.5),
+                            Complex::new(0.1, -0.3),
+                        ]),
+                    },
+                ],
+            },
+            RawPlot {
+                title: "rc".to_string(),
+                date: "today".to_string(),
+                name: "Operating Point".to_string(),
+                vectors: vec![Vector {
+                    name: "v(out)".to_string(),
+                    vtype: VectorType::Voltage,
+                    data: VectorData::Real(vec![1.0]),
+                }],
+            },
+        ]
+    }
+
+    fn assert_round_trip(format: Format) {
+        let plots = sample_plots();
+        let parsed = parse(&write(&plots, format).unwrap()).unwrap();
+        assert_eq!(parsed.len(), plots.len());
+
+        for (parsed, plot) in parsed.iter().zip(&plots) {
+            assert_eq!(parsed.title, plot.title);
+            assert_eq!(parsed.date, plot.date);
+            assert_eq!(parsed.name, plot.name);
+            assert_eq!(parsed.vectors.len(), plot.vectors.len());
+
+            for (parsed, vector) in parsed.vectors.iter().zip(&plot.vectors) {
+                assert_eq!(parsed.name, vector.name);
+                assert_eq!(parsed.vtype, vector.vtype);
+
+                let n_points = data_len(&vector.data);
+                assert_eq!(data_len(&parsed.data), n_points);
+                for point in 0..n_points {
+                    assert_eq!(sample(&parsed.data, point), sample(&vector.data, point));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn parse_binary_real() {
+        let bytes = binary(
+            "Title: rc\nDate: today\nPlotname: Transient Analysis\nFlags: real\n\
+             No. Variables: 2\nNo. Points: 3\n\
+             Variables:\n\t0\ttime\ttime\n\t1\tv(out)\tvoltage\nBinary:\n",
+            &[0.0, 0.0, 1e-3, 0.5, 2e-3, 0.75],
+        );
+
+        let plots = parse(&bytes).unwrap();
+        assert_eq!(plots.len(), 1);
+
+        let plot = &plots[0];
+        assert_eq!(plot.title, "rc");
+        assert_eq!(plot.name, "Transient Analysis");
+        assert_eq!(plot.vectors[0].name, "time");
+        assert_eq!(plot.vectors[0].vtype, VectorType::Time);
+        assert_eq!(plot.vectors[0].data.to_real(), [0.0, 1e-3, 2e-3]);
+        assert_eq!(plot.vectors[1].name, "v(out)");
+        assert_eq!(plot.vectors[1].vtype, VectorType::Voltage);
+        assert_eq!(plot.vectors[1].data.to_real(), [0.0, 0.5, 0.75]);
+    }
+
+    #[test]
+    fn parse_ascii_complex() {
+        let bytes = b"Title: rc\nDate: today\nPlotname: AC Analysis\nFlags: complex\n\
+            No. Variables: 2\nNo. Points: 2\n\
+            Variables:\n\t0\tfrequency\tfrequency grid=3\n\t1\tv(out)\tvoltage\n\
+            Values:\n 0\t1.0e+01,0.0e+00\n\t5.0e-01,-5.0e-01\n\n \
+            1\t1.0e+02,0.0e+00\n\t1.0e-01,-3.0e-01\n\n";
+
+        let plots = parse(bytes).unwrap();
+        assert_eq!(plots.len(), 1);
+
+        let plot = &plots[0];
+        assert_eq!(plot.vectors[0].vtype, VectorType::Frequency);
+        assert_eq!(plot.vectors[0].data.to_real(), [10.0, 100.0]);
+
+        let VectorData::Complex(ref data) = plot.vectors[1].data else {
+            panic!("expected complex data");
+        };
+        assert_eq!(data, &[Complex::new(0.5, -0.5), Complex::new(0.1, -0.3)]);
+    }
+
+    #[test]
+    fn parse_truncated() {
+        let bytes = binary(
+            "Title: rc\nDate: today\nPlotname: Transient Analysis\nFlags: real\n\
+             No. Variables: 1\nNo. Points: 2\nVariables:\n\t0\ttime\ttime\nBinary:\n",
+            &[0.0],
+        );
+        assert!(parse(&bytes).is_err());
+    }
+
+    #[test]
+    fn round_trip_ascii() {
+        assert_round_trip(Format::Ascii);
+    }
+
+    #[test]
+    fn round_trip_binary() {
+        assert_round_trip(Format::Binary);
+    }
+
+    #[test]
+    fn write_different_lengths() {
+        let plots = [RawPlot {
+            title: String::new(),
+            date: String::new(),
+            name: "Transient Analysis".to_string(),
+            vectors: vec![
+                Vector {
+                    name: "time".to_string(),
+                    vtype: VectorType::Time,
+                    data: VectorData::Real(vec![0.0, 1.0]),
+                },
+                Vector {
+                    name: "v(out)".to_string(),
+                    vtype: VectorType::Voltage,
+                    data: VectorData::Real(vec![0.0]),
+                },
+            ],
+        }];
+        assert!(write(&plots, Format::Binary).is_err());
+    }
+
+    #[test]
+    fn write_whitespace_name() {
+        let plots = [RawPlot {
+            title: String::new(),
+            date: String::new(),
+            name: "Transient Analysis".to_string(),
+            vectors: vec![Vector {
+                name: "v(out) - v(in)".to_string(),
+                vtype: VectorType::Voltage,
+                data: VectorData::Real(vec![0.0]),
+            }],
+        }];
+        assert!(write(&plots, Format::Ascii).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Formatting of values with SI prefixes (e.g., `1.5 mV` rather than
+//! `0.0015 V`).
+
+const PREFIXES: [(i32, &str); 10] = [
+    (-15, "f"),
+    (-12, "p"),
+    (-9, "n"),
+    (-6, "µ"),
+    (-3, "m"),
+    (0, ""),
+    (3, "k"),
+    (6, "M"),
+    (9, "G"),
+    (12, "T"),
+];
+
+/// Number of significant digits values are shown with by default.
+const DEFAULT_SIGNIFICANT_DIGITS: i32 = 4;
+
+/// Units that are shown without prefixes, as they are logarithmic, angles,
+/// or ratios.
+const UNPREFIXED_UNITS: [&str; 4] = ["dB", "°", "°C", "%"];
+
+/// Formats `value` with four significant digits and the prefix that keeps
+/// the number between 1 and 1000, followed by `unit`.
+pub fn format(value: f64, unit: &str) -> String {
+    format_with_precision(value, unit, DEFAULT_SIGNIFICANT_DIGITS)
+}
+
+/// Like [`format`], but with the given number of significant digits.
+/// Trailing zeros after the decimal point are removed.
+pub fn format_with_precision(value: f64, unit: &str, significant_digits: i32) -> String {
+    if !value.is_finite() || value == 0.0 {
+        return join(&value.to_string(), "", unit);
+    }
+
+    if !takes_prefix(unit) {
+        let (number, _) = round(value, 0, significant_digits);
+        return join(&number, "", unit);
+    }
+
+    let mut exponent = prefix_exponent(value);
+    let (mut number, rounded) = round(value, exponent, significant_digits);
+    // Rounding may carry over to the next prefix (e.g., 999.99 to 1000).
+    if rounded.abs() >= 1000.0 && exponent < PREFIXES[PREFIXES.len() - 1].0 {
+        exponent += 3;
+        (number, _) = round(value, exponent, significant_digits);
+    }
+
+    join(&number, prefix(exponent), unit)
+}
+
+/// Returns whether values in `unit` are shown with SI prefixes.
+pub fn takes_prefix(unit: &str) -> bool {
+    !UNPREFIXED_UNITS.contains(&unit)
+}
+
+/// A prefix and number of decimals shared by the tick labels of an axis, so
+/// that they read `0 2.5 5 7.5` with the prefix in the axis description,
+/// rather than `0 0.0000000025 0.000000005 0.0000000075`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisScale {
+    exponent: i32,
+    decimals: usize,
+}
+
+impl AxisScale {
+    /// Picks the prefix from the largest magnitude within the visible
+    /// `range`, and enough decimals to tell apart about `tick_count` ticks
+    /// over it.
+    pub fn new((min, max): (f64, f64), tick_count: usize, unit: &str) -> Self {
+        let magnitude = f64::max(min.abs(), max.abs());
+        let exponent = if takes_prefix(unit) && magnitude.is_finite() && magnitude > 0.0 {
+            prefix_exponent(magnitude)
+        } else {
+            0
+        };
+
+        let step = (max - min) / tick_count.max(1) as f64 / 10_f64.powi(exponent);
+        let decimals = if step.is_finite() && step > 0.0 {
+            (-step.log10().floor()).max(0.0) as usize
+        } else {
+            0
+        };
+
+        Self { exponent, decimals }
+    }
+
+    /// Formats a tick value, without prefix or unit.
+    pub fn format(&self, value: f64) -> String {
+        let number = format!("{:.*}", self.decimals, value / 10_f64.powi(self.exponent));
+        // Values that round to zero from below would show as `-0`.
+        if number.starts_with('-') && number.trim_start_matches(['-', '0', '.']).is_empty() {
+            number[1..].to_string()
+        } else {
+            number
+        }
+    }
+
+    /// Returns `unit` with the prefix of the ticks, or a power of ten if
+    /// there is no unit to prefix.
+    pub fn unit(&self, unit: &str) -> String {
+        if unit.is_empty() && self.exponent != 0 {
+            format!("×10^{}", self.exponent)
+        } else {
+            format!("{}{}", prefix(self.exponent), unit)
+        }
+    }
+}
+
+/// Returns the exponent of the prefix that keeps `value` between 1 and 1000,
+/// within the prefixes there are.
+fn prefix_exponent(value: f64) -> i32 {
+    let exponent = (value.abs().log10() / 3.0).floor() as i32 * 3;
+    exponent.clamp(PREFIXES[0].0, PREFIXES[PREFIXES.len() - 1].0)
+}
+
+fn prefix(exponent: i32) -> &'static str {
+    PREFIXES
+        .iter()
+        .find(|(prefix_exponent, _)| *prefix_exponent == exponent)
+        .map(|(_, prefix)| *prefix)
+        .unwrap()
+}
+
+/// Scales `value` by `10^-exponent` and rounds it to the significant digits,
+/// returning it as text and as a number.
+fn round(value: f64, exponent: i32, significant_digits: i32) -> (String, f64) {
+    let scaled = value / 10_f64.powi(exponent);
+    let integer_digits = scaled.abs().log10().floor() as i32 + 1;
+    let decimals = (significant_digits - integer_digits).max(0) as usize;
+
+    let mut number = format!("{:.*}", decimals, scaled);
+    if number.contains('.') {
+        number = number
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string();
+    }
+    let rounded = number.parse().unwrap_or(scaled);
+    (number, rounded)
+}
+
+fn join(number: &str, prefix: &str, unit: &str) -> String {
+    if prefix.is_empty() && unit.is_empty() {
+        number.to_string()
+    } else {
+        format!("{} {}{}", number, prefix, unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefixes() {
+        assert_eq!(format(0.0015, "V"), "1.5 mV");
+        assert_eq!(format(-2.2e-9, "s"), "-2.2 ns");
+        assert_eq!(format(12_346.0, "Hz"), "12.35 kHz");
+        assert_eq!(format(1.0, "A"), "1 A");
+        assert_eq!(format(0.5, ""), "500 m");
+        assert_eq!(format(3.0, ""), "3");
+    }
+
+    #[test]
+    fn carries_to_next_prefix() {
+        assert_eq!(format(999.99, "V"), "1 kV");
+        assert_eq!(format(0.000_999_99, "A"), "1 mA");
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(format(1e-18, "F"), "0.001 fF");
+        assert_eq!(format(2e15, "Hz"), "2000 THz");
+        assert_eq!(format(0.0, "V"), "0 V");
+        assert_eq!(format(f64::NAN, "V"), "NaN V");
+    }
+
+    #[test]
+    fn unprefixed_units() {
+        assert_eq!(format(-3.0103, "dB"), "-3.01 dB");
+        assert_eq!(format(0.0015, "°"), "0.0015 °");
+    }
+
+    #[test]
+    fn axis_scale() {
+        let scale = AxisScale::new((0.0, 10e-9), 10, "s");
+        assert_eq!(scale.unit("s"), "ns");
+        assert_eq!(scale.format(3e-9), "3");
+        assert_eq!(scale.format(5e-9), "5");
+
+        let scale = AxisScale::new((-2e-3, 1e-3), 10, "V");
+        assert_eq!(scale.unit("V"), "mV");
+        assert_eq!(scale.format(-1.5e-3), "-1.5");
+        assert_eq!(scale.format(-1e-19), "0.0");
+
+        let scale = AxisScale::new((0.0, 2e3), 10, "");
+        assert_eq!(scale.unit(""), "×10^3");
+        assert_eq!(scale.format(500.0), "0.5");
+
+        let scale = AxisScale::new((-90.0, 90.0), 10, "°");
+        assert_eq!(scale.unit("°"), "°");
+        assert_eq!(scale.format(45.0), "45");
+    }
+
+    #[test]
+    fn precision() {
+        assert_eq!(format_with_precision(1.23456, "V", 2), "1.2 V");
+        assert_eq!(format_with_precision(123.456, "V", 2), "123 V");
+    }
+}
@@ -0,0 +1,223 @@
+use std::f64::consts::PI;
+
+/// A complex sample, as produced by AC and other small-signal analyses.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Complex {
+    pub re: f64,
+    pub im: f64,
+}
+
+impl Complex {
+    pub fn new(re: f64, im: f64) -> Self {
+        Self { re, im }
+    }
+
+    pub fn norm(self) -> f64 {
+        self.re.hypot(self.im)
+    }
+
+    /// Returns the magnitude in decibels (20·log10).
+    pub fn db(self) -> f64 {
+        20.0 * self.norm().log10()
+    }
+
+    /// Returns the phase in degrees, in the range (-180, 180].
+    pub fn arg_deg(self) -> f64 {
+        self.im.atan2(self.re) * 180.0 / PI
+    }
+}
+
+/// The quantity a vector holds, mirroring ngspice's vector types.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VectorType {
+    #[default]
+    NoType,
+    Time,
+    Frequency,
+    Voltage,
+    Current,
+    VoltageDensity,
+    CurrentDensity,
+    SqrVoltageDensity,
+    SqrCurrentDensity,
+    SqrVoltage,
+    SqrCurrent,
+    Pole,
+    Zero,
+    SParam,
+    Temperature,
+    Resistance,
+    Impedance,
+    Admittance,
+    Power,
+    Phase,
+    Decibel,
+    Capacitance,
+    Charge,
+}
+
+const TYPE_NAMES: &[(VectorType, &str)] = &[
+    (VectorType::NoType, "notype"),
+    (VectorType::Time, "time"),
+    (VectorType::Frequency, "frequency"),
+    (VectorType::Voltage, "voltage"),
+    (VectorType::Current, "current"),
+    (VectorType::VoltageDensity, "voltage-density"),
+    (VectorType::CurrentDensity, "current-density"),
+    (VectorType::SqrVoltageDensity, "sqr-voltage-density"),
+    (VectorType::SqrCurrentDensity, "sqr-current-density"),
+    (VectorType::SqrVoltage, "sqr-voltage"),
+    (VectorType::SqrCurrent, "sqr-current"),
+    (VectorType::Pole, "pole"),
+    (VectorType::Zero, "zero"),
+    (VectorType::SParam, "s-param"),
+    (VectorType::Temperature, "temp-sweep"),
+    (VectorType::Resistance, "res-sweep"),
+    (VectorType::Impedance, "impedance"),
+    (VectorType::Admittance, "admittance"),
+    (VectorType::Power, "power"),
+    (VectorType::Phase, "phase"),
+    (VectorType::Decibel, "decibel"),
+    (VectorType::Capacitance, "capacitance"),
+    (VectorType::Charge, "charge"),
+];
+
+impl VectorType {
+    /// Returns the SI unit symbol of this type, or an empty string if it is
+    /// dimensionless.
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::NoType | Self::Pole | Self::Zero | Self::SParam => "",
+            Self::Time => "s",
+            Self::Frequency => "Hz",
+            Self::Voltage => "V",
+            Self::Current => "A",
+            Self::VoltageDensity => "V/√Hz",
+            Self::CurrentDensity => "A/√Hz",
+            Self::SqrVoltageDensity => "V²/Hz",
+            Self::SqrCurrentDensity => "A²/Hz",
+            Self::SqrVoltage => "V²",
+            Self::SqrCurrent => "A²",
+            Self::Temperature => "°C",
+            Self::Resistance | Self::Impedance => "Ω",
+            Self::Admittance => "S",
+            Self::Power => "W",
+            Self::Phase => "°",
+            Self::Decibel => "dB",
+            Self::Capacitance => "F",
+            Self::Charge => "C",
+        }
+    }
+
+    /// Returns the type name ngspice uses in rawfiles and in `display`, e.g.,
+    /// `voltage` or `sqr-current-density`.
+    pub fn name(self) -> &'static str {
+        TYPE_NAMES
+            .iter()
+            .find(|(vtype, _)| *vtype == self)
+            .map_or("notype", |(_, name)| name)
+    }
+
+    /// Parses a type name returned by [`Self::name`].
+    pub fn from_name(name: &str) -> Self {
+        TYPE_NAMES
+            .iter()
+            .find(|(_, type_name)| *type_name == name)
+            .map_or(Self::NoType, |(vtype, _)| *vtype)
+    }
+
+    /// Returns the type ngspice gives to the scale vector named `name`.
+    pub fn for_scale_name(name: &str) -> Self {
+        match name {
+            "time" => Self::Time,
+            "frequency" => Self::Frequency,
+            "v-sweep" => Self::Voltage,
+            "i-sweep" => Self::Current,
+            "temp-sweep" => Self::Temperature,
+            "res-sweep" => Self::Resistance,
+            _ => Self::NoType,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VectorData {
+    Real(Vec<f64>),
+    Complex(Vec<Complex>),
+}
+
+impl VectorData {
+    pub fn is_complex(&self) -> bool {
+        matches!(self, Self::Complex(_))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Real(real) => real.len(),
+            Self::Complex(complex) => complex.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns empty data of the given kind.
+    pub fn empty(is_real: bool) -> Self {
+        if is_real {
+            Self::Real(Vec::new())
+        } else {
+            Self::Complex(Vec::new())
+        }
+    }
+
+    /// Appends a sample, dropping its imaginary part if the data is real.
+    pub fn push(&mut self, value: Complex) {
+        match self {
+            Self::Real(real) => real.push(value.re),
+            Self::Complex(complex) => complex.push(value),
+        }
+    }
+
+    /// Returns the real part of the sample at `index`.
+    pub fn real_at(&self, index: usize) -> Option<f64> {
+        match self {
+            Self::Real(real) => real.get(index).copied(),
+            Self::Complex(complex) => complex.get(index).map(|c| c.re),
+        }
+    }
+
+    /// Returns the real part of every sample.
+    pub fn to_real(&self) -> Vec<f64> {
+        match self {
+            Self::Real(real) => real.clone(),
+            Self::Complex(complex) => complex.iter().map(|c| c.re).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Vector {
+    pub name: String,
+    pub vtype: VectorType,
+    pub data: VectorData,
+}
+
+/// Guesses the position of the scale vector, the one every other vector of
+/// the plot is a function of, or returns `None` if the plot has no scale
+/// (e.g., an operating point). This is only for plots whose scale the
+/// simulator doesn't report.
+///
+/// ngspice gives the scale of each analysis a dedicated type and name: `time`
+/// for transients, `frequency` for AC, noise and S-parameter analyses, and
+/// `v-sweep`, `i-sweep`, `temp-sweep` or `res-sweep` for DC sweeps.
+pub fn find_scale(vectors: &[Vector]) -> Option<usize> {
+    vectors
+        .iter()
+        .position(|vector| matches!(vector.vtype, VectorType::Time | VectorType::Frequency))
+        .or_else(|| {
+            vectors
+                .iter()
+                .position(|vector| vector.name.ends_with("sweep"))
+        })
+}
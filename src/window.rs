@@ -1,11 +1,8 @@
-use std::{
-    error,
-    fmt::{self, Write},
-};
+use std::{error, fmt};
 
 use adw::{prelude::*, subclass::prelude::*};
-use anyhow::{bail, Context, Result};
-use elektron_ngspice::ComplexSlice;
+use anyhow::{ensure, Context, Result};
+use futures_channel::oneshot;
 use gettextrs::gettext;
 use gtk::{
     gio,
@@ -14,14 +11,19 @@ use gtk::{
 
 use crate::{
     application::Application,
+    backend::{self, Backend, PlotContent},
     circuit::Circuit,
     config::{APP_ID, PROFILE},
+    export::{DataFormat, ImageFormat},
     i18n::gettext_f,
-    ngspice::{Callbacks, NgSpice},
+    ngspice::{self, Callbacks, InitData, NgSpice},
     output_view::OutputView,
+    plot::Plot,
     plot_view::PlotView,
     plots::Plots,
     plots_dropdown::PlotsDropdown,
+    rawfile,
+    vector::{Complex, Vector, VectorData, VectorType},
 };
 
 /// Indicates that a task was cancelled.
@@ -36,8 +38,66 @@ impl fmt::Display for Cancelled {
 
 impl error::Error for Cancelled {}
 
+/// Commands that start or continue an analysis, and are thus run in
+/// ngspice's background thread.
+const SIMULATION_COMMANDS: &[&str] = &[
+    "ac", "dc", "disto", "noise", "op", "pz", "resume", "run", "sens", "sp", "tf", "tran",
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum SimulationState {
+    #[default]
+    Idle,
+    Running,
+    Halted,
+}
+
+/// What a command entered by the user turns out to be.
+#[derive(Debug, PartialEq, Eq)]
+enum RoutedCommand {
+    /// Loads the circuit being edited.
+    Source,
+    ShowPlot(String),
+    Clear,
+    /// Runs an analysis in the background thread.
+    Simulation,
+    /// Any other command, which was already run by the backend.
+    Done,
+}
+
+/// Sorts out the commands handled by the app, and runs the rest on `backend`
+/// right away.
+async fn route_command(backend: &impl Backend, command: &str) -> Result<RoutedCommand> {
+    let routed = match command.split_whitespace().collect::<Vec<_>>().as_slice() {
+        ["source"] => RoutedCommand::Source,
+        ["showplot"] => RoutedCommand::ShowPlot(backend.current_plot_name().await?),
+        ["showplot", plot_name] => RoutedCommand::ShowPlot(plot_name.to_string()),
+        ["clear"] => RoutedCommand::Clear,
+        [name, ..] if SIMULATION_COMMANDS.contains(name) => RoutedCommand::Simulation,
+        _ => {
+            backend.command(command).await?;
+            RoutedCommand::Done
+        }
+    };
+    Ok(routed)
+}
+
+fn ngspice_mode(settings: &gio::Settings) -> ngspice::Mode {
+    match settings.string("ngspice-mode").as_str() {
+        "subprocess" => ngspice::Mode::Subprocess,
+        _ => ngspice::Mode::SharedLibrary,
+    }
+}
+
+/// A plot that is being filled in by a running simulation.
+#[derive(Debug)]
+struct LivePlot {
+    name: String,
+    scale_index: usize,
+}
+
 mod imp {
-    use std::cell::OnceCell;
+    use std::cell::{Cell, OnceCell, RefCell};
 
     use super::*;
 
@@ -51,6 +111,12 @@ mod imp {
         #[template_child]
         pub(super) circuit_title_label: TemplateChild<gtk::Label>,
         #[template_child]
+        pub(super) halt_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) resume_button: TemplateChild<gtk::Button>,
+        #[template_child]
+        pub(super) cancel_button: TemplateChild<gtk::Button>,
+        #[template_child]
         pub(super) progress_bar: TemplateChild<gtk::ProgressBar>,
         #[template_child]
         pub(super) circuit_view: TemplateChild<gtk_source::View>,
@@ -70,6 +136,14 @@ mod imp {
 
         pub(super) ngspice: OnceCell<NgSpice>,
         pub(super) plots: Plots,
+        pub(super) live_plot: RefCell<Option<LivePlot>>,
+        pub(super) shown_plot_name: RefCell<String>,
+        pub(super) shown_analysis_name: RefCell<Option<String>>,
+        pub(super) simulation_state: Cell<SimulationState>,
+        pub(super) simulation_progress: Cell<f64>,
+        /// Resolves the running simulation once ngspice's background thread
+        /// stops.
+        pub(super) background_thread_stopped_tx: RefCell<Option<oneshot::Sender<()>>>,
     }
 
     #[glib::object_subclass]
@@ -98,6 +172,27 @@ mod imp {
                 }
             });
 
+            klass.install_action_async("win.halt-simulation", None, |obj, _, _| async move {
+                if let Err(err) = obj.halt_simulation().await {
+                    tracing::error!("Failed to halt simulation: {:?}", err);
+                    obj.add_message_toast(&gettext("Failed to halt simulation"));
+                }
+            });
+
+            klass.install_action_async("win.resume-simulation", None, |obj, _, _| async move {
+                if let Err(err) = obj.resume_simulation().await {
+                    tracing::error!("Failed to resume simulation: {:?}", err);
+                    obj.add_message_toast(&gettext("Failed to resume simulation"));
+                }
+            });
+
+            klass.install_action_async("win.cancel-simulation", None, |obj, _, _| async move {
+                if let Err(err) = obj.cancel_simulation().await {
+                    tracing::error!("Failed to cancel simulation: {:?}", err);
+                    obj.add_message_toast(&gettext("Failed to cancel simulation"));
+                }
+            });
+
             klass.install_action_async("win.new-circuit", None, |obj, _, _| async move {
                 if obj.handle_unsaved_changes(&obj.circuit()).await.is_err() {
                     return;
@@ -122,6 +217,79 @@ mod imp {
                 }
             });
 
+            klass.install_action_async("win.open-rawfile", None, |obj, _, _| async move {
+                if let Err(err) = obj.open_rawfile().await {
+                    if !err
+                        .downcast_ref::<glib::Error>()
+                        .is_some_and(|error| error.matches(gtk::DialogError::Dismissed))
+                    {
+                        tracing::error!("Failed to open rawfile: {:?}", err);
+                        obj.add_message_toast(&gettext("Failed to open rawfile"));
+                    }
+                }
+            });
+
+            klass.install_action_async(
+                "win.save-rawfile",
+                Some("s"),
+                |obj, _, format| async move {
+                    let format = match format.and_then(|format| format.get::<String>()).as_deref() {
+                        Some("ascii") => rawfile::Format::Ascii,
+                        Some("binary") => rawfile::Format::Binary,
+                        _ => unreachable!(),
+                    };
+
+                    if let Err(err) = obj.save_rawfile(format).await {
+                        if !err
+                            .downcast_ref::<glib::Error>()
+                            .is_some_and(|error| error.matches(gtk::DialogError::Dismissed))
+                        {
+                            tracing::error!("Failed to save rawfile: {:?}", err);
+                            obj.add_message_toast(&gettext("Failed to save rawfile"));
+                        }
+                    }
+                },
+            );
+
+            klass.install_action_async("win.export-data", Some("s"), |obj, _, format| async move {
+                let format = format
+                    .and_then(|format| format.get::<String>())
+                    .and_then(|format| DataFormat::from_nick(&format))
+                    .unwrap();
+
+                if let Err(err) = obj.export_data(format).await {
+                    if !err
+                        .downcast_ref::<glib::Error>()
+                        .is_some_and(|error| error.matches(gtk::DialogError::Dismissed))
+                    {
+                        tracing::error!("Failed to export data: {:?}", err);
+                        obj.add_message_toast(&gettext("Failed to export data"));
+                    }
+                }
+            });
+
+            klass.install_action_async(
+                "win.export-image",
+                Some("s"),
+                |obj, _, format| async move {
+                    let format = format
+                        .and_then(|format| format.get::<String>())
+                        .and_then(|format| ImageFormat::from_nick(&format))
+                        .unwrap();
+
+                    if let Err(err) = obj.export_image(format).await {
+                        if !err.is::<Cancelled>()
+                            && !err
+                                .downcast_ref::<glib::Error>()
+                                .is_some_and(|error| error.matches(gtk::DialogError::Dismissed))
+                        {
+                            tracing::error!("Failed to export image: {:?}", err);
+                            obj.add_message_toast(&gettext("Failed to export image"));
+                        }
+                    }
+                },
+            );
+
             klass.install_action_async("win.save-circuit", None, |obj, _, _| async move {
                 if let Err(err) = obj.save_circuit(&obj.circuit()).await {
                     if !err
@@ -179,25 +347,13 @@ mod imp {
                 })
                 .sync_create()
                 .build();
-            self.circuit_binding_group
-                .bind("busy-progress", &*self.progress_bar, "fraction")
-                .sync_create()
-                .build();
-            self.circuit_binding_group
-                .bind("busy-progress", &*self.progress_bar, "visible")
-                .transform_to(|_, value| {
-                    let busy_progress = value.get::<f64>().unwrap();
-                    let visible = busy_progress != 1.0;
-                    Some(visible.into())
-                })
-                .sync_create()
-                .build();
 
             let circuit_signal_group = glib::SignalGroup::new::<Circuit>();
             circuit_signal_group.connect_notify_local(
                 Some("busy-progress"),
                 clone!(@weak obj => move |_, _| {
                     obj.update_save_actions();
+                    obj.update_progress_bar();
                 }),
             );
             self.circuit_signal_group.set(circuit_signal_group).unwrap();
@@ -205,6 +361,15 @@ mod imp {
             self.plots_dropdown.bind_plots(&self.plots);
             self.plots_dropdown
                 .connect_plot_activated(clone!(@weak obj => move |_, plot| {
+                    if let Some(vectors) = plot.imported_vectors() {
+                        let content = PlotContent::from_vectors(
+                            vectors.to_vec(),
+                            rawfile::scale_index(vectors),
+                        );
+                        obj.show_plot_content(&plot.name(), plot.analysis_name(), content);
+                        return;
+                    }
+
                     let command = format!("showplot {}", plot.name());
                     glib::spawn_future_local(async move {
                         if let Err(err) = obj.run_command(&command).await {
@@ -241,32 +406,50 @@ mod imp {
                     };
                     imp.end_stack.set_visible_child_name("output");
                 }),
-                clone!(@weak obj => move |_, _, _| {
-                    obj.close();
+                clone!(@weak obj => move |progress| {
+                    obj.imp().simulation_progress.set(progress);
+                    obj.update_progress_bar();
+                }),
+                clone!(@weak obj => move |status, _, _| {
+                    glib::spawn_future_local(async move {
+                        obj.handle_ngspice_controlled_exit(status).await;
+                    });
+                }),
+                clone!(@weak obj => move |init_data| {
+                    obj.handle_ngspice_init_data(init_data);
+                }),
+                clone!(@weak obj => move |values| {
+                    obj.handle_ngspice_data(values);
+                }),
+                clone!(@weak obj => move |is_running| {
+                    obj.handle_ngspice_background_thread_running(is_running);
                 }),
             );
-            match NgSpice::new(ngspice_cb) {
-                Ok(ngspice) => {
-                    self.ngspice.set(ngspice).unwrap();
-
-                    glib::spawn_future_local(clone!(@weak obj => async move {
-                        let imp = obj.imp();
-                        let ngspice = imp.ngspice.get().unwrap();
-                        if let Err(err) = imp.plots.update(ngspice).await {
-                            tracing::error!("Failed to update plots: {:?}", err);
-                        }
-                    }));
-                }
-                Err(err) => {
+            self.ngspice.set(NgSpice::new(ngspice_cb)).unwrap();
+
+            let settings = gio::Settings::new(APP_ID);
+            obj.add_action(&settings.create_action("ngspice-mode"));
+            settings.connect_changed(
+                Some("ngspice-mode"),
+                clone!(@weak obj => move |settings, _| {
+                    let mode = ngspice_mode(settings);
+                    glib::spawn_future_local(async move {
+                        obj.switch_ngspice_mode(mode).await;
+                    });
+                }),
+            );
+            let mode = ngspice_mode(&settings);
+            glib::spawn_future_local(clone!(@weak obj => async move {
+                if let Err(err) = obj.init_ngspice(mode).await {
                     tracing::error!("Failed to initialize ngspice: {:?}", err);
                     obj.add_message_toast(&gettext("Can't initialize Ngspice"));
                 }
-            }
+            }));
 
             obj.load_window_size();
 
             obj.set_circuit(&Circuit::draft());
-            obj.update_run_command_action();
+            obj.update_simulation_actions();
         }
 
         fn dispose(&self) {
@@ -324,6 +507,7 @@ impl Window {
         circuit_signal_group.set_target(Some(circuit));
 
         self.update_save_actions();
+        self.update_progress_bar();
     }
 
     fn circuit(&self) -> Circuit {
@@ -339,54 +523,164 @@ impl Window {
         let imp = self.imp();
 
         let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
-        let vector_names = ngspice.all_vector_names(plot_name).await?;
-
-        if vector_names.iter().any(|name| name == "time") {
-            let mut time_vector = Vec::new();
-            let mut other_vectors = Vec::new();
-            for vector_name in vector_names {
-                let vector_info = ngspice.vector_info(&vector_name).await?;
-                let real = match &vector_info.data {
-                    ComplexSlice::Real(real) => real,
-                    ComplexSlice::Complex(_) => bail!("Data contains complex"),
-                };
-                if vector_name == "time" {
-                    time_vector.extend_from_slice(real);
-                } else {
-                    other_vectors.push((vector_name, real.to_vec()));
-                }
-            }
+        let content = PlotContent::fetch(ngspice, plot_name).await?;
+        let analysis_name = ngspice.analysis_name(plot_name).await?;
 
-            imp.plot_view.set_vectors(time_vector, other_vectors)?;
-            imp.output_view.appendln("Shown on plot view");
-            imp.end_stack.set_visible_child_name("plot");
-        } else {
-            let mut text = String::new();
-            for vector_name in vector_names {
-                let vector_info = ngspice.vector_info(&vector_name).await?;
-                match vector_info.data {
-                    ComplexSlice::Real(real) => {
-                        debug_assert_eq!(real.len(), 1);
-
-                        writeln!(text, "{}: {}", vector_name, real[0]).unwrap();
-                    }
-                    ComplexSlice::Complex(complex) => {
-                        debug_assert_eq!(complex.len(), 1);
-
-                        writeln!(
-                            text,
-                            "{}: {} + {}j",
-                            vector_name, complex[0].cx_real, complex[0].cx_imag
-                        )
-                        .unwrap();
-                    }
-                }
+        self.show_plot_content(plot_name, analysis_name.as_deref(), content);
+
+        Ok(())
+    }
+
+    fn show_plot_content(
+        &self,
+        plot_name: &str,
+        analysis_name: Option<&str>,
+        content: PlotContent,
+    ) {
+        let imp = self.imp();
+
+        match content {
+            PlotContent::Traces { scale, vectors } => {
+                imp.plot_view.set_vectors(plot_name, scale, vectors);
+                imp.shown_plot_name.replace(plot_name.to_string());
+                imp.shown_analysis_name
+                    .replace(analysis_name.map(String::from));
+                imp.output_view.appendln("Shown on plot view");
+                imp.end_stack.set_visible_child_name("plot");
+            }
+            PlotContent::Scalars(vectors) => {
+                imp.output_view.append(&backend::format_scalars(&vectors));
+                imp.end_stack.set_visible_child_name("output");
             }
+        }
+    }
+
+    fn handle_ngspice_init_data(&self, init_data: InitData) {
+        let imp = self.imp();
+
+        let Some(scale_index) = init_data.scale_index else {
+            imp.live_plot.replace(None);
+            return;
+        };
+
+        let mut vectors = init_data
+            .vectors
+            .into_iter()
+            .map(|init_vector| Vector {
+                vtype: VectorType::for_scale_name(&init_vector.name),
+                data: VectorData::empty(init_vector.is_real),
+                name: init_vector.name,
+            })
+            .collect::<Vec<_>>();
+        let scale_vector = vectors.remove(scale_index);
+
+        imp.plot_view
+            .set_vectors(&init_data.plot_name, scale_vector, vectors);
+        imp.shown_plot_name.replace(init_data.plot_name.clone());
+        imp.shown_analysis_name
+            .replace(Some(init_data.analysis_name.clone()));
+        imp.end_stack.set_visible_child_name("plot");
+
+        imp.live_plot.replace(Some(LivePlot {
+            name: init_data.plot_name,
+            scale_index,
+        }));
+    }
+
+    fn handle_ngspice_data(&self, mut values: Vec<Complex>) {
+        let imp = self.imp();
+
+        let Some(scale_index) = imp.live_plot.borrow().as_ref().map(|plot| plot.scale_index) else {
+            return;
+        };
+
+        if scale_index >= values.len() {
+            return;
+        }
+
+        let scale_value = values.remove(scale_index);
+        imp.plot_view.push_samples(scale_value.re, &values);
+    }
+
+    fn handle_ngspice_background_thread_running(&self, is_running: bool) {
+        if is_running {
+            return;
+        }
+
+        if let Some(stopped_tx) = self.imp().background_thread_stopped_tx.take() {
+            let _ = stopped_tx.send(());
+        }
+    }
+
+    /// Restarts ngspice after it exited, e.g., because of a `quit` command or
+    /// an internal error, so that the window can keep being used.
+    async fn handle_ngspice_controlled_exit(&self, status: i32) {
+        let imp = self.imp();
+
+        tracing::warn!("Ngspice exited with status {}", status);
+
+        let ngspice = imp.ngspice.get().unwrap();
+        if let Err(err) = self.init_ngspice(ngspice.mode()).await {
+            tracing::error!("Failed to reinitialize ngspice: {:?}", err);
+            self.add_message_toast(&gettext("Ngspice exited and can't be restarted"));
+            return;
+        }
 
-            imp.output_view.append(&text);
-            imp.end_stack.set_visible_child_name("output");
+        imp.plot_view.clear();
+        self.add_message_toast(&gettext("Ngspice exited and was restarted"));
+    }
+
+    async fn switch_ngspice_mode(&self, mode: ngspice::Mode) {
+        let imp = self.imp();
+
+        let ngspice = imp.ngspice.get().unwrap();
+        if ngspice.mode() == mode {
+            return;
+        }
+
+        imp.plot_view.clear();
+
+        if let Err(err) = self.init_ngspice(mode).await {
+            tracing::error!("Failed to initialize ngspice: {:?}", err);
+            self.add_message_toast(&gettext("Can't initialize Ngspice"));
+        }
+    }
+
+    /// Starts ngspice in `mode`, discarding the state of the previous
+    /// instance, if any.
+    async fn init_ngspice(&self, mode: ngspice::Mode) -> Result<()> {
+        let imp = self.imp();
+
+        imp.live_plot.replace(None);
+        imp.background_thread_stopped_tx.replace(None);
+        self.set_simulation_state(SimulationState::Idle);
+
+        let ngspice = imp.ngspice.get().unwrap();
+        ngspice.init(mode).await?;
+
+        if let Err(err) = imp.plots.update(ngspice).await {
+            tracing::error!("Failed to update plots: {:?}", err);
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the live plot, if any, with the final results of the
+    /// simulation that filled it in.
+    async fn finish_live_plot(&self) -> Result<()> {
+        let imp = self.imp();
+
+        // The simulation may still be resumed and continue filling it in.
+        if imp.simulation_state.get() == SimulationState::Halted {
+            return Ok(());
         }
 
+        let Some(live_plot) = imp.live_plot.take() else {
+            return Ok(());
+        };
+
+        self.show_plot(&live_plot.name).await?;
+
         Ok(())
     }
 
@@ -473,7 +767,11 @@ impl Window {
         imp.end_stack.set_visible_child_name("output");
 
         let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
-        ngspice.circuit(circuit_text.lines()).await?;
+        ngspice
+            .circuit(circuit_text.lines().map(String::from).collect())
+            .await?;
+
+        self.finish_live_plot().await?;
 
         imp.plots.update(ngspice).await?;
 
@@ -488,27 +786,109 @@ impl Window {
 
         let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
 
-        match command.split_whitespace().collect::<Vec<_>>().as_slice() {
-            ["source"] => {
+        match route_command(ngspice, command).await? {
+            RoutedCommand::Source => {
                 let circuit = self.circuit();
                 let circuit_text = circuit.text(&circuit.start_iter(), &circuit.end_iter(), true);
-                ngspice.circuit(circuit_text.lines()).await?;
-            }
-            ["showplot"] => {
-                let current_plot_name = ngspice.current_plot_name().await?;
-                self.show_plot(&current_plot_name).await?;
+                ngspice
+                    .circuit(circuit_text.lines().map(String::from).collect())
+                    .await?;
             }
-            ["showplot", plot_name] => {
-                self.show_plot(plot_name).await?;
+            RoutedCommand::ShowPlot(plot_name) => {
+                self.show_plot(&plot_name).await?;
             }
-            ["clear"] => {
+            RoutedCommand::Clear => {
                 imp.output_view.clear();
                 imp.plot_view.clear();
             }
-            _ => {
-                ngspice.command(command).await?;
+            RoutedCommand::Simulation => {
+                self.run_simulation(command).await?;
             }
+            RoutedCommand::Done => {}
+        }
+
+        self.finish_live_plot().await?;
+
+        imp.plots.update(ngspice).await?;
+
+        Ok(())
+    }
+
+    /// Runs `command` in ngspice's background thread and waits until it
+    /// finishes or is halted.
+    async fn run_simulation(&self, command: &str) -> Result<()> {
+        let imp = self.imp();
+
+        let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
+
+        imp.simulation_progress.set(0.0);
+        // Set up before the command is started, so that a background thread
+        // that stops right away isn't missed.
+        let (stopped_tx, stopped_rx) = oneshot::channel();
+        imp.background_thread_stopped_tx.replace(Some(stopped_tx));
+        if let Err(err) = ngspice.background_command(command).await {
+            imp.background_thread_stopped_tx.replace(None);
+            return Err(err);
+        }
+        self.set_simulation_state(SimulationState::Running);
+
+        // This is also canceled if ngspice is restarted in the meantime.
+        let _ = stopped_rx.await;
+
+        if imp.simulation_state.get() == SimulationState::Running {
+            self.set_simulation_state(SimulationState::Idle);
+        }
+
+        Ok(())
+    }
+
+    async fn halt_simulation(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
+
+        // This must be set before the background thread stops, so that
+        // `run_simulation` does not consider the simulation finished.
+        self.set_simulation_state(SimulationState::Halted);
+
+        if let Err(err) = ngspice.halt().await {
+            self.set_simulation_state(SimulationState::Running);
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    async fn resume_simulation(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
+
+        self.run_simulation("resume").await?;
+
+        self.finish_live_plot().await?;
+
+        imp.plots.update(ngspice).await?;
+
+        Ok(())
+    }
+
+    /// Stops the simulation and throws away its intermediate state, keeping
+    /// whatever data it has produced so far.
+    async fn cancel_simulation(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let ngspice = imp.ngspice.get().context("Ngspice was not initialized")?;
+
+        let was_running = imp.simulation_state.get() == SimulationState::Running;
+
+        imp.live_plot.replace(None);
+        self.set_simulation_state(SimulationState::Idle);
+
+        if was_running {
+            ngspice.halt().await?;
         }
+        ngspice.command("reset").await?;
 
         imp.plots.update(ngspice).await?;
 
@@ -538,6 +918,245 @@ impl Window {
         Ok(())
     }
 
+    /// Adds the plots of a rawfile to the plot list, and shows the first
+    /// one with a scale.
+    async fn open_rawfile(&self) -> Result<()> {
+        let imp = self.imp();
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&rawfile_file_filter());
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Open Rawfile"))
+            .filters(&filters)
+            .modal(true)
+            .build();
+        let file = dialog.open_future(Some(self)).await?;
+
+        let (bytes, _) = file.load_contents_future().await?;
+        let raw_plots = gio::spawn_blocking(move || rawfile::parse(&bytes))
+            .await
+            .unwrap()?;
+
+        let file_name = file
+            .basename()
+            .map(|path| path.display().to_string())
+            .unwrap_or_default();
+        let plots = raw_plots
+            .into_iter()
+            .map(|raw_plot| {
+                let name = format!("{} ({})", raw_plot.name, file_name);
+                Plot::imported(&name, &raw_plot.name, raw_plot.vectors)
+            })
+            .collect::<Vec<_>>();
+
+        imp.output_view
+            .appendln(&format!("Opened {} plots from {}", plots.len(), file_name));
+
+        let first_plot = plots.iter().find(|plot| {
+            plot.imported_vectors()
+                .is_some_and(|vectors| rawfile::scale_index(vectors).is_some())
+        });
+        if let Some(plot) = first_plot {
+            let vectors = plot.imported_vectors().unwrap();
+            let content =
+                PlotContent::from_vectors(vectors.to_vec(), rawfile::scale_index(vectors));
+            self.show_plot_content(&plot.name(), plot.analysis_name(), content);
+        }
+
+        imp.plots.add_imported(plots);
+
+        Ok(())
+    }
+
+    /// Saves the vectors of the plot shown in the plot view to a rawfile,
+    /// leaving out the ones added on top of it.
+    async fn save_rawfile(&self, format: rawfile::Format) -> Result<()> {
+        let imp = self.imp();
+
+        let vectors = imp.plot_view.plot_vectors();
+        ensure!(!vectors.is_empty(), "No plot is shown");
+
+        let plot_name = imp.shown_plot_name.borrow().clone();
+        // Readers tell analyses apart by this name, so the plot name is only
+        // a fallback.
+        let analysis_name = imp
+            .shown_analysis_name
+            .borrow()
+            .clone()
+            .unwrap_or_else(|| plot_name.clone());
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&rawfile_file_filter());
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Save Plot as Rawfile"))
+            .filters(&filters)
+            .modal(true)
+            .initial_name(format!("{}.raw", plot_name))
+            .build();
+        let file = dialog.save_future(Some(self)).await?;
+
+        let raw_plot = rawfile::RawPlot {
+            title: self.circuit().title(),
+            date: glib::DateTime::now_local()?
+                .format("%a %b %e %H:%M:%S %Y")?
+                .to_string(),
+            name: analysis_name,
+            vectors,
+        };
+        let bytes = gio::spawn_blocking(move || rawfile::write(&[raw_plot], format))
+            .await
+            .unwrap()?;
+
+        file.replace_contents_future(
+            bytes,
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+        )
+        .await
+        .map_err(|(_, err)| err)?;
+
+        Ok(())
+    }
+
+    /// Saves the vectors shown in the plot view in a format other programs
+    /// can read.
+    async fn export_data(&self, format: DataFormat) -> Result<()> {
+        let imp = self.imp();
+
+        let text = imp
+            .plot_view
+            .export_data(format)
+            .await
+            .context("No plot is shown")?;
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&data_file_filter(format));
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Data"))
+            .filters(&filters)
+            .modal(true)
+            .initial_name(format!(
+                "{}.{}",
+                imp.shown_plot_name.borrow(),
+                format.extension()
+            ))
+            .build();
+        let file = dialog.save_future(Some(self)).await?;
+
+        file.replace_contents_future(text, None, false, gio::FileCreateFlags::REPLACE_DESTINATION)
+            .await
+            .map_err(|(_, err)| err)?;
+
+        Ok(())
+    }
+
+    /// Renders the chart shown in the plot view to an image file, at a size
+    /// and resolution chosen by the user.
+    async fn export_image(&self, format: ImageFormat) -> Result<()> {
+        const CANCEL_RESPONSE_ID: &str = "cancel";
+        const EXPORT_RESPONSE_ID: &str = "export";
+
+        let imp = self.imp();
+
+        ensure!(imp.plot_view.has_vectors(), "No plot is shown");
+
+        let (width, height) = imp.plot_view.chart_size();
+        let width_row = adw::SpinRow::builder()
+            .title(gettext("Width"))
+            .subtitle(gettext("Pixels"))
+            .adjustment(&gtk::Adjustment::new(
+                width as f64,
+                1.0,
+                10_000.0,
+                1.0,
+                10.0,
+                0.0,
+            ))
+            .build();
+        let height_row = adw::SpinRow::builder()
+            .title(gettext("Height"))
+            .subtitle(gettext("Pixels"))
+            .adjustment(&gtk::Adjustment::new(
+                height as f64,
+                1.0,
+                10_000.0,
+                1.0,
+                10.0,
+                0.0,
+            ))
+            .build();
+        let dpi_row = adw::SpinRow::builder()
+            .title(gettext("Resolution"))
+            .subtitle(gettext("Dots per inch"))
+            .adjustment(&gtk::Adjustment::new(96.0, 24.0, 1200.0, 1.0, 10.0, 0.0))
+            // Vector formats are sized in points instead.
+            .visible(format == ImageFormat::Png)
+            .build();
+
+        let group = adw::PreferencesGroup::new();
+        group.add(&width_row);
+        group.add(&height_row);
+        group.add(&dpi_row);
+
+        let dialog = adw::MessageDialog::builder()
+            .modal(true)
+            .transient_for(self)
+            .heading(gettext("Export Image"))
+            .extra_child(&group)
+            .close_response(CANCEL_RESPONSE_ID)
+            .default_response(EXPORT_RESPONSE_ID)
+            .build();
+
+        dialog.add_response(CANCEL_RESPONSE_ID, &gettext("Cancel"));
+
+        dialog.add_response(EXPORT_RESPONSE_ID, &gettext("Export…"));
+        dialog.set_response_appearance(EXPORT_RESPONSE_ID, adw::ResponseAppearance::Suggested);
+
+        if dialog.choose_future().await != EXPORT_RESPONSE_ID {
+            return Err(Cancelled.into());
+        }
+
+        let filters = gio::ListStore::new::<gtk::FileFilter>();
+        filters.append(&image_file_filter(format));
+
+        let dialog = gtk::FileDialog::builder()
+            .title(gettext("Export Image"))
+            .filters(&filters)
+            .modal(true)
+            .initial_name(format!(
+                "{}.{}",
+                imp.shown_plot_name.borrow(),
+                format.extension()
+            ))
+            .build();
+        let file = dialog.save_future(Some(self)).await?;
+
+        let bytes = imp
+            .plot_view
+            .export_image(
+                format,
+                width_row.value() as u32,
+                height_row.value() as u32,
+                dpi_row.value(),
+            )
+            .await?;
+
+        file.replace_contents_future(
+            bytes,
+            None,
+            false,
+            gio::FileCreateFlags::REPLACE_DESTINATION,
+        )
+        .await
+        .map_err(|(_, err)| err)?;
+
+        Ok(())
+    }
+
     async fn save_circuit(&self, circuit: &Circuit) -> Result<()> {
         if circuit.file().is_some() {
             circuit.save().await?;
@@ -601,9 +1220,58 @@ impl Window {
         }
     }
 
+    fn set_simulation_state(&self, simulation_state: SimulationState) {
+        self.imp().simulation_state.set(simulation_state);
+
+        self.update_simulation_actions();
+        self.update_progress_bar();
+    }
+
     fn update_run_command_action(&self) {
-        let is_command_empty = self.imp().command_entry.text().is_empty();
-        self.action_set_enabled("win.run-command", !is_command_empty);
+        let imp = self.imp();
+
+        // ngspice only accepts a few commands while its background thread runs.
+        let is_simulation_running = imp.simulation_state.get() == SimulationState::Running;
+        let is_command_empty = imp.command_entry.text().is_empty();
+        self.action_set_enabled(
+            "win.run-command",
+            !is_command_empty && !is_simulation_running,
+        );
+    }
+
+    fn update_simulation_actions(&self) {
+        let imp = self.imp();
+
+        let simulation_state = imp.simulation_state.get();
+        let is_running = simulation_state == SimulationState::Running;
+        let is_halted = simulation_state == SimulationState::Halted;
+
+        self.action_set_enabled("win.halt-simulation", is_running);
+        self.action_set_enabled("win.resume-simulation", is_halted);
+        self.action_set_enabled("win.cancel-simulation", is_running || is_halted);
+        self.action_set_enabled("win.load-circuit", !is_running);
+
+        imp.halt_button.set_visible(is_running);
+        imp.resume_button.set_visible(is_halted);
+        imp.cancel_button.set_visible(is_running || is_halted);
+
+        self.update_run_command_action();
+    }
+
+    fn update_progress_bar(&self) {
+        let imp = self.imp();
+
+        let circuit = self.circuit();
+        let progress = if circuit.is_busy() {
+            Some(circuit.busy_progress())
+        } else if imp.simulation_state.get() != SimulationState::Idle {
+            Some(imp.simulation_progress.get())
+        } else {
+            None
+        };
+
+        imp.progress_bar.set_visible(progress.is_some());
+        imp.progress_bar.set_fraction(progress.unwrap_or(1.0));
     }
 
     fn update_save_actions(&self) {
@@ -613,6 +1281,37 @@ impl Window {
     }
 }
 
+fn rawfile_file_filter() -> gtk::FileFilter {
+    let filter = gtk::FileFilter::new();
+    filter.set_name(Some(&gettext("SPICE Rawfiles")));
+    filter.add_suffix("raw");
+    filter
+}
+
+fn data_file_filter(format: DataFormat) -> gtk::FileFilter {
+    let filter = gtk::FileFilter::new();
+    let name = match format {
+        DataFormat::Csv => gettext("CSV Files"),
+        DataFormat::Tsv => gettext("TSV Files"),
+        DataFormat::Json => gettext("JSON Files"),
+    };
+    filter.set_name(Some(&name));
+    filter.add_suffix(format.extension());
+    filter
+}
+
+fn image_file_filter(format: ImageFormat) -> gtk::FileFilter {
+    let filter = gtk::FileFilter::new();
+    let name = match format {
+        ImageFormat::Png => gettext("PNG Images"),
+        ImageFormat::Svg => gettext("SVG Images"),
+        ImageFormat::Pdf => gettext("PDF Documents"),
+    };
+    filter.set_name(Some(&name));
+    filter.add_suffix(format.extension());
+    filter
+}
+
 fn netlist_file_filter() -> gtk::FileFilter {
     let filter = gtk::FileFilter::new();
     filter.set_name(Some(&gettext("SPICE Netlist Files")));
@@ -623,3 +1322,39 @@ fn netlist_file_filter() -> gtk::FileFilter {
 
     filter
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::mock::MockBackend;
+
+    #[test]
+    fn route_commands() {
+        let backend = MockBackend::new();
+        backend.add_plot("tran1", Vec::new());
+        // Only the commands the app doesn't handle may reach the backend.
+        backend.expect_command("print v(out)", |_| Ok(()));
+
+        let main_context = glib::MainContext::new();
+        let route = |command| {
+            main_context
+                .block_on(route_command(&backend, command))
+                .unwrap()
+        };
+
+        assert_eq!(route("source"), RoutedCommand::Source);
+        assert_eq!(
+            route("showplot"),
+            RoutedCommand::ShowPlot("tran1".to_string())
+        );
+        assert_eq!(
+            route("showplot const"),
+            RoutedCommand::ShowPlot("const".to_string())
+        );
+        assert_eq!(route(" clear "), RoutedCommand::Clear);
+        assert_eq!(route("tran 1u 1m"), RoutedCommand::Simulation);
+        assert_eq!(route("resume"), RoutedCommand::Simulation);
+        assert_eq!(route("print v(out)"), RoutedCommand::Done);
+        assert!(backend.is_script_done());
+    }
+}